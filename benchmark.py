@@ -347,6 +347,30 @@ filter_rust_counties = (a != b) & (
 )
 
 
+def filter_callable_counties(a, b):
+    return a is not b and int(a.id) // 1000 == int(b.id) // 1000
+
+
+def benchmark_filter(name, filename, key, baseline_filter, filt, compiled=True):
+    topology = topojson.read(filename)
+    obj = topology.objects[key]
+
+    start = perf_counter()
+    expected = topojson.mesh(topology, obj, filter=baseline_filter, compiled=compiled)
+    end = perf_counter()
+    t1 = (end - start) * 1_000
+
+    start = perf_counter()
+    actual = topojson.mesh(topology, obj, filter=filt)
+    end = perf_counter()
+    t2 = (end - start) * 1_000
+
+    is_same = actual.coordinates == expected.coordinates
+    print(
+        f"| {name.title():>21} | {t1 / t2:>6.3f} | {t1:>6.3f} ms | {t2:>6.3f} ms | {str(is_same):>7} |"
+    )
+
+
 def merge_python(key):
     def wrapper(topology):
         objects = topology["objects"][key]["geometries"]
@@ -575,3 +599,45 @@ benchmark(
     quantize_python(),
     quantize_rust(),
 )
+
+print()
+print("|                       |     GeoVar plan performances   |         |")
+print("|                       | ------------------------------ |         |")
+print("|      Function + Data  |  ratio |  baseline |      plan |  Same ? |")
+print("| --------------------- | ------------------------------ | ------- |")
+
+# Baseline: the same GeoVar filter, interpreted on each arc.
+benchmark_filter(
+    "mesh states (interp)",
+    "./states-10m.json",
+    "states",
+    filter_rust_states,
+    filter_rust_states,
+    compiled=False,
+)
+
+benchmark_filter(
+    "mesh counties (interp)",
+    "./counties-10m.json",
+    "counties",
+    filter_rust_counties,
+    filter_rust_counties,
+    compiled=False,
+)
+
+# Baseline: an equivalent Python callable.
+benchmark_filter(
+    "mesh states (callable)",
+    "./states-10m.json",
+    "states",
+    lambda a, b: a is not b,
+    filter_rust_states,
+)
+
+benchmark_filter(
+    "mesh counties (callable)",
+    "./counties-10m.json",
+    "counties",
+    filter_callable_counties,
+    filter_rust_counties,
+)
@@ -0,0 +1,205 @@
+use indexmap::IndexMap;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::neighbors::{Mode, WeightedNeighbors, Weights, wrap_weighted_neighbors};
+use crate::topojsons::{Geometry, TopoJSON};
+
+/// Output format of `adjacency`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Csr,
+    Edges,
+    Dict,
+}
+
+impl TryFrom<&str> for Format {
+    type Error = PyErr;
+
+    fn try_from(value: &str) -> PyResult<Self> {
+        match value {
+            "csr" => Ok(Self::Csr),
+            "edges" => Ok(Self::Edges),
+            "dict" => Ok(Self::Dict),
+            _ => Err(PyValueError::new_err(format!(
+                "Unknown format {:?}, expected 'csr', 'edges' or 'dict'",
+                value
+            ))),
+        }
+    }
+}
+
+/// Identifier of a geometry: its id or, without id, its index.
+#[derive(IntoPyObject, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Id {
+    Id(String),
+    Index(usize),
+}
+
+/// Adjacency weighted by the length of the shared borders: a CSR matrix
+/// `(indptr, indices, data)`, a list of edges `(id_a, id_b, shared_length)`
+/// or a dictionary `{id_a: {id_b: shared_length}}`.
+#[derive(IntoPyObject, PartialEq, Debug)]
+pub enum Adjacency {
+    Csr((Vec<usize>, Vec<usize>, Vec<f64>)),
+    Edges(Vec<(Id, Id, f64)>),
+    Dict(IndexMap<Id, IndexMap<Id, f64>>),
+}
+
+pub fn wrap_adjacency(
+    topology: &TopoJSON,
+    objects: &[&Geometry],
+    format: Format,
+    mode: Mode,
+    spherical: bool,
+) -> Adjacency {
+    let WeightedNeighbors {
+        neighbors, lengths, ..
+    } = wrap_weighted_neighbors(topology, objects, mode, Weights::Length, spherical);
+    let ids: Vec<Id> = objects
+        .iter()
+        .enumerate()
+        .map(|(i, o)| o.id().map(Id::Id).unwrap_or(Id::Index(i)))
+        .collect();
+    let pairs = |i: usize| {
+        neighbors[i]
+            .iter()
+            .map(|&j| j as usize)
+            .zip(lengths[i].iter().copied())
+    };
+
+    match format {
+        Format::Csr => {
+            let mut indptr = vec![0];
+            let mut indices = Vec::new();
+            let mut data = Vec::new();
+            for i in 0..neighbors.len() {
+                for (j, length) in pairs(i) {
+                    indices.push(j);
+                    data.push(length);
+                }
+                indptr.push(indices.len());
+            }
+            Adjacency::Csr((indptr, indices, data))
+        }
+        // Each pair of neighbors is listed once, as `i < j`.
+        Format::Edges => Adjacency::Edges(
+            (0..neighbors.len())
+                .flat_map(|i| {
+                    pairs(i)
+                        .filter(move |&(j, _)| i < j)
+                        .map(|(j, length)| (ids[i].clone(), ids[j].clone(), length))
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+        ),
+        Format::Dict => Adjacency::Dict(
+            (0..neighbors.len())
+                .map(|i| {
+                    (
+                        ids[i].clone(),
+                        pairs(i)
+                            .map(|(j, length)| (ids[j].clone(), length))
+                            .collect(),
+                    )
+                })
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //
+    // +-----+-----+-----+
+    // |     |     |     |
+    // |  a  |  b  |  2  |
+    // |     |     |     |
+    // +-----+-----+-----+
+    //
+    fn simple_topology() -> (TopoJSON, Vec<Geometry>) {
+        let topology = TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: Default::default(),
+            arcs: vec![
+                vec![[1., 0.], [1., 1.]],
+                vec![[1., 1.], [0., 1.], [0., 0.], [1., 0.]],
+                vec![[2., 0.], [2., 1.]],
+                vec![[1., 1.], [2., 1.]],
+                vec![[2., 0.], [1., 0.]],
+                vec![[2., 1.], [3., 1.], [3., 0.], [2., 0.]],
+            ],
+        };
+        let objects = [
+            (vec![vec![1, 0]], Some("a")),
+            (vec![vec![-1, -5, 2, -4]], Some("b")),
+            (vec![vec![5, 2]], None),
+        ]
+        .map(|(arcs, id)| Geometry::Polygon {
+            arcs,
+            id: id.map(|id| id.to_string()),
+            properties: None,
+            bbox: None,
+        })
+        .into_iter()
+        .collect();
+        (topology, objects)
+    }
+
+    fn adjacency(format: Format) -> Adjacency {
+        let (topology, objects) = simple_topology();
+        wrap_adjacency(
+            &topology,
+            &objects.iter().collect::<Vec<_>>(),
+            format,
+            Mode::Rook,
+            false,
+        )
+    }
+
+    #[test]
+    fn test_adjacency_1() {
+        assert_eq!(
+            adjacency(Format::Csr),
+            Adjacency::Csr((vec![0, 1, 3, 4], vec![1, 0, 2, 1], vec![1., 1., 1., 1.]))
+        );
+    }
+
+    #[test]
+    fn test_adjacency_2() {
+        let a = Id::Id("a".to_string());
+        let b = Id::Id("b".to_string());
+        assert_eq!(
+            adjacency(Format::Edges),
+            Adjacency::Edges(vec![
+                (a.clone(), b.clone(), 1.),
+                (b.clone(), Id::Index(2), 1.)
+            ])
+        );
+        assert_eq!(
+            adjacency(Format::Dict),
+            Adjacency::Dict(IndexMap::from_iter([
+                (a.clone(), IndexMap::from_iter([(b.clone(), 1.)])),
+                (
+                    b.clone(),
+                    IndexMap::from_iter([(a.clone(), 1.), (Id::Index(2), 1.)])
+                ),
+                (Id::Index(2), IndexMap::from_iter([(b.clone(), 1.)])),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_adjacency_3() {
+        Python::initialize();
+        assert_eq!(
+            Format::try_from("coo").err().map(|e| e.to_string()),
+            Some(
+                "ValueError: Unknown format \"coo\", expected 'csr', 'edges' or 'dict'".to_string()
+            )
+        );
+    }
+}
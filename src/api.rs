@@ -1,14 +1,30 @@
-use crate::bbox::wrap_bbox;
+use crate::adjacency::{Adjacency, Format, Id, wrap_adjacency};
+use crate::bbox::{wrap_bbox, wrap_geometry_bboxes, wrap_object_bbox};
+use crate::borders::wrap_borders;
+use crate::color::{Method, color_properties, wrap_color};
+use crate::dissolve::{By, wrap_dissolve};
 use crate::feature::wrap_feature;
-use crate::geojsons::{FeatureGeometryType, GeoJSON};
+use crate::filter::{wrap_filter, wrap_filter_collection, wrap_filter_topology};
+use crate::geojsons::{FeatureCollection, FeatureGeometryType, GeoJSON};
+use crate::index::{SpatialIndex, wrap_index};
 use crate::lambda::GeoVar;
-use crate::merge::wrap_merge;
-use crate::mesh::wrap_mesh;
-use crate::neighbors::wrap_neighbors;
-use crate::quantize::wrap_quantize;
+use crate::measure::{Measure, wrap_measure};
+use crate::merge::{wrap_merge, wrap_merge_arcs};
+use crate::mesh::{Filter, wrap_mesh, wrap_mesh_arcs};
+use crate::nearest::wrap_nearest;
+use crate::neighbors::{
+    Mode, NeighborsOutput, Weights, wrap_neighbors, wrap_neighbors_between, wrap_neighbors_queen,
+    wrap_weighted_neighbors,
+};
+use crate::quantize::{
+    QuantizationReport, wrap_quantization_error, wrap_quantize, wrap_quantize_max_error,
+};
+use crate::simplify::wrap_simplify_dp;
 use crate::topojsons::{Geometry, TopoJSON, Transform};
-use pyo3::exceptions::{PyKeyError, PyOSError, PyRuntimeError, PyTypeError};
+use crate::unquantize::wrap_unquantize;
+use pyo3::exceptions::{PyKeyError, PyOSError, PyRuntimeError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
+use std::collections::HashMap;
 use std::fs;
 
 #[pyfunction]
@@ -17,32 +33,247 @@ pub fn feature(topology: &TopoJSON, o: &Geometry) -> GeoJSON {
 }
 
 #[pyfunction]
-pub fn merge(topology: &TopoJSON, objects: Vec<Geometry>) -> FeatureGeometryType {
-    wrap_merge(topology, objects.iter().collect::<Vec<_>>().as_slice())
+#[pyo3(signature = (topology, objects, spherical=false))]
+pub fn merge(topology: &TopoJSON, objects: Vec<Geometry>, spherical: bool) -> FeatureGeometryType {
+    wrap_merge(
+        topology,
+        objects.iter().collect::<Vec<_>>().as_slice(),
+        spherical,
+    )
 }
 
 #[pyfunction]
+#[pyo3(signature = (topology, objects, spherical=false))]
+pub fn merge_arcs(topology: &TopoJSON, objects: Vec<Geometry>, spherical: bool) -> Geometry {
+    wrap_merge_arcs(
+        topology,
+        objects.iter().collect::<Vec<_>>().as_slice(),
+        spherical,
+    )
+}
+
+#[pyfunction]
+#[pyo3(signature = (topology, objects, by, aggregate=None, spherical=false))]
+pub fn dissolve(
+    topology: &TopoJSON,
+    objects: Vec<Geometry>,
+    by: By,
+    aggregate: Option<HashMap<String, String>>,
+    spherical: bool,
+) -> PyResult<FeatureCollection> {
+    wrap_dissolve(
+        topology,
+        objects.iter().collect::<Vec<_>>().as_slice(),
+        &by,
+        &aggregate.unwrap_or_default(),
+        spherical,
+    )
+}
+
+#[pyfunction]
+pub fn filter(
+    topology: &TopoJSON,
+    objects: Vec<Geometry>,
+    predicate: PyRef<GeoVar>,
+) -> PyResult<Geometry> {
+    wrap_filter(
+        topology,
+        objects.iter().collect::<Vec<_>>().as_slice(),
+        &predicate,
+    )
+}
+
+#[pyfunction]
+#[pyo3(signature = (topology, object=None, filter=None, compiled=true))]
 pub fn mesh(
     topology: &TopoJSON,
     object: Option<Geometry>,
-    filter: Option<&GeoVar>,
+    filter: Option<Filter>,
+    compiled: bool,
 ) -> PyResult<FeatureGeometryType> {
-    wrap_mesh(topology, object.as_ref(), filter)
+    wrap_mesh(topology, object.as_ref(), filter.as_ref(), compiled)
+}
+
+#[pyfunction]
+pub fn mesh_arcs(
+    topology: &TopoJSON,
+    object: Option<Geometry>,
+    filter: Option<Filter>,
+) -> PyResult<Geometry> {
+    wrap_mesh_arcs(topology, object.as_ref(), filter.as_ref(), true)
+}
+
+#[pyfunction]
+#[pyo3(signature = (topology, object, spherical=false))]
+pub fn borders(topology: &TopoJSON, object: Geometry, spherical: bool) -> FeatureCollection {
+    wrap_borders(topology, &object, spherical)
+}
+
+#[pyfunction]
+#[pyo3(signature = (topology, spherical=false))]
+pub fn bbox(topology: &TopoJSON, spherical: bool) -> [f64; 4] {
+    wrap_bbox(topology, spherical)
+}
+
+#[pyfunction]
+#[pyo3(signature = (topology, object, spherical=false))]
+pub fn object_bbox(topology: &TopoJSON, object: Geometry, spherical: bool) -> [f64; 4] {
+    wrap_object_bbox(topology, &object, spherical)
+}
+
+#[pyfunction]
+#[pyo3(signature = (topology, object, spherical=false))]
+pub fn compute_geometry_bboxes(
+    topology: &TopoJSON,
+    mut object: Geometry,
+    spherical: bool,
+) -> Geometry {
+    wrap_geometry_bboxes(topology, &mut object, spherical);
+    object
+}
+
+#[pyfunction]
+pub fn index(topology: &TopoJSON, object: Geometry) -> SpatialIndex {
+    wrap_index(topology, &object)
+}
+
+#[pyfunction]
+#[pyo3(signature = (topology, object, spherical=false))]
+pub fn measure(topology: &TopoJSON, object: Geometry, spherical: bool) -> Vec<Measure> {
+    wrap_measure(topology, &object, spherical)
 }
 
 #[pyfunction]
-pub fn bbox(topology: &TopoJSON) -> [f64; 4] {
-    wrap_bbox(topology)
+#[pyo3(signature = (topology, object, x, y, k=1, spherical=false))]
+pub fn nearest(
+    topology: &TopoJSON,
+    object: Geometry,
+    x: f64,
+    y: f64,
+    k: usize,
+    spherical: bool,
+) -> PyResult<Vec<(usize, Option<String>, f64)>> {
+    wrap_nearest(topology, &object, [x, y], k, spherical)
 }
 
 #[pyfunction]
-pub fn neighbors(objects: Vec<Geometry>) -> Vec<Vec<i32>> {
-    wrap_neighbors(objects.iter().collect::<Vec<_>>().as_slice())
+#[pyo3(signature = (objects, mode="rook", topology=None, weights=None, spherical=false))]
+pub fn neighbors(
+    objects: Vec<Geometry>,
+    mode: &str,
+    topology: Option<&TopoJSON>,
+    weights: Option<&str>,
+    spherical: bool,
+) -> PyResult<NeighborsOutput> {
+    let objects = objects.iter().collect::<Vec<_>>();
+    let mode = Mode::try_from(mode)?;
+    let weights = weights.map(Weights::try_from).transpose()?;
+    match topology {
+        Some(topology) => Ok(neighbors_output(
+            topology, &objects, mode, weights, spherical,
+        )),
+        None if mode == Mode::Rook && weights.is_none() => {
+            Ok(NeighborsOutput::Indexes(wrap_neighbors(&objects)))
+        }
+        None => Err(PyValueError::new_err(
+            "The mode 'queen' and the weights require the 'topology' of the objects",
+        )),
+    }
 }
 
 #[pyfunction]
-pub fn quantize(topology: &TopoJSON, transform: f64) -> PyResult<TopoJSON> {
-    wrap_quantize(&topology, &transform)
+#[pyo3(signature = (objects_a, objects_b, ids=false))]
+pub fn neighbors_between(
+    objects_a: Vec<Geometry>,
+    objects_b: Vec<Geometry>,
+    ids: bool,
+) -> Vec<Vec<Id>> {
+    wrap_neighbors_between(
+        objects_a.iter().collect::<Vec<_>>().as_slice(),
+        objects_b.iter().collect::<Vec<_>>().as_slice(),
+        ids,
+    )
+}
+
+#[pyfunction]
+#[pyo3(signature = (topology, objects, format="csr", mode="rook", spherical=false))]
+pub fn adjacency(
+    topology: &TopoJSON,
+    objects: Vec<Geometry>,
+    format: &str,
+    mode: &str,
+    spherical: bool,
+) -> PyResult<Adjacency> {
+    Ok(wrap_adjacency(
+        topology,
+        objects.iter().collect::<Vec<_>>().as_slice(),
+        Format::try_from(format)?,
+        Mode::try_from(mode)?,
+        spherical,
+    ))
+}
+
+#[pyfunction]
+#[pyo3(signature = (objects, max_colors=4, method="dsatur"))]
+pub fn color(objects: Vec<Geometry>, max_colors: usize, method: &str) -> PyResult<Vec<usize>> {
+    wrap_color(
+        objects.iter().collect::<Vec<_>>().as_slice(),
+        max_colors,
+        Method::try_from(method)?,
+    )
+}
+
+fn neighbors_output(
+    topology: &TopoJSON,
+    objects: &[&Geometry],
+    mode: Mode,
+    weights: Option<Weights>,
+    spherical: bool,
+) -> NeighborsOutput {
+    match (mode, weights) {
+        (Mode::Rook, None) => NeighborsOutput::Indexes(wrap_neighbors(objects)),
+        (Mode::Queen, None) => NeighborsOutput::Indexes(wrap_neighbors_queen(topology, objects)),
+        (mode, Some(weights)) => NeighborsOutput::Weighted(wrap_weighted_neighbors(
+            topology, objects, mode, weights, spherical,
+        )),
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (topology, transform=None, max_error=None, spherical=false))]
+pub fn quantize(
+    topology: &TopoJSON,
+    transform: Option<f64>,
+    max_error: Option<f64>,
+    spherical: bool,
+) -> PyResult<TopoJSON> {
+    match (transform, max_error) {
+        (Some(transform), None) => wrap_quantize(&topology, &transform, spherical),
+        (None, Some(max_error)) => wrap_quantize_max_error(topology, &max_error, spherical),
+        _ => Err(PyTypeError::new_err(
+            "Exactly one of 'transform' or 'max_error' must be specified",
+        )),
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (topology, transform, spherical=false))]
+pub fn quantization_error(
+    topology: &TopoJSON,
+    transform: f64,
+    spherical: bool,
+) -> PyResult<QuantizationReport> {
+    wrap_quantization_error(topology, &transform, spherical)
+}
+
+#[pyfunction]
+pub fn simplify_dp(topology: &TopoJSON, tolerance: f64) -> PyResult<TopoJSON> {
+    wrap_simplify_dp(topology, &tolerance)
+}
+
+#[pyfunction]
+pub fn unquantize(topology: &TopoJSON) -> TopoJSON {
+    wrap_unquantize(topology)
 }
 
 #[pymethods]
@@ -68,13 +299,15 @@ impl TopoJSON {
         }
     }
 
-    fn merge(&self, key: &str) -> PyResult<FeatureGeometryType> {
+    #[pyo3(signature = (key, spherical=false))]
+    fn merge(&self, key: &str, spherical: bool) -> PyResult<FeatureGeometryType> {
         if let Geometry::GeometryCollection { geometries, .. } = self.objects.get(key).ok_or(
             PyKeyError::new_err(format!("Key '{}' not found in 'objects'", key)),
         )? {
             Ok(wrap_merge(
                 &self,
                 geometries.iter().collect::<Vec<_>>().as_slice(),
+                spherical,
             ))
         } else {
             Err(PyTypeError::new_err(format!(
@@ -84,11 +317,92 @@ impl TopoJSON {
         }
     }
 
-    fn mesh(&self, key: Option<&str>, filter: Option<&GeoVar>) -> PyResult<FeatureGeometryType> {
+    #[pyo3(signature = (key, spherical=false, into=None))]
+    fn merge_arcs(
+        &mut self,
+        key: &str,
+        spherical: bool,
+        into: Option<String>,
+    ) -> PyResult<Geometry> {
+        let geometry =
+            if let Geometry::GeometryCollection { geometries, .. } = self.objects.get(key).ok_or(
+                PyKeyError::new_err(format!("Key '{}' not found in 'objects'", key)),
+            )? {
+                wrap_merge_arcs(
+                    self,
+                    geometries.iter().collect::<Vec<_>>().as_slice(),
+                    spherical,
+                )
+            } else {
+                return Err(PyTypeError::new_err(format!(
+                    "The type of geometry '{}' must be 'GeometryCollection'",
+                    key
+                )));
+            };
+        if let Some(into) = into {
+            self.objects.insert(into, geometry.clone());
+        }
+        Ok(geometry)
+    }
+
+    #[pyo3(signature = (key, by, aggregate=None, spherical=false))]
+    fn dissolve(
+        &self,
+        key: &str,
+        by: By,
+        aggregate: Option<HashMap<String, String>>,
+        spherical: bool,
+    ) -> PyResult<FeatureCollection> {
+        if let Geometry::GeometryCollection { geometries, .. } = self.objects.get(key).ok_or(
+            PyKeyError::new_err(format!("Key '{}' not found in 'objects'", key)),
+        )? {
+            wrap_dissolve(
+                self,
+                geometries.iter().collect::<Vec<_>>().as_slice(),
+                &by,
+                &aggregate.unwrap_or_default(),
+                spherical,
+            )
+        } else {
+            Err(PyTypeError::new_err(format!(
+                "The type of geometry '{}' must be 'GeometryCollection'",
+                key
+            )))
+        }
+    }
+
+    #[pyo3(signature = (key, predicate, into=None))]
+    fn filter(
+        &mut self,
+        key: &str,
+        predicate: PyRef<GeoVar>,
+        into: Option<String>,
+    ) -> PyResult<Option<TopoJSON>> {
+        let geometry =
+            if let collection @ Geometry::GeometryCollection { .. } = self.objects.get(key).ok_or(
+                PyKeyError::new_err(format!("Key '{}' not found in 'objects'", key)),
+            )? {
+                wrap_filter_collection(self, collection, &predicate)?
+            } else {
+                return Err(PyTypeError::new_err(format!(
+                    "The type of geometry '{}' must be 'GeometryCollection'",
+                    key
+                )));
+            };
+        match into {
+            Some(into) => {
+                self.objects.insert(into, geometry);
+                Ok(None)
+            }
+            None => Ok(Some(wrap_filter_topology(self, key, geometry))),
+        }
+    }
+
+    fn mesh(&self, key: Option<&str>, filter: Option<Filter>) -> PyResult<FeatureGeometryType> {
         match key {
             Some(key) => {
                 if let Some(obj) = self.objects.get(key) {
-                    wrap_mesh(self, Some(obj), filter)
+                    wrap_mesh(self, Some(obj), filter.as_ref(), true)
                 } else {
                     Err(PyKeyError::new_err(format!(
                         "Key '{}' not found in 'objects'",
@@ -96,15 +410,121 @@ impl TopoJSON {
                     )))
                 }
             }
-            None => wrap_mesh(self, None, filter),
+            None => wrap_mesh(self, None, filter.as_ref(), true),
+        }
+    }
+
+    #[pyo3(signature = (key=None, filter=None, into=None))]
+    fn mesh_arcs(
+        &mut self,
+        key: Option<&str>,
+        filter: Option<Filter>,
+        into: Option<String>,
+    ) -> PyResult<Geometry> {
+        let geometry = match key {
+            Some(key) => {
+                if let Some(obj) = self.objects.get(key) {
+                    wrap_mesh_arcs(self, Some(obj), filter.as_ref(), true)?
+                } else {
+                    return Err(PyKeyError::new_err(format!(
+                        "Key '{}' not found in 'objects'",
+                        key
+                    )));
+                }
+            }
+            None => wrap_mesh_arcs(self, None, filter.as_ref(), true)?,
+        };
+        if let Some(into) = into {
+            self.objects.insert(into, geometry.clone());
+        }
+        Ok(geometry)
+    }
+
+    #[pyo3(signature = (key, spherical=false))]
+    fn borders(&self, key: &str, spherical: bool) -> PyResult<FeatureCollection> {
+        match self.objects.get(key) {
+            Some(obj) => Ok(wrap_borders(self, obj, spherical)),
+            None => Err(PyKeyError::new_err(format!(
+                "Key '{}' not found in 'objects'",
+                key
+            ))),
+        }
+    }
+
+    #[pyo3(signature = (spherical=false))]
+    fn compute_bbox(&self, spherical: bool) -> [f64; 4] {
+        wrap_bbox(self, spherical)
+    }
+
+    #[pyo3(signature = (key, spherical=false))]
+    fn object_bbox(&self, key: &str, spherical: bool) -> PyResult<[f64; 4]> {
+        match self.objects.get(key) {
+            Some(obj) => Ok(wrap_object_bbox(self, obj, spherical)),
+            None => Err(PyKeyError::new_err(format!(
+                "Key '{}' not found in 'objects'",
+                key
+            ))),
+        }
+    }
+
+    #[pyo3(signature = (key, spherical=false))]
+    fn compute_geometry_bboxes(&mut self, key: &str, spherical: bool) -> PyResult<()> {
+        let mut obj = self.objects.remove(key).ok_or(PyKeyError::new_err(format!(
+            "Key '{}' not found in 'objects'",
+            key
+        )))?;
+        wrap_geometry_bboxes(self, &mut obj, spherical);
+        self.objects.insert(key.to_string(), obj);
+        Ok(())
+    }
+
+    fn index(&self, key: &str) -> PyResult<SpatialIndex> {
+        match self.objects.get(key) {
+            Some(obj) => Ok(wrap_index(self, obj)),
+            None => Err(PyKeyError::new_err(format!(
+                "Key '{}' not found in 'objects'",
+                key
+            ))),
         }
     }
 
-    fn compute_bbox(&self) -> [f64; 4] {
-        wrap_bbox(self)
+    #[pyo3(signature = (key, spherical=false))]
+    fn measure(&self, key: &str, spherical: bool) -> PyResult<Vec<Measure>> {
+        match self.objects.get(key) {
+            Some(obj) => Ok(wrap_measure(self, obj, spherical)),
+            None => Err(PyKeyError::new_err(format!(
+                "Key '{}' not found in 'objects'",
+                key
+            ))),
+        }
     }
 
-    fn neighbors(&self, keys: Vec<String>) -> PyResult<Vec<Vec<i32>>> {
+    #[pyo3(signature = (key, x, y, k=1, spherical=false))]
+    fn nearest(
+        &self,
+        key: &str,
+        x: f64,
+        y: f64,
+        k: usize,
+        spherical: bool,
+    ) -> PyResult<Vec<(usize, Option<String>, f64)>> {
+        match self.objects.get(key) {
+            Some(obj) => wrap_nearest(self, obj, [x, y], k, spherical),
+            None => Err(PyKeyError::new_err(format!(
+                "Key '{}' not found in 'objects'",
+                key
+            ))),
+        }
+    }
+
+    #[pyo3(signature = (keys, mode="rook", weights=None, spherical=false))]
+    fn neighbors(
+        &self,
+        keys: Vec<String>,
+        mode: &str,
+        weights: Option<&str>,
+        spherical: bool,
+    ) -> PyResult<NeighborsOutput> {
         let objects: Vec<&Geometry> = keys
             .iter()
             .map(|key| {
@@ -114,11 +534,111 @@ impl TopoJSON {
                 )))
             })
             .collect::<PyResult<Vec<&Geometry>>>()?;
-        Ok(wrap_neighbors(&objects))
+        Ok(neighbors_output(
+            self,
+            &objects,
+            Mode::try_from(mode)?,
+            weights.map(Weights::try_from).transpose()?,
+            spherical,
+        ))
+    }
+
+    #[pyo3(signature = (key, max_colors=4, method="dsatur", property=None))]
+    fn color(
+        &mut self,
+        key: &str,
+        max_colors: usize,
+        method: &str,
+        property: Option<&str>,
+    ) -> PyResult<Vec<usize>> {
+        if let Geometry::GeometryCollection { geometries, .. } =
+            self.objects
+                .get_mut(key)
+                .ok_or(PyKeyError::new_err(format!(
+                    "Key '{}' not found in 'objects'",
+                    key
+                )))?
+        {
+            let colors = wrap_color(
+                geometries.iter().collect::<Vec<_>>().as_slice(),
+                max_colors,
+                Method::try_from(method)?,
+            )?;
+            if let Some(property) = property {
+                color_properties(geometries, &colors, property);
+            }
+            Ok(colors)
+        } else {
+            Err(PyTypeError::new_err(format!(
+                "The type of geometry '{}' must be 'GeometryCollection'",
+                key
+            )))
+        }
+    }
+
+    #[pyo3(signature = (key_a, key_b, ids=false))]
+    fn neighbors_between(&self, key_a: &str, key_b: &str, ids: bool) -> PyResult<Vec<Vec<Id>>> {
+        let members = |key: &str| match self.objects.get(key) {
+            Some(o) => Ok(o.members()),
+            None => Err(PyKeyError::new_err(format!(
+                "Key '{}' not found in 'objects'",
+                key
+            ))),
+        };
+        Ok(wrap_neighbors_between(
+            &members(key_a)?,
+            &members(key_b)?,
+            ids,
+        ))
+    }
+
+    #[pyo3(signature = (key, format="csr", mode="rook", spherical=false))]
+    fn adjacency(
+        &self,
+        key: &str,
+        format: &str,
+        mode: &str,
+        spherical: bool,
+    ) -> PyResult<Adjacency> {
+        if let Geometry::GeometryCollection { geometries, .. } = self.objects.get(key).ok_or(
+            PyKeyError::new_err(format!("Key '{}' not found in 'objects'", key)),
+        )? {
+            Ok(wrap_adjacency(
+                self,
+                geometries.iter().collect::<Vec<_>>().as_slice(),
+                Format::try_from(format)?,
+                Mode::try_from(mode)?,
+                spherical,
+            ))
+        } else {
+            Err(PyTypeError::new_err(format!(
+                "The type of geometry '{}' must be 'GeometryCollection'",
+                key
+            )))
+        }
+    }
+
+    #[pyo3(signature = (transform=None, max_error=None, spherical=false))]
+    fn quantize(
+        &self,
+        transform: Option<f64>,
+        max_error: Option<f64>,
+        spherical: bool,
+    ) -> PyResult<TopoJSON> {
+        quantize(self, transform, max_error, spherical)
+    }
+
+    #[pyo3(signature = (transform, spherical=false))]
+    fn quantization_error(&self, transform: f64, spherical: bool) -> PyResult<QuantizationReport> {
+        wrap_quantization_error(self, &transform, spherical)
+    }
+
+    fn simplify_dp(&self, tolerance: f64) -> PyResult<TopoJSON> {
+        wrap_simplify_dp(self, &tolerance)
     }
 
-    fn quantize(&self, transform: f64) -> PyResult<TopoJSON> {
-        wrap_quantize(self, &transform)
+    fn unquantize(&self) -> TopoJSON {
+        wrap_unquantize(self)
     }
 
     fn write(&self, file: &str) -> PyResult<()> {
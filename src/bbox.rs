@@ -1,8 +1,45 @@
+use std::cmp::Ordering;
+
 use crate::topojsons::{Geometry, TopoJSON};
 use crate::transform::{IdentityTransformer, ScaleTransformer, Transformer};
 
-pub fn wrap_bbox(topology: &TopoJSON) -> [f64; 4] {
-    bbox(topology)
+/// Bounding box of the topology or, when `spherical` is `true`, bounding box
+/// whose longitude interval is the smallest one covering the arcs and points,
+/// with `x0 > x1` when it crosses the antimeridian.
+pub fn wrap_bbox(topology: &TopoJSON, spherical: bool) -> [f64; 4] {
+    if !spherical {
+        return bbox(topology);
+    }
+    match &topology.transform {
+        Some(transform) => {
+            GeometryBbox::new(topology, ScaleTransformer::new(transform), true).topology()
+        }
+        None => GeometryBbox::new(topology, IdentityTransformer::new(), true).topology(),
+    }
+}
+
+/// Bounding box of an object, decoding only the arcs it references.
+pub fn wrap_object_bbox(topology: &TopoJSON, o: &Geometry, spherical: bool) -> [f64; 4] {
+    let extent = match &topology.transform {
+        Some(transform) => {
+            GeometryBbox::new(topology, ScaleTransformer::new(transform), spherical).geometry(o)
+        }
+        None => GeometryBbox::new(topology, IdentityTransformer::new(), spherical).geometry(o),
+    };
+    extent.bounds(spherical)
+}
+
+/// Fills the `bbox` of an object and of all its geometries. Geometries
+/// without positions get no bounding box.
+pub fn wrap_geometry_bboxes(topology: &TopoJSON, o: &mut Geometry, spherical: bool) {
+    match &topology.transform {
+        Some(transform) => {
+            GeometryBbox::new(topology, ScaleTransformer::new(transform), spherical).fill(o);
+        }
+        None => {
+            GeometryBbox::new(topology, IdentityTransformer::new(), spherical).fill(o);
+        }
+    }
 }
 
 pub fn bbox(topology: &TopoJSON) -> [f64; 4] {
@@ -41,7 +78,7 @@ impl<T: Transformer> Bbox<T> {
     pub fn bbox(mut self, topology: &TopoJSON) -> [f64; 4] {
         topology.arcs.iter().for_each(|arc_vec| {
             for (i, arc) in arc_vec.iter().enumerate() {
-                let p = self.transformer.call(&arc.map(|x| x as f64), i);
+                let p = self.transformer.call(arc, i);
                 if p[0] < self.x0 {
                     self.x0 = p[0];
                 }
@@ -94,9 +131,203 @@ impl<T: Transformer> Bbox<T> {
     }
 }
 
+const EMPTY: [f64; 4] = [f64::INFINITY, f64::INFINITY, -f64::INFINITY, -f64::INFINITY];
+
+/// Extent of positions: the sorted and disjoint x intervals covered, reduced
+/// to a single interval unless on the sphere, and the y interval.
+#[derive(Clone, Debug, PartialEq)]
+struct Extent {
+    x: Vec<[f64; 2]>,
+    y: [f64; 2],
+}
+
+impl Extent {
+    fn empty() -> Self {
+        Self {
+            x: Vec::new(),
+            y: [f64::INFINITY, -f64::INFINITY],
+        }
+    }
+
+    fn point(p: [f64; 2]) -> Self {
+        Self {
+            x: vec![[p[0], p[0]]],
+            y: [p[1], p[1]],
+        }
+    }
+
+    fn union(mut self, other: &Extent, spherical: bool) -> Self {
+        self.y = [self.y[0].min(other.y[0]), self.y[1].max(other.y[1])];
+        self.x.extend_from_slice(&other.x);
+        self.normalize(spherical);
+        self
+    }
+
+    fn normalize(&mut self, spherical: bool) {
+        if self.x.len() < 2 {
+            return;
+        }
+        if !spherical {
+            let x0 = self.x.iter().map(|x| x[0]).fold(f64::INFINITY, f64::min);
+            let x1 = self.x.iter().map(|x| x[1]).fold(-f64::INFINITY, f64::max);
+            self.x = vec![[x0, x1]];
+            return;
+        }
+        self.x
+            .sort_by(|a, b| a[0].partial_cmp(&b[0]).unwrap_or(Ordering::Equal));
+        let mut merged: Vec<[f64; 2]> = Vec::with_capacity(self.x.len());
+        for x in self.x.drain(..) {
+            match merged.last_mut() {
+                Some(last) if x[0] <= last[1] => last[1] = last[1].max(x[1]),
+                _ => merged.push(x),
+            }
+        }
+        self.x = merged;
+    }
+
+    /// `[x0, y0, x1, y1]`. On the sphere, the longitude interval is the
+    /// complement of the largest gap between the covered intervals.
+    fn bounds(&self, spherical: bool) -> [f64; 4] {
+        let (Some(first), Some(last)) = (self.x.first(), self.x.last()) else {
+            return EMPTY;
+        };
+        let [y0, y1] = self.y;
+        if !spherical {
+            return [first[0], y0, last[1], y1];
+        }
+        let mut gap = first[0] + 360. - last[1];
+        let mut bounds = [first[0], y0, last[1], y1];
+        for x in self.x.windows(2) {
+            if x[1][0] - x[0][1] > gap {
+                gap = x[1][0] - x[0][1];
+                bounds = [x[1][0], y0, x[0][1], y1];
+            }
+        }
+        bounds
+    }
+}
+
+/// Bounding boxes of geometries, with a cache of the extent of each arc so
+/// that arcs shared by several geometries are decoded once. On the sphere,
+/// the segments of the arcs take the shortest way in longitude, crossing the
+/// antimeridian when their longitudes differ by more than 180°.
+struct GeometryBbox<'a, T>
+where
+    T: Transformer,
+{
+    topology: &'a TopoJSON,
+    transformer: T,
+    spherical: bool,
+    arcs: Vec<Option<Extent>>,
+}
+
+impl<'a, T: Transformer> GeometryBbox<'a, T> {
+    fn new(topology: &'a TopoJSON, transformer: T, spherical: bool) -> Self {
+        Self {
+            topology,
+            transformer,
+            spherical,
+            arcs: vec![None; topology.arcs.len()],
+        }
+    }
+
+    fn topology(mut self) -> [f64; 4] {
+        let mut extent = Extent::empty();
+        for i in 0..self.topology.arcs.len() {
+            extent = extent.union(&self.arc(i as i32), self.spherical);
+        }
+        for o in self.topology.objects.values() {
+            extent = extent.union(&self.geometry(o), self.spherical);
+        }
+        extent.bounds(self.spherical)
+    }
+
+    fn arc(&mut self, i: i32) -> Extent {
+        let i = if i < 0 { !i } else { i } as usize;
+        if let Some(extent) = &self.arcs[i] {
+            return extent.clone();
+        }
+        let mut extent = Extent::empty();
+        let mut previous: Option<[f64; 2]> = None;
+        for (j, p) in self.topology.arcs[i].iter().enumerate() {
+            let p = self.transformer.call(p, j);
+            extent.y = [extent.y[0].min(p[1]), extent.y[1].max(p[1])];
+            match previous {
+                Some(a) if self.spherical && (p[0] - a[0]).abs() > 180. => {
+                    let [x0, x1] = [a[0].min(p[0]), a[0].max(p[0])];
+                    extent.x.push([x1, 180.]);
+                    extent.x.push([-180., x0]);
+                }
+                Some(a) => extent.x.push([a[0].min(p[0]), a[0].max(p[0])]),
+                None => extent.x.push([p[0], p[0]]),
+            }
+            previous = Some(p);
+        }
+        extent.normalize(self.spherical);
+        self.arcs[i] = Some(extent.clone());
+        extent
+    }
+
+    fn line(&mut self, arcs: &[i32]) -> Extent {
+        arcs.iter().fold(Extent::empty(), |extent, &i| {
+            extent.union(&self.arc(i), self.spherical)
+        })
+    }
+
+    fn polygon(&mut self, arcs: &[Vec<i32>]) -> Extent {
+        arcs.iter().fold(Extent::empty(), |extent, arcs| {
+            extent.union(&self.line(arcs), self.spherical)
+        })
+    }
+
+    fn point(&mut self, p: &[f64; 2]) -> Extent {
+        Extent::point(self.transformer.call(p, 0))
+    }
+
+    fn geometry(&mut self, o: &Geometry) -> Extent {
+        let spherical = self.spherical;
+        match o {
+            Geometry::GeometryCollection { geometries, .. } => {
+                geometries.iter().fold(Extent::empty(), |extent, o| {
+                    extent.union(&self.geometry(o), spherical)
+                })
+            }
+            Geometry::Point { coordinates, .. } => self.point(coordinates),
+            Geometry::MultiPoint { coordinates, .. } => {
+                coordinates.iter().fold(Extent::empty(), |extent, p| {
+                    extent.union(&self.point(p), spherical)
+                })
+            }
+            Geometry::LineString { arcs, .. } => self.line(arcs),
+            Geometry::MultiLineString { arcs, .. } => self.polygon(arcs),
+            Geometry::Polygon { arcs, .. } => self.polygon(arcs),
+            Geometry::MultiPolygon { arcs, .. } => {
+                arcs.iter().fold(Extent::empty(), |extent, arcs| {
+                    extent.union(&self.polygon(arcs), spherical)
+                })
+            }
+        }
+    }
+
+    fn fill(&mut self, o: &mut Geometry) -> Extent {
+        let spherical = self.spherical;
+        let extent = match o {
+            Geometry::GeometryCollection { geometries, .. } => {
+                geometries.iter_mut().fold(Extent::empty(), |extent, o| {
+                    extent.union(&self.fill(o), spherical)
+                })
+            }
+            _ => self.geometry(o),
+        };
+        *o.bbox_mut() = (!extent.x.is_empty()).then(|| extent.bounds(spherical).to_vec());
+        extent
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::request::request;
+    use crate::topojsons::Transform;
     use pyo3::prelude::PyResult;
     use std::collections::HashMap;
 
@@ -112,18 +343,168 @@ mod tests {
             arcs: Vec::new(),
         };
         assert_eq!(
-            wrap_bbox(&topology),
+            wrap_bbox(&topology, false),
             [f64::INFINITY, f64::INFINITY, -f64::INFINITY, -f64::INFINITY]
         );
         Ok(())
     }
 
+    fn topology(transform: Option<Transform>) -> TopoJSON {
+        let arcs = match transform {
+            Some(_) => vec![
+                vec![[0., 0.], [2., 0.], [0., 1.]],
+                vec![[2., 1.], [2., 2.]],
+                vec![[10., 10.], [1., 1.]],
+            ],
+            None => vec![
+                vec![[0., 0.], [2., 0.], [2., 1.]],
+                vec![[2., 1.], [4., 3.]],
+                vec![[10., 10.], [11., 11.]],
+            ],
+        };
+        TopoJSON {
+            bbox: Vec::new(),
+            transform,
+            objects: HashMap::new(),
+            arcs,
+        }
+    }
+
+    fn collection() -> Geometry {
+        Geometry::GeometryCollection {
+            geometries: vec![
+                Geometry::LineString {
+                    arcs: vec![0],
+                    id: None,
+                    properties: None,
+                    bbox: None,
+                },
+                Geometry::LineString {
+                    arcs: vec![0, 1],
+                    id: None,
+                    properties: None,
+                    bbox: None,
+                },
+                Geometry::Point {
+                    coordinates: [-1., 1.],
+                    id: None,
+                    properties: None,
+                    bbox: None,
+                },
+                Geometry::GeometryCollection {
+                    geometries: Vec::new(),
+                    id: None,
+                    properties: None,
+                    bbox: None,
+                },
+            ],
+            id: None,
+            properties: None,
+            bbox: None,
+        }
+    }
+
+    #[test]
+    fn test_bbox_6() {
+        // The arc 2 is not referenced by the object.
+        let topology = topology(None);
+        assert_eq!(
+            wrap_object_bbox(&topology, &collection(), false),
+            [-1., 0., 4., 3.]
+        );
+        assert_eq!(
+            wrap_object_bbox(
+                &topology,
+                &Geometry::LineString {
+                    arcs: vec![-2],
+                    id: None,
+                    properties: None,
+                    bbox: None,
+                },
+                false
+            ),
+            [2., 1., 4., 3.]
+        );
+
+        let transform = Transform {
+            scale: [2., 1.],
+            translate: [0., 0.],
+        };
+        let topology = self::topology(Some(transform));
+        assert_eq!(
+            wrap_object_bbox(&topology, &collection(), false),
+            [-2., 0., 8., 3.]
+        );
+    }
+
+    #[test]
+    fn test_bbox_7() {
+        let topology = topology(None);
+        let mut o = collection();
+        wrap_geometry_bboxes(&topology, &mut o, false);
+        assert_eq!(o.bbox(), Some(vec![-1., 0., 4., 3.]));
+        let Geometry::GeometryCollection { geometries, .. } = o else {
+            unreachable!()
+        };
+        assert_eq!(
+            geometries.iter().map(|o| o.bbox()).collect::<Vec<_>>(),
+            vec![
+                Some(vec![0., 0., 2., 1.]),
+                Some(vec![0., 0., 4., 3.]),
+                Some(vec![-1., 1., -1., 1.]),
+                None
+            ]
+        );
+    }
+
+    //
+    // Fiji: arc 0 east of the antimeridian, arc 1 west of it, arc 2 crossing
+    // it and arc 3 a point near Greenwich.
+    //
+    #[test]
+    fn test_bbox_8() {
+        let topology = TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: HashMap::new(),
+            arcs: vec![
+                vec![[177., -16.], [179., -17.]],
+                vec![[-180., -16.], [-178., -17.]],
+                vec![[170., 1.], [-170., 2.]],
+                vec![[0., 0.], [1., 0.]],
+            ],
+        };
+        let line = |arcs: Vec<i32>| Geometry::LineString {
+            arcs,
+            id: None,
+            properties: None,
+            bbox: None,
+        };
+        assert_eq!(
+            wrap_object_bbox(&topology, &line(vec![0, 1]), true),
+            [177., -17., -178., -16.]
+        );
+        assert_eq!(
+            wrap_object_bbox(&topology, &line(vec![0, 1]), false),
+            [-180., -17., 179., -16.]
+        );
+        assert_eq!(
+            wrap_object_bbox(&topology, &line(vec![2]), true),
+            [170., 1., -170., 2.]
+        );
+        assert_eq!(wrap_bbox(&topology, true), [0., -17., -170., 2.]);
+        assert_eq!(
+            wrap_object_bbox(&topology, &line(vec![0, 3]), true),
+            [0., -17., 179., 0.]
+        );
+    }
+
     #[tokio::test]
     async fn test_bbox_2() -> Result<(), String> {
         let topology =
             serde_json::from_str::<TopoJSON>(&request("test/topojson/polygon-q1e4.json").await?)
                 .unwrap();
-        assert_eq!(wrap_bbox(&topology), [0., 0., 10., 10.]);
+        assert_eq!(wrap_bbox(&topology, false), [0., 0., 10., 10.]);
         Ok(())
     }
 
@@ -132,7 +513,7 @@ mod tests {
         let topology =
             serde_json::from_str::<TopoJSON>(&request("test/topojson/polygon.json").await?)
                 .unwrap();
-        assert_eq!(wrap_bbox(&topology), [0., 0., 10., 10.]);
+        assert_eq!(wrap_bbox(&topology, false), [0., 0., 10., 10.]);
         Ok(())
     }
 
@@ -140,7 +521,7 @@ mod tests {
     async fn test_bbox_4() -> Result<(), String> {
         let topology =
             serde_json::from_str::<TopoJSON>(&request("test/topojson/point.json").await?).unwrap();
-        assert_eq!(wrap_bbox(&topology), [0., 0., 10., 10.]);
+        assert_eq!(wrap_bbox(&topology, false), [0., 0., 10., 10.]);
         Ok(())
     }
 
@@ -148,7 +529,7 @@ mod tests {
     async fn test_bbox_5() -> Result<(), String> {
         let topology =
             serde_json::from_str::<TopoJSON>(&request("test/topojson/points.json").await?).unwrap();
-        assert_eq!(wrap_bbox(&topology), [0., 0., 10., 10.]);
+        assert_eq!(wrap_bbox(&topology, false), [0., 0., 10., 10.]);
         Ok(())
     }
 }
@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+
+use rustc_hash::FxHashMap;
+use serde_json::{Map, Value};
+
+use crate::feature::{decode_ring, object_func};
+use crate::geojsons::{Feature, FeatureCollection};
+use crate::merge::planar_ring_signed_area;
+use crate::mesh::geoms_by_arc;
+use crate::neighbors::arc_length;
+use crate::stitch::stitch;
+use crate::topojsons::{Geometry, TopoJSON};
+
+/// Borders between the geometries of `object`, one feature per pair of
+/// geometries and per geometry for its exterior border. Each border is
+/// stitched so that the geometry `left_id` lies on its left and the geometry
+/// `right_id`, `null` for the exterior, on its right.
+pub fn wrap_borders(topology: &TopoJSON, object: &Geometry, spherical: bool) -> FeatureCollection {
+    let members = object.members();
+    let mut indexes: FxHashMap<*const Geometry, usize> = FxHashMap::default();
+    members
+        .iter()
+        .enumerate()
+        .for_each(|(i, o)| index(&mut indexes, o, i));
+    let mut counterclockwise: FxHashMap<*const Geometry, bool> = FxHashMap::default();
+
+    let mut borders: BTreeMap<(usize, Option<usize>), Vec<i32>> = BTreeMap::new();
+    for (_, geoms) in geoms_by_arc(object) {
+        let mut sides: Vec<(usize, i32, &Geometry)> = geoms
+            .iter()
+            .map(|item| {
+                (
+                    indexes[&(item.geometry as *const Geometry)],
+                    item.i,
+                    item.geometry,
+                )
+            })
+            .collect();
+        sides.sort_by_key(|&(index, ..)| index);
+        let (left, i, geometry) = sides[0];
+        let right = match sides.last() {
+            Some(&(right, ..)) if right != left => Some(right),
+            // An arc used twice by the same geometry is not a border.
+            _ if sides.len() > 1 => continue,
+            _ => None,
+        };
+        let ccw = *counterclockwise
+            .entry(geometry as *const Geometry)
+            .or_insert_with(|| is_counterclockwise(topology, geometry));
+        borders
+            .entry((left, right))
+            .or_default()
+            .push(if ccw { i } else { !i });
+    }
+
+    let id = |i: usize| {
+        members[i]
+            .id()
+            .map(Value::from)
+            .unwrap_or_else(|| Value::from(i))
+    };
+    let features = borders
+        .into_iter()
+        .map(|((left, right), arcs)| {
+            let length: f64 = arcs
+                .iter()
+                .map(|&i| arc_length(topology, if i < 0 { !i } else { i } as usize, spherical))
+                .sum();
+            let mut properties = Map::new();
+            properties.insert("left_id".to_string(), id(left));
+            properties.insert("right_id".to_string(), right.map(id).into());
+            properties.insert("length".to_string(), Value::from(length));
+            Feature {
+                properties: Some(Value::Object(properties).to_string()),
+                geometry: object_func(
+                    topology,
+                    &Geometry::MultiLineString {
+                        arcs: stitch(topology, arcs),
+                        id: None,
+                        properties: None,
+                        bbox: None,
+                    },
+                ),
+                id: None,
+                bbox: None,
+            }
+        })
+        .collect();
+    FeatureCollection { features }
+}
+
+/// Maps the innermost geometries of `o` to the index `i` of `o`.
+fn index(indexes: &mut FxHashMap<*const Geometry, usize>, o: &Geometry, i: usize) {
+    indexes.insert(o as *const Geometry, i);
+    if let Geometry::GeometryCollection { geometries, .. } = o {
+        geometries.iter().for_each(|o| index(indexes, o, i));
+    }
+}
+
+/// Whether the exterior rings of the polygons of `o` are counterclockwise,
+/// i.e. whether the polygons lie on the left of their arcs. Lines are
+/// considered counterclockwise.
+fn is_counterclockwise(topology: &TopoJSON, o: &Geometry) -> bool {
+    let exteriors: Vec<&Vec<i32>> = match o {
+        Geometry::Polygon { arcs, .. } => arcs.first().into_iter().collect(),
+        Geometry::MultiPolygon { arcs, .. } => arcs.iter().filter_map(|p| p.first()).collect(),
+        _ => Vec::new(),
+    };
+    let area: f64 = exteriors
+        .into_iter()
+        .map(|ring| planar_ring_signed_area(&decode_ring(topology, ring)))
+        .sum();
+    area >= 0.
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::geojsons::FeatureGeometryType;
+
+    use super::*;
+
+    //
+    // (0,1)-(1,1)-(2,1)
+    //   |  A  |  B  |
+    // (0,0)-(1,0)-(2,0)
+    //
+    fn topology(sign: f64) -> (TopoJSON, Vec<Geometry>) {
+        let arcs: Vec<Vec<[f64; 2]>> = vec![
+            vec![[1., 0.], [1., 1.]],
+            vec![[1., 1.], [0., 1.], [0., 0.], [1., 0.]],
+            vec![[1., 0.], [2., 0.], [2., 1.], [1., 1.]],
+        ];
+        let topology = TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: HashMap::new(),
+            arcs: arcs
+                .into_iter()
+                .map(|arc| arc.into_iter().map(|[x, y]| [sign * x, y]).collect())
+                .collect(),
+        };
+        let geometries = [("A", vec![vec![0, 1]]), ("B", vec![vec![2, -1]])]
+            .map(|(id, arcs)| Geometry::Polygon {
+                arcs,
+                id: Some(id.to_string()),
+                properties: None,
+                bbox: None,
+            })
+            .into_iter()
+            .collect();
+        (topology, geometries)
+    }
+
+    fn properties(feature: &Feature) -> Value {
+        serde_json::from_str(feature.properties.as_ref().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_borders_1() {
+        let (topology, geometries) = topology(1.);
+        let collection = Geometry::GeometryCollection {
+            geometries,
+            id: None,
+            properties: None,
+            bbox: None,
+        };
+        let borders = wrap_borders(&topology, &collection, false);
+        assert_eq!(
+            borders.features.iter().map(properties).collect::<Vec<_>>(),
+            vec![
+                serde_json::json!({"left_id": "A", "right_id": null, "length": 3.}),
+                serde_json::json!({"left_id": "A", "right_id": "B", "length": 1.}),
+                serde_json::json!({"left_id": "B", "right_id": null, "length": 3.}),
+            ]
+        );
+        assert_eq!(
+            borders.features[1].geometry,
+            FeatureGeometryType::MultiLineString {
+                coordinates: vec![vec![[1., 0.], [1., 1.]]]
+            }
+        );
+    }
+
+    #[test]
+    fn test_borders_2() {
+        // Clockwise rings: the shared border is reversed to keep A on its
+        // left.
+        let (topology, geometries) = topology(-1.);
+        let mut polygon = geometries[0].clone();
+        let collection = Geometry::GeometryCollection {
+            geometries,
+            id: None,
+            properties: None,
+            bbox: None,
+        };
+        let borders = wrap_borders(&topology, &collection, false);
+        assert_eq!(
+            borders.features[1].geometry,
+            FeatureGeometryType::MultiLineString {
+                coordinates: vec![vec![[-1., 1.], [-1., 0.]]]
+            }
+        );
+
+        // A single geometry only has an exterior border, identified by index.
+        if let Geometry::Polygon { id, .. } = &mut polygon {
+            *id = None;
+        }
+        let borders = wrap_borders(&topology, &polygon, false);
+        assert_eq!(
+            borders.features.iter().map(properties).collect::<Vec<_>>(),
+            vec![serde_json::json!({"left_id": 0, "right_id": null, "length": 4.})]
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::fmt;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde_json::{Map, Value};
+
+use crate::neighbors::wrap_neighbors;
+use crate::topojsons::Geometry;
+
+/// Coloring algorithm of `color`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Method {
+    Greedy,
+    Dsatur,
+}
+
+impl TryFrom<&str> for Method {
+    type Error = PyErr;
+
+    fn try_from(value: &str) -> PyResult<Self> {
+        match value {
+            "greedy" => Ok(Self::Greedy),
+            "dsatur" => Ok(Self::Dsatur),
+            _ => Err(PyValueError::new_err(format!(
+                "Unknown method {:?}, expected 'greedy' or 'dsatur'",
+                value
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Greedy => "greedy",
+            Self::Dsatur => "dsatur",
+        })
+    }
+}
+
+/// Colors the objects so that objects sharing an arc have different colors,
+/// returning the color index of each object. Ties are broken by the order of
+/// the objects so that results are deterministic.
+pub fn wrap_color(
+    objects: &[&Geometry],
+    max_colors: usize,
+    method: Method,
+) -> PyResult<Vec<usize>> {
+    let neighbors = wrap_neighbors(objects);
+    if !(1..=64).contains(&max_colors) {
+        return Err(PyValueError::new_err(
+            "'max_colors' must be between 1 and 64",
+        ));
+    }
+    let colors = match method {
+        Method::Greedy => Coloring::new(&neighbors).greedy(),
+        Method::Dsatur => Coloring::new(&neighbors).dsatur(),
+    };
+    match colors.iter().max() {
+        Some(&max) if max >= max_colors => Err(PyValueError::new_err(format!(
+            "Cannot color the geometries with {} colors, the '{}' coloring needs {}",
+            max_colors,
+            method,
+            max + 1
+        ))),
+        _ => Ok(colors),
+    }
+}
+
+/// Writes the color of each geometry into its properties under `property`.
+pub fn color_properties(geometries: &mut [Geometry], colors: &[usize], property: &str) {
+    for (o, &color) in geometries.iter_mut().zip(colors) {
+        let properties = o.properties_mut();
+        let mut map = match properties
+            .as_ref()
+            .and_then(|properties| serde_json::from_str(properties).ok())
+        {
+            Some(Value::Object(map)) => map,
+            _ => Map::new(),
+        };
+        map.insert(property.to_string(), Value::from(color));
+        *properties = Some(Value::Object(map).to_string());
+    }
+}
+
+struct Coloring<'a> {
+    neighbors: &'a [Vec<i32>],
+    colors: Vec<Option<usize>>,
+}
+
+impl<'a> Coloring<'a> {
+    fn new(neighbors: &'a [Vec<i32>]) -> Self {
+        Self {
+            neighbors,
+            colors: vec![None; neighbors.len()],
+        }
+    }
+
+    /// Smallest color not used by the neighbors of `i`.
+    fn smallest(&self, i: usize) -> usize {
+        let mut used: Vec<usize> = self.neighbors[i]
+            .iter()
+            .filter_map(|&j| self.colors[j as usize])
+            .collect();
+        used.sort_unstable();
+        used.dedup();
+        used.iter()
+            .enumerate()
+            .find(|&(color, &other)| color != other)
+            .map(|(color, _)| color)
+            .unwrap_or(used.len())
+    }
+
+    fn greedy(mut self) -> Vec<usize> {
+        for i in 0..self.neighbors.len() {
+            self.colors[i] = Some(self.smallest(i));
+        }
+        self.colors.into_iter().flatten().collect()
+    }
+
+    /// Colors first the vertex whose neighbors use the most distinct colors,
+    /// then the one with the most neighbors, then the first one.
+    fn dsatur(mut self) -> Vec<usize> {
+        let n = self.neighbors.len();
+        let mut saturations: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); n];
+        let degree = |i: usize| self.neighbors[i].len();
+        let mut queue: BTreeSet<(usize, usize, Reverse<usize>)> =
+            (0..n).map(|i| (0, degree(i), Reverse(i))).collect();
+
+        while let Some((_, _, Reverse(i))) = queue.pop_last() {
+            let color = self.smallest(i);
+            self.colors[i] = Some(color);
+            for &j in &self.neighbors[i] {
+                let j = j as usize;
+                if self.colors[j].is_some() {
+                    continue;
+                }
+                let saturation = saturations[j].len();
+                if saturations[j].insert(color) {
+                    let degree = self.neighbors[j].len();
+                    queue.remove(&(saturation, degree, Reverse(j)));
+                    queue.insert((saturation + 1, degree, Reverse(j)));
+                }
+            }
+        }
+        self.colors.into_iter().flatten().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polygons(arcs: &[Vec<Vec<i32>>]) -> Vec<Geometry> {
+        arcs.iter()
+            .map(|arcs| Geometry::Polygon {
+                arcs: arcs.clone(),
+                id: None,
+                properties: None,
+                bbox: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_color_1() {
+        assert_eq!(
+            wrap_color(&[], 4, Method::Dsatur).unwrap(),
+            Vec::<usize>::new()
+        );
+        assert!(wrap_color(&[], 0, Method::Dsatur).is_err());
+        assert_eq!(
+            Method::try_from("welsh").err().map(|e| e.to_string()),
+            Some("ValueError: Unknown method \"welsh\", expected 'greedy' or 'dsatur'".to_string())
+        );
+    }
+
+    //
+    // +---+---+---+
+    // | 0 | 1 | 2 |
+    // +---+---+---+
+    //
+    #[test]
+    fn test_color_2() {
+        let objects = polygons(&[vec![vec![0, 1]], vec![vec![-2, 2]], vec![vec![-3, 3]]]);
+        let objects = objects.iter().collect::<Vec<_>>();
+        assert_eq!(
+            wrap_color(&objects, 4, Method::Greedy).unwrap(),
+            vec![0, 1, 0]
+        );
+        assert_eq!(
+            wrap_color(&objects, 4, Method::Dsatur).unwrap(),
+            vec![1, 0, 1]
+        );
+        assert_eq!(
+            wrap_color(&objects, 1, Method::Dsatur)
+                .err()
+                .map(|e| e.to_string()),
+            Some(
+                "ValueError: Cannot color the geometries with 1 colors, the 'dsatur' coloring needs 2"
+                    .to_string()
+            )
+        );
+    }
+
+    //
+    // Wheel: a center 0 surrounded by 1, 2, 3, 4, 5, each touching the next.
+    //
+    #[test]
+    fn test_color_3() {
+        let objects = polygons(&[
+            vec![vec![0, 1, 2, 3, 4]],
+            vec![vec![-1, 5, -7]],
+            vec![vec![-2, 6, -8]],
+            vec![vec![-3, 7, -9]],
+            vec![vec![-4, 8, -10]],
+            vec![vec![-5, 9, -6]],
+        ]);
+        let objects = objects.iter().collect::<Vec<_>>();
+        for method in [Method::Greedy, Method::Dsatur] {
+            let colors = wrap_color(&objects, 4, method).unwrap();
+            let neighbors = wrap_neighbors(&objects);
+            for (i, neighbors) in neighbors.iter().enumerate() {
+                for &j in neighbors {
+                    assert_ne!(colors[i], colors[j as usize]);
+                }
+            }
+            assert_eq!(colors, wrap_color(&objects, 4, method).unwrap());
+            assert!(wrap_color(&objects, 3, method).is_err());
+        }
+    }
+
+    #[test]
+    fn test_color_4() {
+        let mut geometries = polygons(&[vec![vec![0, 1]], vec![vec![-2, 2]]]);
+        *geometries[0].properties_mut() = Some(r#"{"name":"a"}"#.to_string());
+        color_properties(&mut geometries, &[1, 0], "color");
+        assert_eq!(
+            geometries[0].properties(),
+            Some(r#"{"color":1,"name":"a"}"#.to_string())
+        );
+        assert_eq!(
+            geometries[1].properties(),
+            Some(r#"{"color":0}"#.to_string())
+        );
+    }
+}
@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use serde_json::{Map, Value};
+
+use crate::geojsons::{Feature, FeatureCollection};
+use crate::lambda::GeoVar;
+use crate::merge::wrap_merge;
+use crate::topojsons::{Geometry, TopoJSON};
+
+/// Grouping key of `dissolve`: either the name of a property or an
+/// expression evaluated on each geometry such as `var()["id"].int() / 1000`.
+#[derive(FromPyObject)]
+pub enum By<'py> {
+    Property(String),
+    Expression(PyRef<'py, GeoVar>),
+}
+
+impl By<'_> {
+    fn name(&self) -> &str {
+        match self {
+            By::Property(name) => name,
+            By::Expression(_) => "key",
+        }
+    }
+
+    fn key(
+        &self,
+        topology: &TopoJSON,
+        o: &Geometry,
+        properties: &Map<String, Value>,
+    ) -> PyResult<Value> {
+        match self {
+            By::Property(name) => Ok(properties.get(name).cloned().unwrap_or(Value::Null)),
+            By::Expression(geo_var) => Ok(geo_var.evaluate(topology, o)?.into_json()),
+        }
+    }
+}
+
+enum Aggregation {
+    Sum,
+    First,
+    Count,
+}
+
+impl TryFrom<&str> for Aggregation {
+    type Error = PyErr;
+
+    fn try_from(value: &str) -> PyResult<Self> {
+        match value {
+            "sum" => Ok(Self::Sum),
+            "first" => Ok(Self::First),
+            "count" => Ok(Self::Count),
+            _ => Err(PyValueError::new_err(format!(
+                "Unknown aggregation {:?}, expected 'sum', 'first' or 'count'",
+                value
+            ))),
+        }
+    }
+}
+
+impl Aggregation {
+    /// Aggregates the non-null values of the property `name` of a group.
+    fn call<'a, I>(&self, name: &str, values: I) -> PyResult<Value>
+    where
+        I: Iterator<Item = &'a Value>,
+    {
+        let mut values = values.filter(|value| !value.is_null());
+        match self {
+            Self::First => Ok(values.next().cloned().unwrap_or(Value::Null)),
+            Self::Count => Ok(Value::from(values.count())),
+            Self::Sum => {
+                let mut int = Some(0i64);
+                let mut float = 0.;
+                for value in values {
+                    let Value::Number(number) = value else {
+                        return Err(PyTypeError::new_err(format!(
+                            "Cannot sum the non-numeric property '{}'",
+                            name
+                        )));
+                    };
+                    int = int.zip(number.as_i64()).and_then(|(a, b)| a.checked_add(b));
+                    float += number.as_f64().unwrap_or(f64::NAN);
+                }
+                Ok(int.map(Value::from).unwrap_or(Value::from(float)))
+            }
+        }
+    }
+}
+
+pub fn wrap_dissolve(
+    topology: &TopoJSON,
+    objects: &[&Geometry],
+    by: &By,
+    aggregate: &HashMap<String, String>,
+    spherical: bool,
+) -> PyResult<FeatureCollection> {
+    // The key is stored under `by.name()`, which an aggregation would overwrite.
+    if aggregate.contains_key(by.name()) {
+        return Err(PyValueError::new_err(format!(
+            "Cannot aggregate the property '{}' storing the key",
+            by.name()
+        )));
+    }
+    let aggregate = aggregate
+        .iter()
+        .map(|(name, aggregation)| Ok((name, Aggregation::try_from(aggregation.as_str())?)))
+        .collect::<PyResult<Vec<_>>>()?;
+
+    let mut indexes: HashMap<String, usize> = HashMap::new();
+    let mut groups: Vec<Group> = Vec::new();
+    for o in objects {
+        let properties = properties(o);
+        let key = by.key(topology, o, &properties)?;
+        let index = *indexes.entry(key.to_string()).or_insert_with(|| {
+            groups.push(Group::new(key));
+            groups.len() - 1
+        });
+        groups[index].objects.push(o);
+        groups[index].properties.push(properties);
+    }
+
+    let features = groups
+        .into_iter()
+        .map(|group| {
+            let mut properties = aggregate
+                .iter()
+                .map(|(name, aggregation)| {
+                    let values = group.properties.iter().filter_map(|p| p.get(*name));
+                    Ok((name.to_string(), aggregation.call(name, values)?))
+                })
+                .collect::<PyResult<Map<String, Value>>>()?;
+            properties.insert(by.name().to_string(), group.key.clone());
+            Ok(Feature {
+                properties: Some(Value::Object(properties).to_string()),
+                geometry: wrap_merge(topology, &group.objects, spherical),
+                id: match group.key {
+                    Value::Null => None,
+                    Value::String(id) => Some(id),
+                    key => Some(key.to_string()),
+                },
+                bbox: None,
+            })
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+
+    Ok(FeatureCollection { features })
+}
+
+struct Group<'a> {
+    key: Value,
+    objects: Vec<&'a Geometry>,
+    properties: Vec<Map<String, Value>>,
+}
+
+impl Group<'_> {
+    fn new(key: Value) -> Self {
+        Self {
+            key,
+            objects: Vec::new(),
+            properties: Vec::new(),
+        }
+    }
+}
+
+fn properties(o: &Geometry) -> Map<String, Value> {
+    match o
+        .properties()
+        .and_then(|properties| serde_json::from_str(&properties).ok())
+    {
+        Some(Value::Object(properties)) => properties,
+        _ => Map::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geojsons::FeatureGeometryType;
+
+    use super::*;
+
+    fn polygon(arcs: Vec<i32>, id: &str, properties: &str) -> Geometry {
+        Geometry::Polygon {
+            arcs: vec![arcs],
+            id: Some(id.to_string()),
+            properties: Some(properties.to_string()),
+            bbox: None,
+        }
+    }
+
+    //
+    // +-----+-----+-----+
+    // |     |     |     |
+    // |  A  |  A  |  B  |
+    // |     |     |     |
+    // +-----+-----+-----+
+    //
+    fn simple_topology() -> TopoJSON {
+        TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: HashMap::from_iter([(
+                "collection".to_string(),
+                Geometry::GeometryCollection {
+                    geometries: vec![
+                        polygon(vec![2, 0], "1001", r#"{"STATE":"A","POP":1,"NAME":"a"}"#),
+                        polygon(vec![3, 1, 4, -1], "1003", r#"{"STATE":"A","POP":2}"#),
+                        polygon(vec![5, 1], "2001", r#"{"STATE":"B","POP":2.5,"NAME":"c"}"#),
+                    ],
+                    id: None,
+                    properties: None,
+                    bbox: None,
+                },
+            )]),
+            arcs: vec![
+                vec![[1., 0.], [1., 1.]],
+                vec![[2., 0.], [2., 1.]],
+                vec![[1., 1.], [0., 1.], [0., 0.], [1., 0.]],
+                vec![[1., 0.], [2., 0.]],
+                vec![[2., 1.], [1., 1.]],
+                vec![[2., 1.], [3., 1.], [3., 0.], [2., 0.]],
+            ],
+        }
+    }
+
+    fn objects(topology: &TopoJSON) -> Vec<&Geometry> {
+        match &topology.objects["collection"] {
+            Geometry::GeometryCollection { geometries, .. } => geometries.iter().collect(),
+            _ => panic!("Topology must have a collection of geometries."),
+        }
+    }
+
+    fn properties(feature: &Feature) -> Value {
+        serde_json::from_str(feature.properties.as_ref().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_dissolve_1() {
+        let topology = simple_topology();
+        let aggregate = HashMap::from_iter([
+            ("POP".to_string(), "sum".to_string()),
+            ("NAME".to_string(), "first".to_string()),
+        ]);
+        let collection = wrap_dissolve(
+            &topology,
+            &objects(&topology),
+            &By::Property("STATE".to_string()),
+            &aggregate,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(collection.features.len(), 2);
+        let [a, b] = &collection.features[..] else {
+            unreachable!()
+        };
+        assert_eq!(a.id, Some("A".to_string()));
+        assert_eq!(
+            properties(a),
+            serde_json::json!({"STATE": "A", "POP": 3, "NAME": "a"})
+        );
+        assert_eq!(b.id, Some("B".to_string()));
+        assert_eq!(
+            properties(b),
+            serde_json::json!({"STATE": "B", "POP": 2.5, "NAME": "c"})
+        );
+        match &a.geometry {
+            FeatureGeometryType::MultiPolygon { coordinates } => {
+                assert_eq!(coordinates.len(), 1);
+                assert_eq!(coordinates[0].len(), 1);
+                assert_eq!(coordinates[0][0].len(), 7);
+            }
+            _ => panic!("Feature Geometry Type must be 'MultiPolygon'."),
+        }
+    }
+
+    #[test]
+    fn test_dissolve_2() {
+        Python::initialize();
+        let topology = simple_topology();
+        let aggregate = HashMap::from_iter([("NAME".to_string(), "count".to_string())]);
+        Python::attach(|py| {
+            let by = Bound::new(
+                py,
+                GeoVar::new()
+                    .item("id")
+                    .and_then(|v| v.int())
+                    .and_then(|v| v.__truediv__(&1000i64.into_pyobject(py).unwrap().into_any()))
+                    .unwrap(),
+            )
+            .unwrap();
+            let collection = wrap_dissolve(
+                &topology,
+                &objects(&topology),
+                &By::Expression(by.borrow()),
+                &aggregate,
+                false,
+            )
+            .unwrap();
+            assert_eq!(
+                collection
+                    .features
+                    .iter()
+                    .map(|feature| (feature.id.clone(), properties(feature)))
+                    .collect::<Vec<_>>(),
+                vec![
+                    (
+                        Some("1".to_string()),
+                        serde_json::json!({"key": 1, "NAME": 1})
+                    ),
+                    (
+                        Some("2".to_string()),
+                        serde_json::json!({"key": 2, "NAME": 1})
+                    ),
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn test_dissolve_3() {
+        Python::initialize();
+        let topology = simple_topology();
+        for (name, aggregation, error) in [
+            (
+                "POP",
+                "mean",
+                "ValueError: Unknown aggregation \"mean\", expected 'sum', 'first' or 'count'",
+            ),
+            (
+                "NAME",
+                "sum",
+                "TypeError: Cannot sum the non-numeric property 'NAME'",
+            ),
+            (
+                "STATE",
+                "first",
+                "ValueError: Cannot aggregate the property 'STATE' storing the key",
+            ),
+        ] {
+            let aggregate = HashMap::from_iter([(name.to_string(), aggregation.to_string())]);
+            assert_eq!(
+                wrap_dissolve(
+                    &topology,
+                    &objects(&topology),
+                    &By::Property("STATE".to_string()),
+                    &aggregate,
+                    false,
+                )
+                .err()
+                .map(|e| e.to_string()),
+                Some(error.to_string())
+            );
+        }
+    }
+}
@@ -23,6 +23,38 @@ pub fn object_func(topology: &TopoJSON, o: &Geometry) -> FeatureGeometryType {
     }
 }
 
+/// Decoded positions of the arc `i`, in the direction of the arc.
+pub(crate) fn decode_arc(topology: &TopoJSON, i: usize) -> Vec<[f64; 2]> {
+    let line = Geometry::LineString {
+        arcs: vec![i as i32],
+        id: None,
+        properties: None,
+        bbox: None,
+    };
+    match object_func(topology, &line) {
+        FeatureGeometryType::LineString { coordinates } => coordinates,
+        _ => unreachable!(
+            "Object function with 'Geometry::LineString' must return 'FeatureGeometryType::LineString'"
+        ),
+    }
+}
+
+/// Decoded positions of a ring made of the arcs `ring`.
+pub(crate) fn decode_ring(topology: &TopoJSON, ring: &[i32]) -> Vec<[f64; 2]> {
+    let polygon = Geometry::Polygon {
+        arcs: vec![ring.to_vec()],
+        id: None,
+        properties: None,
+        bbox: None,
+    };
+    match object_func(topology, &polygon) {
+        FeatureGeometryType::Polygon { mut coordinates } => coordinates.swap_remove(0),
+        _ => unreachable!(
+            "Object function with 'Geometry::Polygon' must return 'FeatureGeometryType::Polygon'"
+        ),
+    }
+}
+
 fn feature_item(topology: &TopoJSON, o: &Geometry) -> Feature {
     let geometry = object_func(topology, o);
     let id = o.id();
@@ -40,7 +72,7 @@ pub struct Object<'a, T>
 where
     T: Transformer,
 {
-    arcs: &'a Vec<Vec<[i32; 2]>>,
+    arcs: &'a Vec<Vec<[f64; 2]>>,
     transformer: T,
 }
 
@@ -59,7 +91,7 @@ impl<'a, T: Transformer> Object<'a, T> {
         }
         let a = &self.arcs[if i < 0 { !i } else { i } as usize];
         for (k, arc) in a.iter().enumerate() {
-            points.push(self.transformer.call(&arc.map(|x| x as f64), k));
+            points.push(self.transformer.call(arc, k));
         }
         if i < 0 {
             reverse(points, a.len());
@@ -127,7 +159,7 @@ impl<'a, T: Transformer> Object<'a, T> {
 
 #[cfg(test)]
 mod tests {
-    use crate::topojsons::Transform;
+    use crate::topojsons::{Transform, quantized_arcs};
     use std::collections::HashMap;
 
     use super::*;
@@ -140,13 +172,13 @@ mod tests {
                 translate: [0., 0.],
             }),
             objects: HashMap::from_iter([("foo".to_string(), object)]),
-            arcs: vec![
+            arcs: quantized_arcs(vec![
                 vec![[0, 0], [1, 0], [0, 1], [-1, 0], [0, -1]],
                 vec![[0, 0], [1, 0], [0, 1]],
                 vec![[1, 1], [-1, 0], [0, -1]],
                 vec![[1, 1]],
                 vec![[0, 0]],
-            ],
+            ]),
         }
     }
 
@@ -369,7 +401,7 @@ mod tests {
                     },
                 ),
             ]),
-            arcs: vec![vec![[0, 0], [1, 1]], vec![[1, 1], [-1, -1]]],
+            arcs: quantized_arcs(vec![vec![[0, 0], [1, 1]], vec![[1, 1], [-1, -1]]]),
         };
 
         if let GeoJSON::Feature(feature) = wrap_feature(&topology, &topology.objects["foo"]) {
@@ -0,0 +1,318 @@
+use pyo3::prelude::*;
+use rustc_hash::FxHashMap;
+
+use crate::lambda::GeoVar;
+use crate::topojsons::{Geometry, TopoJSON};
+
+/// Keeps the geometries for which the single-variable `predicate`, such as
+/// `var()["id"].startswith("06")`, is true.
+pub fn wrap_filter(
+    topology: &TopoJSON,
+    objects: &[&Geometry],
+    predicate: &GeoVar,
+) -> PyResult<Geometry> {
+    let mut geometries = Vec::new();
+    for o in objects {
+        if predicate.matches(topology, o)? {
+            geometries.push((*o).clone());
+        }
+    }
+    Ok(Geometry::GeometryCollection {
+        geometries,
+        id: None,
+        properties: None,
+        bbox: None,
+    })
+}
+
+/// Keeps the members of `collection` for which `predicate` is true. The id and
+/// the properties of the collection are kept, as well as its bbox when no
+/// member is dropped.
+pub fn wrap_filter_collection(
+    topology: &TopoJSON,
+    collection: &Geometry,
+    predicate: &GeoVar,
+) -> PyResult<Geometry> {
+    let members = collection.members();
+    let mut geometry = wrap_filter(topology, &members, predicate)?;
+    if let (
+        Geometry::GeometryCollection {
+            geometries,
+            id,
+            properties,
+            bbox,
+        },
+        Geometry::GeometryCollection {
+            id: source_id,
+            properties: source_properties,
+            bbox: source_bbox,
+            ..
+        },
+    ) = (&mut geometry, collection)
+    {
+        *id = source_id.clone();
+        *properties = source_properties.clone();
+        if geometries.len() == members.len() {
+            *bbox = source_bbox.clone();
+        }
+    }
+    Ok(geometry)
+}
+
+/// Copy of `topology` where the object `key` is replaced by `geometry`. The
+/// arcs no longer referenced by any object are dropped and the references to
+/// the other ones are renumbered.
+pub fn wrap_filter_topology(topology: &TopoJSON, key: &str, geometry: Geometry) -> TopoJSON {
+    let mut objects = topology.objects.clone();
+    objects.insert(key.to_string(), geometry);
+
+    let mut used = vec![false; topology.arcs.len()];
+    objects.values_mut().for_each(|o| {
+        for_each_arc(o, &mut |a| {
+            used[if *a < 0 { !*a } else { *a } as usize] = true
+        })
+    });
+    let mut indexes: FxHashMap<i32, i32> = FxHashMap::default();
+    let mut arcs = Vec::new();
+    for (i, arc) in topology.arcs.iter().enumerate() {
+        if used[i] {
+            indexes.insert(i as i32, arcs.len() as i32);
+            arcs.push(arc.clone());
+        }
+    }
+    objects.values_mut().for_each(|o| {
+        for_each_arc(o, &mut |a| {
+            *a = if *a < 0 { !indexes[&!*a] } else { indexes[a] }
+        })
+    });
+
+    TopoJSON {
+        bbox: topology.bbox.clone(),
+        transform: topology.transform.clone(),
+        objects,
+        arcs,
+    }
+}
+
+fn for_each_arc<F: FnMut(&mut i32)>(o: &mut Geometry, f: &mut F) {
+    match o {
+        Geometry::GeometryCollection { geometries, .. } => {
+            geometries.iter_mut().for_each(|o| for_each_arc(o, f))
+        }
+        Geometry::Point { .. } | Geometry::MultiPoint { .. } => {}
+        Geometry::LineString { arcs, .. } => arcs.iter_mut().for_each(f),
+        Geometry::MultiLineString { arcs, .. } | Geometry::Polygon { arcs, .. } => {
+            arcs.iter_mut().flatten().for_each(f)
+        }
+        Geometry::MultiPolygon { arcs, .. } => arcs.iter_mut().flatten().flatten().for_each(f),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::lambda::Value;
+
+    use super::*;
+
+    fn topology() -> TopoJSON {
+        TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: Default::default(),
+            arcs: Vec::new(),
+        }
+    }
+
+    fn county(id: &str, properties: &str) -> Geometry {
+        Geometry::Polygon {
+            arcs: vec![],
+            id: Some(id.to_string()),
+            properties: Some(properties.to_string()),
+            bbox: None,
+        }
+    }
+
+    #[test]
+    fn test_filter_1() {
+        Python::initialize();
+        let objects = [
+            county("06001", r#"{"NAME":"Alameda"}"#),
+            county("06003", r#"{"NAME":"Alpine"}"#),
+            county("32001", r#"{"NAME":"Churchill"}"#),
+        ];
+        let objects = objects.iter().collect::<Vec<_>>();
+        let ids = |predicate: GeoVar| match wrap_filter(&topology(), &objects, &predicate).unwrap()
+        {
+            Geometry::GeometryCollection { geometries, .. } => geometries
+                .iter()
+                .map(|o| o.id().unwrap())
+                .collect::<Vec<_>>(),
+            _ => panic!("Geometry must be a 'GeometryCollection'."),
+        };
+
+        assert_eq!(
+            ids(GeoVar::new()
+                .item("id")
+                .unwrap()
+                .startswith("06".to_string())
+                .unwrap()),
+            vec!["06001", "06003"]
+        );
+        assert_eq!(
+            ids(GeoVar::new()
+                .item("properties")
+                .and_then(|v| v.item("NAME"))
+                .and_then(|v| v.len())
+                .unwrap()
+                .__gt__(Value::Int(6).into())),
+            vec!["06001", "32001"]
+        );
+        assert!(ids(GeoVar::new().item("id").unwrap().__eq__(Value::Null.into())).is_empty());
+    }
+
+    #[test]
+    fn test_filter_2() {
+        Python::initialize();
+        let objects = [county("06001", r#"{"NAME":"Alameda"}"#)];
+        let predicate = GeoVar::new()
+            .item("properties")
+            .and_then(|v| v.item("NAME"))
+            .and_then(|v| v.int())
+            .unwrap();
+        assert_eq!(
+            wrap_filter(&topology(), &objects.iter().collect::<Vec<_>>(), &predicate)
+                .err()
+                .map(|e| e.to_string()),
+            Some("ValueError: invalid literal for int() with base 10: \"Alameda\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_filter_3() {
+        Python::initialize();
+        let polygon = |arcs: Vec<i32>, id: &str| Geometry::Polygon {
+            arcs: vec![arcs],
+            id: Some(id.to_string()),
+            properties: None,
+            bbox: None,
+        };
+        let arcs = vec![
+            vec![[0., 0.], [1., 0.], [0., 1.], [-1., 0.], [0., -1.]],
+            vec![[0., 2.], [3., 0.]],
+            vec![[2., 0.], [1., 0.], [0., 1.], [-1., 0.], [0., -1.]],
+        ];
+        let topology = TopoJSON {
+            bbox: vec![0., 0., 3., 2.],
+            transform: None,
+            objects: HashMap::from_iter([
+                (
+                    "counties".to_string(),
+                    Geometry::GeometryCollection {
+                        geometries: vec![polygon(vec![0], "06001"), polygon(vec![-3], "32001")],
+                        id: None,
+                        properties: None,
+                        bbox: None,
+                    },
+                ),
+                (
+                    "rivers".to_string(),
+                    Geometry::LineString {
+                        arcs: vec![1],
+                        id: None,
+                        properties: None,
+                        bbox: None,
+                    },
+                ),
+            ]),
+            arcs: arcs.clone(),
+        };
+        let Geometry::GeometryCollection { geometries, .. } = &topology.objects["counties"] else {
+            unreachable!()
+        };
+        let predicate = GeoVar::new()
+            .item("id")
+            .unwrap()
+            .startswith("32".to_string())
+            .unwrap();
+        let geometry = wrap_filter(
+            &topology,
+            &geometries.iter().collect::<Vec<_>>(),
+            &predicate,
+        )
+        .unwrap();
+        let filtered = wrap_filter_topology(&topology, "counties", geometry);
+
+        // The arc of "06001" is dropped, the other objects are kept.
+        assert_eq!(filtered.arcs, arcs[1..].to_vec());
+        assert_eq!(
+            filtered.objects["counties"],
+            Geometry::GeometryCollection {
+                geometries: vec![polygon(vec![-2], "32001")],
+                id: None,
+                properties: None,
+                bbox: None,
+            }
+        );
+        assert_eq!(
+            filtered.objects["rivers"],
+            Geometry::LineString {
+                arcs: vec![0],
+                id: None,
+                properties: None,
+                bbox: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_filter_4() {
+        Python::initialize();
+        let collection = Geometry::GeometryCollection {
+            geometries: vec![
+                county("06001", r#"{"NAME":"Alameda"}"#),
+                county("32001", r#"{"NAME":"Churchill"}"#),
+            ],
+            id: Some("counties".to_string()),
+            properties: Some(r#"{"year":2020}"#.to_string()),
+            bbox: Some(vec![0., 0., 3., 2.]),
+        };
+        let filter = |prefix: &str| {
+            let predicate = GeoVar::new()
+                .item("id")
+                .unwrap()
+                .startswith(prefix.to_string())
+                .unwrap();
+            match wrap_filter_collection(&topology(), &collection, &predicate).unwrap() {
+                Geometry::GeometryCollection {
+                    geometries,
+                    id,
+                    properties,
+                    bbox,
+                } => (geometries.len(), id, properties, bbox),
+                _ => panic!("Geometry must be a 'GeometryCollection'."),
+            }
+        };
+
+        // The bbox is kept only when no member is dropped.
+        assert_eq!(
+            filter("06"),
+            (
+                1,
+                Some("counties".to_string()),
+                Some(r#"{"year":2020}"#.to_string()),
+                None
+            )
+        );
+        assert_eq!(
+            filter(""),
+            (
+                2,
+                Some("counties".to_string()),
+                Some(r#"{"year":2020}"#.to_string()),
+                Some(vec![0., 0., 3., 2.])
+            )
+        );
+    }
+}
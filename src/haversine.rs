@@ -0,0 +1,40 @@
+/// Mean radius of the Earth in metres.
+pub const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Great-circle distance in metres between two positions `[longitude,
+/// latitude]` expressed in degrees.
+pub fn haversine(a: &[f64; 2], b: &[f64; 2]) -> f64 {
+    let phi0 = a[1].to_radians();
+    let phi1 = b[1].to_radians();
+    let sin_dphi = ((phi1 - phi0) / 2.).sin();
+    let sin_dlambda = ((b[0] - a[0]).to_radians() / 2.).sin();
+    let h = sin_dphi * sin_dphi + phi0.cos() * phi1.cos() * sin_dlambda * sin_dlambda;
+    2. * EARTH_RADIUS * h.sqrt().min(1.).asin()
+}
+
+/// Returns `true` when the bounding box `[x0, y0, x1, y1]` can describe
+/// longitudes and latitudes.
+pub fn is_geographic(bbox: &[f64]) -> bool {
+    bbox.len() >= 4 && bbox[0] >= -180. && bbox[2] <= 180. && bbox[1] >= -90. && bbox[3] <= 90.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_haversine_1() {
+        assert_eq!(haversine(&[10., 20.], &[10., 20.]), 0.);
+        let d = haversine(&[0., 0.], &[1., 0.]);
+        assert!((d - 111_195.08).abs() < 0.01, "{d}");
+        let d = haversine(&[179.5, 0.], &[-179.5, 0.]);
+        assert!((d - 111_195.08).abs() < 0.01, "{d}");
+    }
+
+    #[test]
+    fn test_is_geographic_1() {
+        assert!(is_geographic(&[-180., -90., 180., 90.]));
+        assert!(!is_geographic(&[0., 0., 960., 600.]));
+        assert!(!is_geographic(&[]));
+    }
+}
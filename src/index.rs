@@ -0,0 +1,214 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use serde::{Deserialize, Serialize};
+
+use crate::feature::object_func;
+use crate::geojsons::FeatureGeometryType;
+use crate::rtree::RTree;
+use crate::topojsons::{Geometry, TopoJSON};
+
+type Polygon = Vec<Vec<[f64; 2]>>;
+
+/// Spatial index over the polygons of the geometries of an object, which
+/// locates the geometry containing a position. The index is built by
+/// `TopoJSON.index`, holds the decoded polygons and can be pickled.
+#[pyclass(module = "topojson")]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpatialIndex {
+    tree: RTree,
+    polygons: Vec<Vec<Polygon>>,
+    ids: Vec<Option<String>>,
+}
+
+#[pymethods]
+impl SpatialIndex {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index and id of the first geometry containing `(x, y)`.
+    pub fn locate(&self, x: f64, y: f64) -> Option<(usize, Option<String>)> {
+        let mut candidates = self.tree.search([x, y, x, y]);
+        candidates.sort_unstable();
+        candidates
+            .into_iter()
+            .find(|&i| self.polygons[i].iter().any(|p| contains(p, [x, y])))
+            .map(|i| (i, self.ids[i].clone()))
+    }
+
+    pub fn locate_many(&self, points: Vec<[f64; 2]>) -> Vec<Option<(usize, Option<String>)>> {
+        points.iter().map(|&[x, y]| self.locate(x, y)).collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.polygons.len()
+    }
+
+    fn __getstate__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let state = serde_json::to_vec(self).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(PyBytes::new(py, &state))
+    }
+
+    fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        *self = serde_json::from_slice(state).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Indexes the polygons of the geometries of `object`, or of `object` itself
+/// when it is not a collection. Other geometries are never located.
+pub fn wrap_index(topology: &TopoJSON, object: &Geometry) -> SpatialIndex {
+    let members = object.members();
+    let polygons: Vec<Vec<Polygon>> = members
+        .iter()
+        .map(|o| {
+            let mut polygons = Vec::new();
+            collect_polygons(object_func(topology, o), &mut polygons);
+            polygons
+        })
+        .collect();
+    let items = polygons
+        .iter()
+        .enumerate()
+        .filter(|(_, polygons)| !polygons.is_empty())
+        .map(|(i, polygons)| (bbox(polygons), i))
+        .collect();
+    SpatialIndex {
+        tree: RTree::new(items),
+        polygons,
+        ids: members.iter().map(|o| o.id()).collect(),
+    }
+}
+
+fn collect_polygons(geometry: FeatureGeometryType, polygons: &mut Vec<Polygon>) {
+    match geometry {
+        FeatureGeometryType::GeometryCollection { geometries } => geometries
+            .into_iter()
+            .for_each(|geometry| collect_polygons(geometry, polygons)),
+        FeatureGeometryType::Polygon { coordinates } => polygons.push(coordinates),
+        FeatureGeometryType::MultiPolygon { coordinates } => polygons.extend(coordinates),
+        _ => (),
+    }
+}
+
+fn bbox(polygons: &[Polygon]) -> [f64; 4] {
+    polygons
+        .iter()
+        .flat_map(|polygon| polygon.first())
+        .flatten()
+        .fold(
+            [f64::INFINITY, f64::INFINITY, -f64::INFINITY, -f64::INFINITY],
+            |b, p| {
+                [
+                    b[0].min(p[0]),
+                    b[1].min(p[1]),
+                    b[2].max(p[0]),
+                    b[3].max(p[1]),
+                ]
+            },
+        )
+}
+
+/// Whether the polygon contains `p` with the even-odd rule, holes included.
+/// Positions on the boundary may be considered inside or outside.
+fn contains(polygon: &Polygon, p: [f64; 2]) -> bool {
+    let mut inside = false;
+    for ring in polygon {
+        let Some(mut a) = ring.last() else {
+            continue;
+        };
+        for b in ring {
+            if (a[1] > p[1]) != (b[1] > p[1])
+                && p[0] < (b[0] - a[0]) * (p[1] - a[1]) / (b[1] - a[1]) + a[0]
+            {
+                inside = !inside;
+            }
+            a = b;
+        }
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    //
+    // +-----+-----+
+    // | A   | B   |   B has a hole around (1.5, 0.5), C is a point
+    // +-----+-----+
+    //
+    fn topology() -> (TopoJSON, Geometry) {
+        let topology = TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: HashMap::new(),
+            arcs: vec![
+                vec![[1., 0.], [1., 1.]],
+                vec![[1., 1.], [0., 1.], [0., 0.], [1., 0.]],
+                vec![[1., 0.], [2., 0.], [2., 1.], [1., 1.]],
+                vec![[1.4, 0.4], [1.4, 0.6], [1.6, 0.6], [1.6, 0.4], [1.4, 0.4]],
+            ],
+        };
+        let collection = Geometry::GeometryCollection {
+            geometries: vec![
+                Geometry::Polygon {
+                    arcs: vec![vec![0, 1]],
+                    id: Some("A".to_string()),
+                    properties: None,
+                    bbox: None,
+                },
+                Geometry::Polygon {
+                    arcs: vec![vec![2, -1], vec![3]],
+                    id: None,
+                    properties: None,
+                    bbox: None,
+                },
+                Geometry::Point {
+                    coordinates: [0.5, 0.5],
+                    id: Some("C".to_string()),
+                    properties: None,
+                    bbox: None,
+                },
+            ],
+            id: None,
+            properties: None,
+            bbox: None,
+        };
+        (topology, collection)
+    }
+
+    #[test]
+    fn test_index_1() {
+        let (topology, collection) = topology();
+        let index = wrap_index(&topology, &collection);
+        assert_eq!(index.locate(0.5, 0.5), Some((0, Some("A".to_string()))));
+        assert_eq!(index.locate(1.2, 0.8), Some((1, None)));
+        assert_eq!(index.locate(1.5, 0.5), None);
+        assert_eq!(index.locate(3., 0.5), None);
+        assert_eq!(
+            index.locate_many(vec![[0.1, 0.9], [1.9, 0.1], [-1., -1.]]),
+            vec![Some((0, Some("A".to_string()))), Some((1, None)), None]
+        );
+        assert_eq!(SpatialIndex::new().locate(0.5, 0.5), None);
+    }
+
+    #[test]
+    fn test_index_2() -> PyResult<()> {
+        let (topology, collection) = topology();
+        let index = wrap_index(&topology, &collection);
+        Python::initialize();
+        Python::attach(|py| {
+            let state = index.__getstate__(py)?;
+            let mut restored = SpatialIndex::new();
+            restored.__setstate__(state.as_bytes())?;
+            assert_eq!(restored, index);
+            assert!(restored.__setstate__(b"{").is_err());
+            Ok(())
+        })
+    }
+}
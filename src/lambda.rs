@@ -27,32 +27,68 @@
 // )
 // ```
 
-use std::num::{ParseFloatError, ParseIntError};
-use std::slice::Iter;
-
-use crate::topojsons::Geometry;
-use pyo3::exceptions::{PyRuntimeError, PyTypeError, PyValueError, PyZeroDivisionError};
+use std::cmp::Ordering;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+use crate::feature::object_func;
+use crate::geojsons::FeatureGeometryType;
+use crate::merge::planar_ring_area;
+use crate::topojsons::{Geometry, TopoJSON};
+use pyo3::exceptions::{
+    PyAttributeError, PyIndexError, PyKeyError, PyRuntimeError, PyTypeError, PyValueError,
+    PyZeroDivisionError,
+};
 use pyo3::prelude::*;
+use pyo3::types::{PyBool, PySlice};
+use rustc_hash::FxHashMap;
+
+/// Identifiers of the variables bound explicitly by `var(0)` and `var(1)`.
+const POSITIONS: usize = 2;
+
+/// Identifier of the next variable created by `var()`, which only identifies
+/// it: the binding of such a variable depends on the expression it is used in.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(POSITIONS);
 
 #[derive(Debug, Clone)]
 pub enum GeoVarEnum {
-    NoChange,
-    Ops(Vec<Ops>),
+    NoChange(usize),
+    Ops(usize, Vec<Ops>),
+    Literal(Value),
     Eq([Box<GeoVarEnum>; 2]),
     Neq([Box<GeoVarEnum>; 2]),
+    Lt([Box<GeoVarEnum>; 2]),
+    Le([Box<GeoVarEnum>; 2]),
+    Gt([Box<GeoVarEnum>; 2]),
+    Ge([Box<GeoVarEnum>; 2]),
     And([Box<GeoVarEnum>; 2]),
     Or([Box<GeoVarEnum>; 2]),
+    Min([Box<GeoVarEnum>; 2]),
+    Max([Box<GeoVarEnum>; 2]),
+    Add([Box<GeoVarEnum>; 2]),
+    Sub([Box<GeoVarEnum>; 2]),
+    Mul([Box<GeoVarEnum>; 2]),
+    Div([Box<GeoVarEnum>; 2]),
+    Mod([Box<GeoVarEnum>; 2]),
+    FloorDiv([Box<GeoVarEnum>; 2]),
+    Not(Box<GeoVarEnum>),
+    /// Operations applied to the result of an expression, as in
+    /// `abs(a["x"] - b["x"])`.
+    Apply(Box<GeoVarEnum>, Vec<Ops>),
 }
 
 #[pyclass]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Transform {
     AsI64,
     AsF64,
+    AsStr,
     Length,
+    Lower,
+    Upper,
+    Abs,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Ops {
     ItemGetter(String),
     Transform(Transform),
@@ -64,58 +100,442 @@ pub enum Ops {
     MulF64(f64),
     DivI64(i64),
     DivF64(f64),
+    ModI64(i64),
+    ModF64(f64),
+    FloorDivI64(i64),
+    FloorDivF64(f64),
+    AddStr(String),
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, i64),
+    StartsWith(String),
+    EndsWith(String),
+    IsIn(Vec<Value>),
 }
 
-impl GeoVarEnum {
-    fn compare(&self, geom1: &Geometry, geom2: &Geometry) -> PyResult<Value> {
+/// Geometries bound to the variables of an expression: `var(0)` and `var(1)`
+/// are bound to the first and the second geometry. Unless `var(0)` is used,
+/// the other variable appearing first in the expression is bound to the first
+/// geometry, and the remaining ones to the second.
+struct Bindings<'a> {
+    first: Option<usize>,
+    geometries: [&'a Geometry; 2],
+    topology: &'a TopoJSON,
+}
+
+impl<'a> Bindings<'a> {
+    fn get(&self, id: usize) -> &'a Geometry {
+        let first = match id {
+            0 => true,
+            1 => false,
+            id => self.first == Some(id),
+        };
+        self.geometries[if first { 0 } else { 1 }]
+    }
+}
+
+/// Result of an expression: a variable without any operation evaluates to
+/// its geometry.
+enum Evaluated<'a> {
+    Geometry(&'a Geometry),
+    Value(Value),
+}
+
+impl Evaluated<'_> {
+    fn is_truthy(&self) -> bool {
         match self {
-            Self::Eq(vars) => {
-                Self::cmp_vars(vars, geom1, geom2, |g1, g2| g1 == g2, |v1, v2| v1 == v2)
+            Self::Geometry(_) => true,
+            Self::Value(value) => value.is_truthy(),
+        }
+    }
+
+    fn into_value(self, symbol: &str, other: &Self) -> PyResult<Value> {
+        match self {
+            Self::Value(value) => Ok(value),
+            Self::Geometry(_) => Err(PyTypeError::new_err(format!(
+                "'{}' not supported between instances of 'Geometry' and '{}'",
+                symbol,
+                other.value_type()
+            ))),
+        }
+    }
+
+    /// Value on which the operations of `GeoVarEnum::Apply` are applied.
+    fn into_operand(self) -> PyResult<Value> {
+        match self {
+            Self::Value(value) => Ok(value),
+            Self::Geometry(_) => Err(PyTypeError::new_err(
+                "Operations cannot be applied to a 'Geometry'.",
+            )),
+        }
+    }
+
+    fn value_type(&self) -> &str {
+        match self {
+            Self::Geometry(_) => "Geometry",
+            Self::Value(value) => value.value_type(),
+        }
+    }
+}
+
+/// Binary operator of an expression, shared by the interpreter and the
+/// compiled `Plan` so that both have the same semantics.
+#[derive(Debug, Clone, Copy)]
+enum Binary {
+    Eq,
+    Neq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+    Min,
+    Max,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    FloorDiv,
+}
+
+impl Binary {
+    fn call(self, v1: Evaluated, v2: Evaluated) -> PyResult<Value> {
+        Ok(match self {
+            Self::Eq => Value::Bool(Self::equal(v1, v2)),
+            Self::Neq => Value::Bool(!Self::equal(v1, v2)),
+            Self::Lt => Value::Bool(Self::order(v1, v2, "<")?.is_some_and(|o| o.is_lt())),
+            Self::Le => Value::Bool(Self::order(v1, v2, "<=")?.is_some_and(|o| o.is_le())),
+            Self::Gt => Value::Bool(Self::order(v1, v2, ">")?.is_some_and(|o| o.is_gt())),
+            Self::Ge => Value::Bool(Self::order(v1, v2, ">=")?.is_some_and(|o| o.is_ge())),
+            Self::And => Value::Bool(v1.is_truthy() && v2.is_truthy()),
+            Self::Or => Value::Bool(v1.is_truthy() || v2.is_truthy()),
+            Self::Min => {
+                let [v1, v2] = Self::values(v1, v2, "<")?;
+                match v2.cmp_py(&v1, "<")? {
+                    Some(Ordering::Less) => v2,
+                    _ => v1,
+                }
+            }
+            Self::Max => {
+                let [v1, v2] = Self::values(v1, v2, ">")?;
+                match v2.cmp_py(&v1, ">")? {
+                    Some(Ordering::Greater) => v2,
+                    _ => v1,
+                }
+            }
+            Self::Add => match Self::values(v1, v2, "+")? {
+                [v1, Value::Str(v2)] => v1.add_str(&v2)?,
+                [v1, v2] => v1.arithmetic(v2, "+", Value::add_i64, Value::add_f64)?,
+            },
+            Self::Sub => {
+                let [v1, v2] = Self::values(v1, v2, "-")?;
+                v1.arithmetic(v2, "-", Value::sub_i64, Value::sub_f64)?
+            }
+            Self::Mul => {
+                let [v1, v2] = Self::values(v1, v2, "*")?;
+                v1.arithmetic(v2, "*", Value::mul_i64, Value::mul_f64)?
+            }
+            Self::Div => {
+                let [v1, v2] = Self::values(v1, v2, "/")?;
+                v1.arithmetic(v2, "/", Value::div_i64, Value::div_f64)?
             }
-            Self::Neq(vars) => {
-                Self::cmp_vars(vars, geom1, geom2, |g1, g2| g1 != g2, |v1, v2| v1 != v2)
+            Self::Mod => {
+                let [v1, v2] = Self::values(v1, v2, "%")?;
+                v1.arithmetic(v2, "%", Value::mod_i64, Value::mod_f64)?
             }
-            Self::And(vars) => {
-                Self::cmp_vars(vars, geom1, geom2, |_, _| true, |v1, v2| v1.and(&v2))
+            Self::FloorDiv => {
+                let [v1, v2] = Self::values(v1, v2, "//")?;
+                v1.arithmetic(v2, "//", Value::floor_div_i64, Value::floor_div_f64)?
             }
-            Self::Or(vars) => Self::cmp_vars(vars, geom1, geom2, |_, _| true, |v1, v2| v1.or(&v2)),
-            Self::NoChange => Ok(Value::Bool(true)),
-            Self::Ops(_) => Err(PyRuntimeError::new_err(
+        })
+    }
+
+    fn equal(v1: Evaluated, v2: Evaluated) -> bool {
+        match [v1, v2] {
+            [Evaluated::Geometry(g1), Evaluated::Geometry(g2)] => g1 == g2,
+            [Evaluated::Value(v1), Evaluated::Value(v2)] => v1 == v2,
+            _ => false,
+        }
+    }
+
+    fn order(v1: Evaluated, v2: Evaluated, symbol: &str) -> PyResult<Option<Ordering>> {
+        let [v1, v2] = Self::values(v1, v2, symbol)?;
+        v1.cmp_py(&v2, symbol)
+    }
+
+    fn values(v1: Evaluated, v2: Evaluated, symbol: &str) -> PyResult<[Value; 2]> {
+        if let Evaluated::Geometry(_) = v2 {
+            return Err(PyTypeError::new_err(format!(
+                "'{}' not supported between instances of '{}' and 'Geometry'",
+                symbol,
+                v1.value_type()
+            )));
+        }
+        let v1 = v1.into_value(symbol, &v2)?;
+        let Evaluated::Value(v2) = v2 else {
+            unreachable!()
+        };
+        Ok([v1, v2])
+    }
+}
+
+impl GeoVarEnum {
+    /// Checks that the expression compares two values rather than being a
+    /// single value.
+    fn distinct(&self) -> PyResult<()> {
+        match self {
+            Self::Ops(..) | Self::Literal(_) => Err(PyRuntimeError::new_err(
                 "Cannot compare geometries without two distinct values.",
             )),
+            _ => Ok(()),
         }
     }
 
-    fn cmp_vars<G, V>(
-        vars: &[Box<GeoVarEnum>; 2],
-        geom1: &Geometry,
-        geom2: &Geometry,
-        geom_fn: G,
-        value_fn: V,
-    ) -> PyResult<Value>
-    where
-        G: FnOnce(&Geometry, &Geometry) -> bool,
-        V: FnOnce(Value, Value) -> bool,
-    {
-        let [var1, var2] = vars;
-        match [var1.as_ref(), var2.as_ref()] {
-            [GeoVarEnum::NoChange, GeoVarEnum::NoChange] => Ok(Value::Bool(geom_fn(geom1, geom2))),
-            [GeoVarEnum::NoChange, _] | [_, GeoVarEnum::NoChange] => Ok(Value::Bool(false)),
-            [GeoVarEnum::Ops(ops1), GeoVarEnum::Ops(ops2)] => Ok(Value::Bool(value_fn(
-                geom1.process(ops1)?,
-                geom2.process(ops2)?,
-            ))),
-            _ => Ok(Value::Bool(value_fn(
-                var1.as_ref().compare(geom1, geom2)?,
-                var2.as_ref().compare(geom1, geom2)?,
-            ))),
+    fn binary(&self) -> Option<(Binary, &[Box<GeoVarEnum>; 2])> {
+        match self {
+            Self::Eq(vars) => Some((Binary::Eq, vars)),
+            Self::Neq(vars) => Some((Binary::Neq, vars)),
+            Self::Lt(vars) => Some((Binary::Lt, vars)),
+            Self::Le(vars) => Some((Binary::Le, vars)),
+            Self::Gt(vars) => Some((Binary::Gt, vars)),
+            Self::Ge(vars) => Some((Binary::Ge, vars)),
+            Self::And(vars) => Some((Binary::And, vars)),
+            Self::Or(vars) => Some((Binary::Or, vars)),
+            Self::Min(vars) => Some((Binary::Min, vars)),
+            Self::Max(vars) => Some((Binary::Max, vars)),
+            Self::Add(vars) => Some((Binary::Add, vars)),
+            Self::Sub(vars) => Some((Binary::Sub, vars)),
+            Self::Mul(vars) => Some((Binary::Mul, vars)),
+            Self::Div(vars) => Some((Binary::Div, vars)),
+            Self::Mod(vars) => Some((Binary::Mod, vars)),
+            Self::FloorDiv(vars) => Some((Binary::FloorDiv, vars)),
+            _ => None,
+        }
+    }
+
+    /// Identifiers of the variables of the expression, from left to right.
+    fn ids(&self, ids: &mut Vec<usize>) {
+        match self {
+            Self::NoChange(id) | Self::Ops(id, _) => ids.push(*id),
+            Self::Literal(_) => {}
+            Self::Not(var) | Self::Apply(var, _) => var.ids(ids),
+            _ => {
+                if let Some((_, [var1, var2])) = self.binary() {
+                    var1.ids(ids);
+                    var2.ids(ids);
+                }
+            }
+        }
+    }
+
+    /// Identifier of the variable bound to the first geometry without
+    /// `var(0)`: the one appearing first in the expression, so that the
+    /// binding does not depend on the order in which variables are created.
+    fn first_id(&self) -> Option<usize> {
+        let mut ids = Vec::new();
+        self.ids(&mut ids);
+        if ids.contains(&0) {
+            return None;
         }
+        ids.into_iter().find(|&id| id >= POSITIONS)
+    }
+
+    fn evaluate<'a>(&self, bindings: &Bindings<'a>) -> PyResult<Evaluated<'a>> {
+        let value = match self {
+            Self::NoChange(id) => return Ok(Evaluated::Geometry(bindings.get(*id))),
+            Self::Ops(id, ops) => bindings.get(*id).process(ops, bindings.topology)?,
+            Self::Literal(value) => value.clone(),
+            Self::Not(var) => Value::Bool(!var.evaluate(bindings)?.is_truthy()),
+            Self::Apply(var, ops) => var.evaluate(bindings)?.into_operand()?.process(ops)?,
+            _ => {
+                let (binary, [var1, var2]) = self.binary().expect("Binary operator expected");
+                binary.call(var1.evaluate(bindings)?, var2.evaluate(bindings)?)?
+            }
+        };
+        Ok(Evaluated::Value(value))
+    }
+}
+
+/// Step of a compiled expression, run on a stack: operators pop their
+/// operands and push their result.
+#[derive(Debug)]
+enum Step {
+    Geometry(usize),
+    Operand(usize, usize),
+    Literal(Value),
+    Not,
+    Apply(Vec<Ops>),
+    Binary(Binary),
+}
+
+/// Expression compiled once into a flat sequence of steps. The values of the
+/// keys such as `a["properties"]` and of the operands such as
+/// `a["properties"]["STATE"]` are memoized per geometry, so that a geometry
+/// is parsed or measured once whatever its number of arcs.
+pub(crate) struct Plan<'t> {
+    topology: &'t TopoJSON,
+    first: Option<usize>,
+    steps: Vec<Step>,
+    keys: Vec<String>,
+    operands: Vec<(usize, Vec<Ops>)>,
+    cache: FxHashMap<*const Geometry, Vec<Option<Value>>>,
+}
+
+impl<'t> Plan<'t> {
+    fn new(var: &GeoVarEnum, topology: &'t TopoJSON) -> PyResult<Self> {
+        var.distinct()?;
+        let mut plan = Self {
+            topology,
+            first: var.first_id(),
+            steps: Vec::new(),
+            keys: Vec::new(),
+            operands: Vec::new(),
+            cache: FxHashMap::default(),
+        };
+        plan.compile(var)?;
+        Ok(plan)
+    }
+
+    fn compile(&mut self, var: &GeoVarEnum) -> PyResult<()> {
+        let step = match var {
+            GeoVarEnum::NoChange(id) => Step::Geometry(*id),
+            GeoVarEnum::Ops(id, ops) => {
+                let Some((Ops::ItemGetter(key), ops)) = ops.split_first() else {
+                    return Err(first_operator_error());
+                };
+                let key = Self::position(&mut self.keys, key);
+                // `a["id"]` and `b["id"]` share the same memoized operand.
+                Step::Operand(
+                    *id,
+                    Self::position(&mut self.operands, &(key, ops.to_vec())),
+                )
+            }
+            GeoVarEnum::Literal(value) => Step::Literal(value.clone()),
+            GeoVarEnum::Not(var) => {
+                self.compile(var)?;
+                Step::Not
+            }
+            GeoVarEnum::Apply(var, ops) => {
+                self.compile(var)?;
+                Step::Apply(ops.clone())
+            }
+            _ => {
+                let (binary, [var1, var2]) = var.binary().expect("Binary operator expected");
+                self.compile(var1)?;
+                self.compile(var2)?;
+                Step::Binary(binary)
+            }
+        };
+        self.steps.push(step);
+        Ok(())
+    }
+
+    fn position<T: PartialEq + Clone>(values: &mut Vec<T>, value: &T) -> usize {
+        match values.iter().position(|other| other == value) {
+            Some(index) => index,
+            None => {
+                values.push(value.clone());
+                values.len() - 1
+            }
+        }
+    }
+
+    pub(crate) fn compare(&mut self, geom1: &Geometry, geom2: &Geometry) -> PyResult<bool> {
+        let bindings = Bindings {
+            first: self.first,
+            geometries: [geom1, geom2],
+            topology: self.topology,
+        };
+        let mut stack: Vec<Evaluated> = Vec::with_capacity(self.steps.len());
+        for step in &self.steps {
+            let evaluated = match step {
+                Step::Geometry(id) => Evaluated::Geometry(bindings.get(*id)),
+                Step::Operand(id, index) => {
+                    let geom = bindings.get(*id);
+                    // Values of the keys first, then values of the operands.
+                    let values = self
+                        .cache
+                        .entry(geom as *const Geometry)
+                        .or_insert_with(|| vec![None; self.keys.len() + self.operands.len()]);
+                    let slot = self.keys.len() + index;
+                    let value = match &values[slot] {
+                        Some(value) => value.clone(),
+                        None => {
+                            let (key, ops) = &self.operands[*index];
+                            let value = match &values[*key] {
+                                Some(value) => value.clone(),
+                                None => {
+                                    let value = geom.item(&self.keys[*key], self.topology)?;
+                                    values[*key] = Some(value.clone());
+                                    value
+                                }
+                            };
+                            let value = value.process(ops)?;
+                            values[slot] = Some(value.clone());
+                            value
+                        }
+                    };
+                    Evaluated::Value(value)
+                }
+                Step::Literal(value) => Evaluated::Value(value.clone()),
+                Step::Not => {
+                    let evaluated = stack.pop().expect("Operand expected on the stack");
+                    Evaluated::Value(Value::Bool(!evaluated.is_truthy()))
+                }
+                Step::Apply(ops) => {
+                    let evaluated = stack.pop().expect("Operand expected on the stack");
+                    Evaluated::Value(evaluated.into_operand()?.process(ops)?)
+                }
+                Step::Binary(binary) => {
+                    let v2 = stack.pop().expect("Operand expected on the stack");
+                    let v1 = stack.pop().expect("Operand expected on the stack");
+                    Evaluated::Value(binary.call(v1, v2)?)
+                }
+            };
+            stack.push(evaluated);
+        }
+        Ok(stack.pop().is_some_and(|evaluated| evaluated.is_truthy()))
+    }
+}
+
+/// Operand of a binary operator of `GeoVar`: another variable or a literal
+/// such as `10000` or `"06"`.
+pub struct Operand(GeoVarEnum);
+
+impl<'a, 'py> FromPyObject<'a, 'py> for Operand {
+    type Error = PyErr;
+
+    fn extract(obj: Borrowed<'a, 'py, PyAny>) -> PyResult<Self> {
+        match obj.cast::<GeoVar>() {
+            Ok(var) => Ok(Operand(var.borrow().inner.clone())),
+            Err(_) => Ok(Operand(GeoVarEnum::Literal(Value::extract(&obj)?))),
+        }
+    }
+}
+
+impl From<&GeoVar> for Operand {
+    fn from(var: &GeoVar) -> Self {
+        Operand(var.inner.clone())
+    }
+}
+
+impl From<Value> for Operand {
+    fn from(value: Value) -> Self {
+        Operand(GeoVarEnum::Literal(value))
     }
 }
 
 #[pyfunction]
-pub fn var() -> GeoVar {
-    GeoVar::new()
+#[pyo3(signature = (position=None))]
+pub fn var(position: Option<usize>) -> PyResult<GeoVar> {
+    match position {
+        None => Ok(GeoVar::new()),
+        Some(position) if position < POSITIONS => Ok(GeoVar {
+            inner: GeoVarEnum::NoChange(position),
+        }),
+        Some(_) => Err(PyValueError::new_err("'position' must be 0 or 1")),
+    }
 }
 
 #[pyclass]
@@ -129,34 +549,43 @@ impl GeoVar {
     #[new]
     pub fn new() -> Self {
         Self {
-            inner: GeoVarEnum::NoChange,
+            inner: GeoVarEnum::NoChange(NEXT_ID.fetch_add(1, AtomicOrdering::Relaxed)),
         }
     }
 
-    pub fn __getitem__(&self, key: &str) -> PyResult<GeoVar> {
-        match self.inner {
-            GeoVarEnum::NoChange => Ok(GeoVar {
-                inner: GeoVarEnum::Ops(vec![Ops::ItemGetter(key.to_string())]),
-            }),
-            _ => Err(PyTypeError::new_err(
-                "The method 'attribute' must be called only on 'GeoVarEnum::NoChange' variant",
-            )),
+    pub fn __getitem__(&self, key: &Bound<'_, PyAny>) -> PyResult<GeoVar> {
+        if let Ok(key) = key.extract::<String>() {
+            return self.item(&key);
         }
+        let op = if let GeoVarEnum::NoChange(_) = self.inner {
+            return Err(PyTypeError::new_err(
+                "The attribute of a geometry must be a string.",
+            ));
+        } else if let Ok(index) = key.extract::<i64>() {
+            Ops::Index(index)
+        } else if let Ok(slice) = key.cast::<PySlice>() {
+            let step = slice
+                .getattr("step")?
+                .extract::<Option<i64>>()?
+                .unwrap_or(1);
+            if step == 0 {
+                return Err(PyValueError::new_err("slice step cannot be zero"));
+            }
+            Ops::Slice(
+                slice.getattr("start")?.extract()?,
+                slice.getattr("stop")?.extract()?,
+                step,
+            )
+        } else {
+            return Err(PyTypeError::new_err(
+                "Expected a string, an integer or a slice.",
+            ));
+        };
+        self.push(op, "__getitem__")
     }
 
     fn transform(&self, transform: Transform) -> PyResult<GeoVar> {
-        match &self.inner {
-            GeoVarEnum::Ops(ops) => {
-                let mut cloned = ops.clone();
-                cloned.push(Ops::Transform(transform));
-                Ok(GeoVar {
-                    inner: GeoVarEnum::Ops(cloned),
-                })
-            }
-            _ => Err(PyTypeError::new_err(
-                "The method 'transform' must be called only on 'GeoVarEnum::Ops' variant",
-            )),
-        }
+        self.push(Ops::Transform(transform), "transform")
     }
 
     pub fn int(&self) -> PyResult<GeoVar> {
@@ -171,36 +600,115 @@ impl GeoVar {
         self.transform(Transform::Length)
     }
 
+    pub fn str(&self) -> PyResult<GeoVar> {
+        self.transform(Transform::AsStr)
+    }
+
+    pub fn lower(&self) -> PyResult<GeoVar> {
+        self.transform(Transform::Lower)
+    }
+
+    pub fn upper(&self) -> PyResult<GeoVar> {
+        self.transform(Transform::Upper)
+    }
+
+    pub fn __abs__(&self) -> PyResult<GeoVar> {
+        self.transform(Transform::Abs)
+    }
+
+    pub fn startswith(&self, prefix: String) -> PyResult<GeoVar> {
+        self.push(Ops::StartsWith(prefix), "startswith")
+    }
+
+    pub fn endswith(&self, suffix: String) -> PyResult<GeoVar> {
+        self.push(Ops::EndsWith(suffix), "endswith")
+    }
+
+    pub fn isin(&self, values: &Bound<'_, PyAny>) -> PyResult<GeoVar> {
+        let values = values
+            .try_iter()?
+            .map(|value| Value::extract(&value?))
+            .collect::<PyResult<Vec<_>>>()?;
+        self.push(Ops::IsIn(values), "isin")
+    }
+
     pub fn __add__(&self, other: &Bound<'_, PyAny>) -> PyResult<GeoVar> {
-        self.ops(other, |v| Ops::AddI64(v), |v| Ops::AddF64(v), "__add__")
+        if let Ok(other) = other.extract::<String>() {
+            return self.push(Ops::AddStr(other), "__add__");
+        }
+        self.ops(other, Ops::AddI64, Ops::AddF64, GeoVarEnum::Add, "__add__")
     }
 
     pub fn __sub__(&self, other: &Bound<'_, PyAny>) -> PyResult<GeoVar> {
-        self.ops(other, |v| Ops::SubI64(v), |v| Ops::SubF64(v), "__sub__")
+        self.ops(other, Ops::SubI64, Ops::SubF64, GeoVarEnum::Sub, "__sub__")
     }
 
     pub fn __mul__(&self, other: &Bound<'_, PyAny>) -> PyResult<GeoVar> {
-        self.ops(other, |v| Ops::MulI64(v), |v| Ops::MulF64(v), "__mul__")
+        self.ops(other, Ops::MulI64, Ops::MulF64, GeoVarEnum::Mul, "__mul__")
     }
 
     pub fn __truediv__(&self, other: &Bound<'_, PyAny>) -> PyResult<GeoVar> {
-        self.ops(other, |v| Ops::DivI64(v), |v| Ops::DivF64(v), "__div__")
+        self.ops(other, Ops::DivI64, Ops::DivF64, GeoVarEnum::Div, "__div__")
+    }
+
+    pub fn __mod__(&self, other: &Bound<'_, PyAny>) -> PyResult<GeoVar> {
+        self.ops(other, Ops::ModI64, Ops::ModF64, GeoVarEnum::Mod, "__mod__")
+    }
+
+    pub fn __floordiv__(&self, other: &Bound<'_, PyAny>) -> PyResult<GeoVar> {
+        self.ops(
+            other,
+            Ops::FloorDivI64,
+            Ops::FloorDivF64,
+            GeoVarEnum::FloorDiv,
+            "__floordiv__",
+        )
+    }
+
+    pub fn __eq__(&self, other: Operand) -> Self {
+        self.cmp(other, GeoVarEnum::Eq)
+    }
+
+    pub fn __ne__(&self, other: Operand) -> Self {
+        self.cmp(other, GeoVarEnum::Neq)
+    }
+
+    pub fn __lt__(&self, other: Operand) -> Self {
+        self.cmp(other, GeoVarEnum::Lt)
+    }
+
+    pub fn __le__(&self, other: Operand) -> Self {
+        self.cmp(other, GeoVarEnum::Le)
     }
 
-    pub fn __eq__(&self, other: &GeoVar) -> Self {
-        self.cmp(other, |vars| GeoVarEnum::Eq(vars))
+    pub fn __gt__(&self, other: Operand) -> Self {
+        self.cmp(other, GeoVarEnum::Gt)
     }
 
-    pub fn __ne__(&self, other: &GeoVar) -> Self {
-        self.cmp(other, |vars| GeoVarEnum::Neq(vars))
+    pub fn __ge__(&self, other: Operand) -> Self {
+        self.cmp(other, GeoVarEnum::Ge)
     }
 
-    pub fn __and__(&self, other: &GeoVar) -> Self {
-        self.cmp(other, |vars| GeoVarEnum::And(vars))
+    pub fn __and__(&self, other: Operand) -> Self {
+        self.cmp(other, GeoVarEnum::And)
     }
 
-    pub fn __or__(&self, other: &GeoVar) -> Self {
-        self.cmp(other, |vars| GeoVarEnum::Or(vars))
+    pub fn __or__(&self, other: Operand) -> Self {
+        self.cmp(other, GeoVarEnum::Or)
+    }
+
+    pub fn __invert__(&self) -> Self {
+        Self {
+            inner: GeoVarEnum::Not(Box::new(self.inner.clone())),
+        }
+    }
+
+    pub fn min(&self, other: Operand) -> Self {
+        self.cmp(other, GeoVarEnum::Min)
+    }
+
+    pub fn max(&self, other: Operand) -> Self {
+        self.cmp(other, GeoVarEnum::Max)
     }
 
     pub fn __str__(&self) -> String {
@@ -209,140 +717,405 @@ impl GeoVar {
 }
 
 impl GeoVar {
+    /// Accesses the attribute `key` of the geometry or the field `key` of the
+    /// current value, as `var()[key]`.
+    pub(crate) fn item(&self, key: &str) -> PyResult<GeoVar> {
+        match &self.inner {
+            GeoVarEnum::NoChange(id) => Ok(GeoVar {
+                inner: GeoVarEnum::Ops(*id, vec![Ops::ItemGetter(key.to_string())]),
+            }),
+            _ => self.push(Ops::ItemGetter(key.to_string()), "__getitem__"),
+        }
+    }
+
+    #[cfg(test)]
     pub(crate) fn compare(&self, geom1: &Geometry, geom2: &Geometry) -> PyResult<Value> {
-        self.inner.compare(geom1, geom2)
+        let topology = TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: Default::default(),
+            arcs: Vec::new(),
+        };
+        Ok(Value::Bool(self.compile(&topology)?.compare(geom1, geom2)?))
     }
 
-    fn cmp<F>(&self, other: &GeoVar, f: F) -> Self
+    /// Compiles the expression for repeated comparisons of geometries of
+    /// `topology` such as the ones of `mesh`.
+    pub(crate) fn compile<'t>(&self, topology: &'t TopoJSON) -> PyResult<Plan<'t>> {
+        Plan::new(&self.inner, topology)
+    }
+
+    /// Evaluates the expression on a pair of geometries without compiling it,
+    /// walking the expression and parsing the properties on each call.
+    pub(crate) fn interpret(
+        &self,
+        topology: &TopoJSON,
+        geom1: &Geometry,
+        geom2: &Geometry,
+    ) -> PyResult<bool> {
+        let bindings = Bindings {
+            first: self.inner.first_id(),
+            geometries: [geom1, geom2],
+            topology,
+        };
+        Ok(self.inner.evaluate(&bindings)?.is_truthy())
+    }
+
+    /// Evaluates the expression on a single geometry, e.g. `var()["id"].int()`:
+    /// all its variables are bound to this geometry.
+    pub(crate) fn evaluate(&self, topology: &TopoJSON, geom: &Geometry) -> PyResult<Value> {
+        let bindings = Bindings {
+            first: None,
+            geometries: [geom, geom],
+            topology,
+        };
+        Ok(match self.inner.evaluate(&bindings)? {
+            Evaluated::Geometry(_) => Value::Bool(true),
+            Evaluated::Value(value) => value,
+        })
+    }
+
+    /// Evaluates the expression as a predicate on a single geometry, e.g.
+    /// `var()["id"].startswith("06")`.
+    pub(crate) fn matches(&self, topology: &TopoJSON, geom: &Geometry) -> PyResult<bool> {
+        Ok(self.evaluate(topology, geom)?.is_truthy())
+    }
+
+    /// Appends an operation to the value of a key, or applies it to the result
+    /// of another expression.
+    fn push(&self, op: Ops, method_name: &str) -> PyResult<Self> {
+        let inner = match &self.inner {
+            GeoVarEnum::NoChange(_) | GeoVarEnum::Literal(_) => {
+                return Err(PyTypeError::new_err(format!(
+                    "The method '{method_name}' must be called only on 'GeoVarEnum::Ops' variant"
+                )));
+            }
+            GeoVarEnum::Ops(id, ops) => {
+                let mut cloned = ops.clone();
+                cloned.push(op);
+                GeoVarEnum::Ops(*id, cloned)
+            }
+            GeoVarEnum::Apply(var, ops) => {
+                let mut cloned = ops.clone();
+                cloned.push(op);
+                GeoVarEnum::Apply(var.clone(), cloned)
+            }
+            var => GeoVarEnum::Apply(Box::new(var.clone()), vec![op]),
+        };
+        Ok(GeoVar { inner })
+    }
+
+    fn cmp<F>(&self, other: Operand, f: F) -> Self
     where
         F: FnOnce([Box<GeoVarEnum>; 2]) -> GeoVarEnum,
     {
         Self {
-            inner: f([Box::new(self.inner.clone()), Box::new(other.inner.clone())]),
+            inner: f([Box::new(self.inner.clone()), Box::new(other.0)]),
         }
     }
 
-    fn ops<I, F>(
+    /// Arithmetic operator: a literal operand is appended as an operation, as
+    /// in `a["id"].int() / 1000`, while another variable builds a binary node,
+    /// as in `a["id"].int() - b["id"].int()`.
+    fn ops<I, F, B>(
         &self,
         other: &Bound<'_, PyAny>,
-        ops_i32: I,
-        ops_f32: F,
+        ops_i64: I,
+        ops_f64: F,
+        binary: B,
         method_name: &str,
     ) -> PyResult<Self>
     where
         I: FnOnce(i64) -> Ops,
         F: FnOnce(f64) -> Ops,
+        B: FnOnce([Box<GeoVarEnum>; 2]) -> GeoVarEnum,
     {
-        match &self.inner {
-            GeoVarEnum::Ops(ops) => {
-                let mut cloned = ops.clone();
-                if let Ok(extracted) = other.extract() {
-                    cloned.push(ops_i32(extracted));
-                } else if let Ok(extracted) = other.extract() {
-                    cloned.push(ops_f32(extracted));
-                } else {
-                    return Err(PyTypeError::new_err("Expected an integer or a float."));
-                }
-                Ok(GeoVar {
-                    inner: GeoVarEnum::Ops(cloned),
-                })
-            }
-            _ => Err(PyTypeError::new_err(format!(
+        if let GeoVarEnum::NoChange(_) = self.inner {
+            return Err(PyTypeError::new_err(format!(
                 "The method '{method_name}' must be called only on 'GeoVarEnum::Ops' variant"
-            ))),
+            )));
         }
+        if let Ok(other) = other.cast::<GeoVar>() {
+            return Ok(self.cmp((&*other.borrow()).into(), binary));
+        }
+        let op = if let Ok(extracted) = other.extract() {
+            ops_i64(extracted)
+        } else if let Ok(extracted) = other.extract() {
+            ops_f64(extracted)
+        } else {
+            return Err(PyTypeError::new_err(
+                "Expected an integer, a float or a GeoVar.",
+            ));
+        };
+        self.push(op, method_name)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum Value {
     Float(f64),
     Int(i64),
     Bool(bool),
+    Str(String),
+    Null,
+    Array(Vec<serde_json::Value>),
+    Object(serde_json::Map<String, serde_json::Value>),
 }
 
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         match [self, other] {
             [Self::Int(a), Self::Int(b)] => a == b,
-            [Self::Float(a), Self::Float(b)] => a == b,
-            [Self::Bool(a), Self::Bool(b)] => a == b,
-            [Self::Float(a), Self::Int(b)] => *a == *b as f64,
-            [Self::Int(a), Self::Float(b)] => *a as f64 == *b,
-            _ => false,
+            [Self::Str(a), Self::Str(b)] => a == b,
+            [Self::Null, Self::Null] => true,
+            [Self::Array(a), Self::Array(b)] => a == b,
+            [Self::Object(a), Self::Object(b)] => a == b,
+            [a, b] => match [a.as_number(), b.as_number()] {
+                [Some(a), Some(b)] => a == b,
+                _ => false,
+            },
         }
     }
+}
 
-    fn ne(&self, other: &Self) -> bool {
-        match [self, other] {
-            [Self::Int(a), Self::Int(b)] => a != b,
-            [Self::Float(a), Self::Float(b)] => a != b,
-            [Self::Bool(a), Self::Bool(b)] => a != b,
-            [Self::Float(a), Self::Int(b)] => *a != *b as f64,
-            [Self::Int(a), Self::Float(b)] => *a as f64 != *b,
-            _ => false,
+impl Value {
+    /// Converts a Python literal used in a `GeoVar` expression.
+    fn extract(value: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if value.is_none() {
+            Ok(Self::Null)
+        } else if let Ok(value) = value.cast::<PyBool>() {
+            Ok(Self::Bool(value.is_true()))
+        } else if let Ok(value) = value.extract() {
+            Ok(Self::Int(value))
+        } else if let Ok(value) = value.extract() {
+            Ok(Self::Float(value))
+        } else if let Ok(value) = value.extract() {
+            Ok(Self::Str(value))
+        } else {
+            Err(PyTypeError::new_err(
+                "Expected a string, an integer, a float, a boolean or None.",
+            ))
         }
     }
-}
 
-impl Value {
-    fn and(&self, other: &Self) -> bool {
-        match [self, other] {
-            [Self::Int(a), Self::Int(b)] => *a != 0 && *b != 0,
-            [Self::Float(a), Self::Float(b)] => *a != 0.0 && *b != 0.0,
-            [Self::Bool(a), Self::Bool(b)] => *a && *b,
-            [Self::Float(a), Self::Int(b)] => *a != 0.0 && *b != 0,
-            [Self::Int(a), Self::Float(b)] => *a != 0 && *b != 0.0,
-            [Self::Bool(a), Self::Float(b)] => *a && *b != 0.0,
-            [Self::Float(a), Self::Bool(b)] => *a != 0.0 && *b,
-            [Self::Bool(a), Self::Int(b)] => *a && *b != 0,
-            [Self::Int(a), Self::Bool(b)] => *a != 0 && *b,
+    fn from_json(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Self::Null,
+            serde_json::Value::Bool(x) => Self::Bool(x),
+            serde_json::Value::Number(x) => match x.as_i64() {
+                Some(x) => Self::Int(x),
+                None => Self::Float(x.as_f64().unwrap_or(f64::NAN)),
+            },
+            serde_json::Value::String(x) => Self::Str(x),
+            serde_json::Value::Array(x) => Self::Array(x),
+            serde_json::Value::Object(x) => Self::Object(x),
+        }
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            Self::Int(x) => Some(*x as f64),
+            Self::Float(x) => Some(*x),
+            Self::Bool(x) => Some(*x as i64 as f64),
+            _ => None,
         }
     }
 
-    fn or(&self, other: &Self) -> bool {
+    /// Compares two values as Python does: `None` is returned when one of
+    /// them is NaN.
+    fn cmp_py(&self, other: &Self, symbol: &str) -> PyResult<Option<Ordering>> {
         match [self, other] {
-            [Self::Int(a), Self::Int(b)] => *a != 0 || *b != 0,
-            [Self::Float(a), Self::Float(b)] => *a != 0.0 || *b != 0.0,
-            [Self::Bool(a), Self::Bool(b)] => *a || *b,
-            [Self::Float(a), Self::Int(b)] => *a != 0.0 || *b != 0,
-            [Self::Int(a), Self::Float(b)] => *a != 0 || *b != 0.0,
-            [Self::Bool(a), Self::Float(b)] => *a || *b != 0.0,
-            [Self::Float(a), Self::Bool(b)] => *a != 0.0 || *b,
-            [Self::Bool(a), Self::Int(b)] => *a || *b != 0,
-            [Self::Int(a), Self::Bool(b)] => *a != 0 || *b,
+            [Self::Int(a), Self::Int(b)] => Ok(Some(a.cmp(b))),
+            [Self::Str(a), Self::Str(b)] => Ok(Some(a.cmp(b))),
+            [a, b] => match [a.as_number(), b.as_number()] {
+                [Some(a), Some(b)] => Ok(a.partial_cmp(&b)),
+                _ => Err(PyTypeError::new_err(format!(
+                    "'{}' not supported between instances of '{}' and '{}'",
+                    symbol,
+                    a.value_type(),
+                    b.value_type()
+                ))),
+            },
+        }
+    }
+
+    fn item(self, key: &str) -> PyResult<Self> {
+        match self {
+            Self::Object(mut x) => x
+                .remove(key)
+                .map(Self::from_json)
+                .ok_or_else(|| PyKeyError::new_err(key.to_string())),
+            _ => Err(PyTypeError::new_err(format!(
+                "'{}' object is not subscriptable",
+                self.value_type()
+            ))),
+        }
+    }
+
+    fn as_i64(self) -> PyResult<Self> {
+        match self {
+            Self::Int(x) => Ok(Self::Int(x)),
+            Self::Bool(x) => Ok(Self::Int(x as i64)),
+            Self::Float(x) => Ok(Self::Int(x as i64)),
+            Self::Str(x) => x.trim().parse::<i64>().map(Self::Int).map_err(|_| {
+                PyValueError::new_err(format!("invalid literal for int() with base 10: {:?}", x))
+            }),
+            _ => Err(PyTypeError::new_err(format!(
+                "int() argument must be a string, a bytes-like object or a real number, not '{}'",
+                self.value_type()
+            ))),
         }
     }
 
-    fn as_i64(self) -> Self {
+    fn as_f64(self) -> PyResult<Self> {
         match self {
-            Self::Int(x) => Self::Int(x),
-            Self::Bool(x) => Self::Int(x as i64),
-            Self::Float(x) => Self::Int(x as i64),
+            Self::Int(x) => Ok(Self::Float(x as f64)),
+            Self::Bool(x) => Ok(Self::Float(x as i64 as f64)),
+            Self::Float(x) => Ok(Self::Float(x)),
+            Self::Str(x) => x.trim().parse::<f64>().map(Self::Float).map_err(|_| {
+                PyValueError::new_err(format!("could not convert string to float: {:?}", x))
+            }),
+            _ => Err(PyTypeError::new_err(format!(
+                "float() argument must be a string or a real number, not '{}'",
+                self.value_type()
+            ))),
         }
     }
 
-    fn as_f64(self) -> Self {
+    fn into_str(self) -> Self {
         match self {
-            Self::Int(x) => Self::Float(x as f64),
-            Self::Bool(x) => Self::Float(x as i64 as f64),
-            Self::Float(x) => Self::Float(x),
+            Self::Str(x) => Self::Str(x),
+            Self::Int(x) => Self::Str(x.to_string()),
+            Self::Float(x) if x.fract() == 0. && x.is_finite() => Self::Str(format!("{:.1}", x)),
+            Self::Float(x) => Self::Str(x.to_string()),
+            Self::Bool(true) => Self::Str("True".to_string()),
+            Self::Bool(false) => Self::Str("False".to_string()),
+            Self::Null => Self::Str("None".to_string()),
+            value => Self::Str(value.into_json().to_string()),
         }
     }
 
-    fn op_i64<I, F>(self, other: i64, int_fn: I, float_fn: F) -> Self
+    fn no_attribute(&self, attribute: &str) -> PyErr {
+        PyAttributeError::new_err(format!(
+            "'{}' object has no attribute '{}'",
+            self.value_type(),
+            attribute
+        ))
+    }
+
+    fn map_str<F>(self, attribute: &str, f: F) -> PyResult<Self>
+    where
+        F: FnOnce(String) -> Self,
+    {
+        match self {
+            Self::Str(x) => Ok(f(x)),
+            _ => Err(self.no_attribute(attribute)),
+        }
+    }
+
+    fn add_str(self, other: &str) -> PyResult<Self> {
+        match self {
+            Self::Str(x) => Ok(Self::Str(x + other)),
+            _ => Err(self.unsupported("+", "str")),
+        }
+    }
+
+    fn index(self, index: i64) -> PyResult<Self> {
+        let (n, value_type) = match &self {
+            Self::Str(x) => (x.chars().count(), "string"),
+            Self::Array(x) => (x.len(), "list"),
+            _ => {
+                return Err(PyTypeError::new_err(format!(
+                    "'{}' object is not subscriptable",
+                    self.value_type()
+                )));
+            }
+        };
+        let i = if index < 0 { index + n as i64 } else { index };
+        if i < 0 || i >= n as i64 {
+            return Err(PyIndexError::new_err(format!(
+                "{} index out of range",
+                value_type
+            )));
+        }
+        Ok(match self {
+            Self::Str(x) => Self::Str(x.chars().nth(i as usize).unwrap().to_string()),
+            Self::Array(mut x) => Self::from_json(x.swap_remove(i as usize)),
+            _ => unreachable!(),
+        })
+    }
+
+    fn slice(self, start: Option<i64>, stop: Option<i64>, step: i64) -> PyResult<Self> {
+        match self {
+            Self::Str(x) => {
+                let chars: Vec<char> = x.chars().collect();
+                Ok(Self::Str(
+                    slice_indices(chars.len(), start, stop, step)
+                        .map(|i| chars[i])
+                        .collect(),
+                ))
+            }
+            Self::Array(x) => Ok(Self::Array(
+                slice_indices(x.len(), start, stop, step)
+                    .map(|i| x[i].clone())
+                    .collect(),
+            )),
+            _ => Err(PyTypeError::new_err(format!(
+                "'{}' object is not subscriptable",
+                self.value_type()
+            ))),
+        }
+    }
+
+    fn len(self) -> PyResult<Self> {
+        match self {
+            Self::Str(x) => Ok(Self::Int(x.chars().count() as i64)),
+            Self::Array(x) => Ok(Self::Int(x.len() as i64)),
+            Self::Object(x) => Ok(Self::Int(x.len() as i64)),
+            _ => Err(PyTypeError::new_err(format!(
+                "object of type '{}' has no len()",
+                self.value_type()
+            ))),
+        }
+    }
+
+    fn unsupported(&self, symbol: &str, other: &str) -> PyErr {
+        PyTypeError::new_err(format!(
+            "unsupported operand type(s) for {}: '{}' and '{}'",
+            symbol,
+            self.value_type(),
+            other
+        ))
+    }
+
+    fn op_i64<I, F>(self, other: i64, symbol: &str, int_fn: I, float_fn: F) -> PyResult<Self>
     where
         I: FnOnce(i64, i64) -> i64,
         F: FnOnce(f64, f64) -> f64,
     {
         match self {
-            Self::Int(x) => Self::Int(int_fn(x, other)),
-            Self::Float(x) => Self::Float(float_fn(x, other as f64)),
-            Self::Bool(x) => Self::Int(int_fn(x as i64, other)),
+            Self::Int(x) => Ok(Self::Int(int_fn(x, other))),
+            Self::Float(x) => Ok(Self::Float(float_fn(x, other as f64))),
+            Self::Bool(x) => Ok(Self::Int(int_fn(x as i64, other))),
+            _ => Err(self.unsupported(symbol, "int")),
+        }
+    }
+
+    /// Arithmetic operator between two values, such as `a["x"] - b["x"]`.
+    fn arithmetic<I, F>(self, other: Self, symbol: &str, int_fn: I, float_fn: F) -> PyResult<Self>
+    where
+        I: FnOnce(Self, i64) -> PyResult<Self>,
+        F: FnOnce(Self, f64) -> PyResult<Self>,
+    {
+        match other {
+            Self::Int(x) => int_fn(self, x),
+            Self::Bool(x) => int_fn(self, x as i64),
+            Self::Float(x) => float_fn(self, x),
+            _ => Err(self.unsupported(symbol, other.value_type())),
         }
     }
 
-    fn op_f64<F>(self, other: f64, f: F) -> Self
+    fn op_f64<F>(self, other: f64, symbol: &str, f: F) -> PyResult<Self>
     where
         F: FnOnce(f64, f64) -> f64,
     {
@@ -350,156 +1123,266 @@ impl Value {
             Self::Int(x) => x as f64,
             Self::Float(x) => x,
             Self::Bool(x) => x as i64 as f64,
+            _ => return Err(self.unsupported(symbol, "float")),
         };
-        Self::Float(f(val, other))
+        Ok(Self::Float(f(val, other)))
     }
 
-    fn add_i64(self, other: i64) -> Self {
-        self.op_i64(other, |a, b| a + b, |a, b| a + b)
+    fn add_i64(self, other: i64) -> PyResult<Self> {
+        self.op_i64(other, "+", |a, b| a + b, |a, b| a + b)
     }
 
-    fn add_f64(self, other: f64) -> Self {
-        self.op_f64(other, |a, b| a + b)
+    fn add_f64(self, other: f64) -> PyResult<Self> {
+        self.op_f64(other, "+", |a, b| a + b)
     }
 
-    fn sub_i64(self, other: i64) -> Self {
-        self.op_i64(other, |a, b| a - b, |a, b| a - b)
+    fn sub_i64(self, other: i64) -> PyResult<Self> {
+        self.op_i64(other, "-", |a, b| a - b, |a, b| a - b)
     }
 
-    fn sub_f64(self, other: f64) -> Self {
-        self.op_f64(other, |a, b| a - b)
+    fn sub_f64(self, other: f64) -> PyResult<Self> {
+        self.op_f64(other, "-", |a, b| a - b)
     }
 
-    fn mul_i64(self, other: i64) -> Self {
-        self.op_i64(other, |a, b| a * b, |a, b| a * b)
+    fn mul_i64(self, other: i64) -> PyResult<Self> {
+        self.op_i64(other, "*", |a, b| a * b, |a, b| a * b)
     }
 
-    fn mul_f64(self, other: f64) -> Self {
-        self.op_f64(other, |a, b| a * b)
+    fn mul_f64(self, other: f64) -> PyResult<Self> {
+        self.op_f64(other, "*", |a, b| a * b)
     }
 
     fn div_i64(self, other: i64) -> PyResult<Self> {
         match other {
             0 => Err(PyZeroDivisionError::new_err("division by zero")),
-            _ => Ok(self.op_i64(other, |a, b| a / b, |a, b| a / b)),
+            _ => self.op_i64(other, "/", |a, b| a / b, |a, b| a / b),
         }
     }
 
     fn div_f64(self, other: f64) -> PyResult<Self> {
         match other {
             0.0 => Err(PyZeroDivisionError::new_err("division by zero")),
-            _ => Ok(self.op_f64(other, |a, b| a / b)),
+            _ => self.op_f64(other, "/", |a, b| a / b),
         }
     }
 
-    fn value_type(&self) -> &str {
-        match self {
-            Value::Int(_) => "int",
-            Value::Float(_) => "float",
-            Value::Bool(_) => "bool",
+    fn mod_i64(self, other: i64) -> PyResult<Self> {
+        match (&self, other) {
+            (Self::Float(_), 0) => Err(PyZeroDivisionError::new_err("float modulo by zero")),
+            (_, 0) => Err(PyZeroDivisionError::new_err("integer modulo by zero")),
+            _ => self.op_i64(other, "%", floor_mod_i64, floor_mod_f64),
         }
     }
 
-    pub fn into_bool(self) -> bool {
-        match self {
-            Self::Bool(x) => x,
-            Self::Int(x) => x != 0,
-            Self::Float(x) => x != 0.0,
+    fn mod_f64(self, other: f64) -> PyResult<Self> {
+        match other {
+            0.0 => Err(PyZeroDivisionError::new_err("float modulo by zero")),
+            _ => self.op_f64(other, "%", floor_mod_f64),
         }
     }
-}
 
-impl Geometry {
-    fn transform_id(&self, transform: &Transform) -> PyResult<Value> {
-        let id_str = self.id().ok_or_else(|| {
-            PyTypeError::new_err(
-                "argument must be a string, a bytes-like object or a real number, not 'NoneType'",
-            )
-        })?;
+    fn floor_div_i64(self, other: i64) -> PyResult<Self> {
+        match (&self, other) {
+            (Self::Float(_), 0) => {
+                Err(PyZeroDivisionError::new_err("float floor division by zero"))
+            }
+            (_, 0) => Err(PyZeroDivisionError::new_err(
+                "integer division or modulo by zero",
+            )),
+            _ => self.op_i64(other, "//", floor_div_i64, |a, b| (a / b).floor()),
+        }
+    }
 
-        match transform {
-            Transform::AsI64 => id_str
-                .parse::<i64>()
-                .map(Value::Int)
-                .map_err(|e: ParseIntError| PyValueError::new_err(e.to_string())),
+    fn floor_div_f64(self, other: f64) -> PyResult<Self> {
+        match other {
+            0.0 => Err(PyZeroDivisionError::new_err("float floor division by zero")),
+            _ => self.op_f64(other, "//", |a, b| (a / b).floor()),
+        }
+    }
 
-            Transform::AsF64 => id_str
-                .parse::<f64>()
-                .map(Value::Float)
-                .map_err(|e: ParseFloatError| PyValueError::new_err(e.to_string())),
+    fn abs(self) -> PyResult<Self> {
+        match self {
+            Self::Int(x) => Ok(Self::Int(x.abs())),
+            Self::Float(x) => Ok(Self::Float(x.abs())),
+            Self::Bool(x) => Ok(Self::Int(x as i64)),
+            _ => Err(PyTypeError::new_err(format!(
+                "bad operand type for abs(): '{}'",
+                self.value_type()
+            ))),
+        }
+    }
 
-            Transform::Length => Ok(Value::Int(id_str.len() as i64)),
+    fn value_type(&self) -> &str {
+        match self {
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "bool",
+            Value::Str(_) => "str",
+            Value::Null => "NoneType",
+            Value::Array(_) => "list",
+            Value::Object(_) => "dict",
         }
     }
 
-    fn transform_bbox(&self, transform: &Transform) -> PyResult<Value> {
-        let bbox = self.bbox().ok_or_else(|| {
-            PyTypeError::new_err(
-                "argument must be a string, a bytes-like object or a real number, not 'NoneType'",
-            )
-        })?;
+    pub fn into_json(self) -> serde_json::Value {
+        match self {
+            Self::Bool(x) => serde_json::Value::from(x),
+            Self::Int(x) => serde_json::Value::from(x),
+            Self::Float(x) => serde_json::Value::from(x),
+            Self::Str(x) => serde_json::Value::from(x),
+            Self::Null => serde_json::Value::Null,
+            Self::Array(x) => serde_json::Value::from(x),
+            Self::Object(x) => serde_json::Value::from(x),
+        }
+    }
 
-        match transform {
-            Transform::AsI64 => Err(PyTypeError::new_err(
-                "int() argument must be a string, a bytes-like object or a real number, not 'list'",
-            )),
-            Transform::AsF64 => Err(PyTypeError::new_err(
-                "float() argument must be a string or a real number, not 'list'",
-            )),
-            Transform::Length => Ok(Value::Int(bbox.len() as i64)),
+    fn is_truthy(&self) -> bool {
+        match self {
+            Self::Bool(x) => *x,
+            Self::Int(x) => *x != 0,
+            Self::Float(x) => *x != 0.0,
+            Self::Str(x) => !x.is_empty(),
+            Self::Null => false,
+            Self::Array(x) => !x.is_empty(),
+            Self::Object(x) => !x.is_empty(),
         }
     }
 
-    fn transform_properties(&self, transform: &Transform) -> PyResult<Value> {
-        let properties_str = self.properties().ok_or_else(|| {
-            PyTypeError::new_err(
-                "argument must be a string, a bytes-like object or a real number, not 'NoneType'",
-            )
-        })?;
+    #[cfg(test)]
+    pub fn into_bool(self) -> bool {
+        self.is_truthy()
+    }
+}
 
-        match transform {
-            Transform::AsI64 => properties_str
-                .parse::<i64>()
-                .map(Value::Int)
-                .map_err(|e: ParseIntError| PyValueError::new_err(e.to_string())),
+/// Remainder of the division with the sign of the divisor, as Python's `%`.
+fn floor_mod_i64(a: i64, b: i64) -> i64 {
+    let r = a % b;
+    if r != 0 && (r < 0) != (b < 0) {
+        r + b
+    } else {
+        r
+    }
+}
 
-            Transform::AsF64 => properties_str
-                .parse::<f64>()
-                .map(Value::Float)
-                .map_err(|e: ParseFloatError| PyValueError::new_err(e.to_string())),
+fn floor_mod_f64(a: f64, b: f64) -> f64 {
+    let r = a % b;
+    if r != 0. && (r < 0.) != (b < 0.) {
+        r + b
+    } else {
+        r
+    }
+}
 
-            Transform::Length => Ok(Value::Int(properties_str.len() as i64)),
-        }
+/// Quotient of the division rounded towards negative infinity, as Python's
+/// `//`.
+fn floor_div_i64(a: i64, b: i64) -> i64 {
+    let q = a / b;
+    if a % b != 0 && (a < 0) != (b < 0) {
+        q - 1
+    } else {
+        q
     }
+}
 
-    fn preprocess(&self, queue: &mut Iter<'_, Ops>) -> PyResult<Value> {
-        let op = queue.next().ok_or_else(|| {
-            PyRuntimeError::new_err("The vector of operations must never be empty.")
-        })?;
+/// Indexes selected by the slice `[start:stop:step]` of a sequence of length
+/// `n`, following Python semantics.
+fn slice_indices(
+    n: usize,
+    start: Option<i64>,
+    stop: Option<i64>,
+    step: i64,
+) -> impl Iterator<Item = usize> {
+    let n = n as i64;
+    let adjust = |i: i64, lower: i64, upper: i64| if i < 0 { i + n } else { i }.clamp(lower, upper);
+    let (start, stop) = if step > 0 {
+        (
+            start.map_or(0, |i| adjust(i, 0, n)),
+            stop.map_or(n, |i| adjust(i, 0, n)),
+        )
+    } else {
+        (
+            start.map_or(n - 1, |i| adjust(i, -1, n - 1)),
+            stop.map_or(-1, |i| adjust(i, -1, n - 1)),
+        )
+    };
+    std::iter::successors(Some(start), move |i| Some(i + step))
+        .take_while(move |&i| if step > 0 { i < stop } else { i > stop })
+        .map(|i| i as usize)
+}
 
-        let key = match op {
-            Ops::ItemGetter(k) => k,
-            _ => {
-                return Err(PyValueError::new_err(
-                    "First operator must be 'Ops::ItemGetter'. Use 'my_var[my_key]'.",
-                ));
-            }
-        };
+fn first_operator_error() -> PyErr {
+    PyValueError::new_err("First operator must be 'Ops::ItemGetter'. Use 'my_var[my_key]'.")
+}
 
-        let transform = match queue.next() {
-            Some(Ops::Transform(t)) => t,
-            _ => {
-                return Err(PyValueError::new_err(concat!(
-                    "A 'Ops::ItemGetter' must be followed by a 'Ops::Transform' operator. ",
-                    "Use 'obj.int()', 'obj.float()' or 'obj.len()'.",
-                )));
+impl Value {
+    /// Applies the operations following the access to a key of a geometry.
+    fn process(self, ops: &[Ops]) -> PyResult<Value> {
+        let mut value = self;
+        for op in ops {
+            value = match op {
+                Ops::AddI64(other) => value.add_i64(*other)?,
+                Ops::AddF64(other) => value.add_f64(*other)?,
+                Ops::SubI64(other) => value.sub_i64(*other)?,
+                Ops::SubF64(other) => value.sub_f64(*other)?,
+                Ops::MulI64(other) => value.mul_i64(*other)?,
+                Ops::MulF64(other) => value.mul_f64(*other)?,
+                Ops::DivI64(other) => value.div_i64(*other)?,
+                Ops::DivF64(other) => value.div_f64(*other)?,
+                Ops::ModI64(other) => value.mod_i64(*other)?,
+                Ops::ModF64(other) => value.mod_f64(*other)?,
+                Ops::FloorDivI64(other) => value.floor_div_i64(*other)?,
+                Ops::FloorDivF64(other) => value.floor_div_f64(*other)?,
+                Ops::Transform(Transform::Abs) => value.abs()?,
+                Ops::Transform(Transform::AsI64) => value.as_i64()?,
+                Ops::Transform(Transform::AsF64) => value.as_f64()?,
+                Ops::Transform(Transform::Length) => value.len()?,
+                Ops::Transform(Transform::AsStr) => value.into_str(),
+                Ops::Transform(Transform::Lower) => {
+                    value.map_str("lower", |x| Value::Str(x.to_lowercase()))?
+                }
+                Ops::Transform(Transform::Upper) => {
+                    value.map_str("upper", |x| Value::Str(x.to_uppercase()))?
+                }
+                Ops::ItemGetter(key) => value.item(key)?,
+                Ops::AddStr(other) => value.add_str(other)?,
+                Ops::Index(index) => value.index(*index)?,
+                Ops::Slice(start, stop, step) => value.slice(*start, *stop, *step)?,
+                Ops::StartsWith(prefix) => {
+                    value.map_str("startswith", |x| Value::Bool(x.starts_with(prefix)))?
+                }
+                Ops::EndsWith(suffix) => {
+                    value.map_str("endswith", |x| Value::Bool(x.ends_with(suffix)))?
+                }
+                Ops::IsIn(values) => Value::Bool(values.contains(&value)),
             }
-        };
+        }
+        Ok(value)
+    }
+}
 
-        match key.as_str() {
-            "id" => self.transform_id(transform),
-            "properties" => self.transform_properties(transform),
-            "bbox" => self.transform_bbox(transform),
+impl Geometry {
+    /// Value of a key of the geometry: `id`, `properties`, `bbox` or one of
+    /// the values derived from its shape, `type`, `arc_count`, `ring_count`
+    /// and `area`. The properties are parsed as JSON so that their fields can
+    /// be accessed.
+    fn item(&self, key: &str, topology: &TopoJSON) -> PyResult<Value> {
+        match key {
+            "id" => Ok(self.id().map(Value::Str).unwrap_or(Value::Null)),
+            "properties" => Ok(match self.properties() {
+                Some(properties) => match serde_json::from_str(&properties) {
+                    Ok(properties) => Value::from_json(properties),
+                    Err(_) => Value::Str(properties),
+                },
+                None => Value::Null,
+            }),
+            "bbox" => Ok(self
+                .bbox()
+                .map(|bbox| Value::Array(bbox.into_iter().map(serde_json::Value::from).collect()))
+                .unwrap_or(Value::Null)),
+            "type" => Ok(Value::Str(self.type_name().to_string())),
+            "arc_count" => Ok(Value::Int(self.arc_count() as i64)),
+            "ring_count" => Ok(Value::Int(self.ring_count() as i64)),
+            "area" => Ok(Value::Float(planar_area(&object_func(topology, self)))),
             _ => Err(PyValueError::new_err(format!(
                 "Unknown or not implemented key {:?}",
                 key
@@ -507,36 +1390,79 @@ impl Geometry {
         }
     }
 
-    fn process(&self, ops: &Vec<Ops>) -> PyResult<Value> {
-        let mut queue = ops.iter();
-        let mut value = self.preprocess(&mut queue)?;
-        while let Some(op) = queue.next() {
-            value = match *op {
-                Ops::AddI64(other) => value.add_i64(other),
-                Ops::AddF64(other) => value.add_f64(other),
-                Ops::SubI64(other) => value.sub_i64(other),
-                Ops::SubF64(other) => value.sub_f64(other),
-                Ops::MulI64(other) => value.mul_i64(other),
-                Ops::MulF64(other) => value.mul_f64(other),
-                Ops::DivI64(other) => value.div_i64(other)?,
-                Ops::DivF64(other) => value.div_f64(other)?,
-                Ops::Transform(Transform::AsI64) => value.as_i64(),
-                Ops::Transform(Transform::AsF64) => value.as_f64(),
-                Ops::Transform(Transform::Length) => {
-                    return Err(PyTypeError::new_err(format!(
-                        "object of type '{}' has no len()",
-                        value.value_type()
-                    )));
-                }
-                Ops::ItemGetter(_) => {
-                    return Err(PyTypeError::new_err(format!(
-                        "'{}' object is not subcriptable",
-                        value.value_type()
-                    )));
-                }
+    fn type_name(&self) -> &'static str {
+        match self {
+            Geometry::GeometryCollection { .. } => "GeometryCollection",
+            Geometry::Point { .. } => "Point",
+            Geometry::MultiPoint { .. } => "MultiPoint",
+            Geometry::LineString { .. } => "LineString",
+            Geometry::MultiLineString { .. } => "MultiLineString",
+            Geometry::Polygon { .. } => "Polygon",
+            Geometry::MultiPolygon { .. } => "MultiPolygon",
+        }
+    }
+
+    /// Number of references to arcs, a shared arc being counted once per
+    /// reference.
+    fn arc_count(&self) -> usize {
+        match self {
+            Geometry::GeometryCollection { geometries, .. } => {
+                geometries.iter().map(|o| o.arc_count()).sum()
             }
+            Geometry::Point { .. } | Geometry::MultiPoint { .. } => 0,
+            Geometry::LineString { arcs, .. } => arcs.len(),
+            Geometry::MultiLineString { arcs, .. } | Geometry::Polygon { arcs, .. } => {
+                arcs.iter().map(|arcs| arcs.len()).sum()
+            }
+            Geometry::MultiPolygon { arcs, .. } => arcs
+                .iter()
+                .flat_map(|polygon| polygon.iter().map(|arcs| arcs.len()))
+                .sum(),
+        }
+    }
+
+    /// Number of rings, exterior and interior, of the polygons.
+    fn ring_count(&self) -> usize {
+        match self {
+            Geometry::GeometryCollection { geometries, .. } => {
+                geometries.iter().map(|o| o.ring_count()).sum()
+            }
+            Geometry::Polygon { arcs, .. } => arcs.len(),
+            Geometry::MultiPolygon { arcs, .. } => arcs.iter().map(|polygon| polygon.len()).sum(),
+            _ => 0,
+        }
+    }
+
+    fn process(&self, ops: &[Ops], topology: &TopoJSON) -> PyResult<Value> {
+        match ops.split_first() {
+            Some((Ops::ItemGetter(key), ops)) => self.item(key, topology)?.process(ops),
+            Some(_) => Err(first_operator_error()),
+            None => Err(PyRuntimeError::new_err(
+                "The vector of operations must never be empty.",
+            )),
         }
-        Ok(value)
+    }
+}
+
+/// Planar area of the polygons of a geometry, holes excluded, in the units of
+/// the decoded coordinates.
+fn planar_area(geometry: &FeatureGeometryType) -> f64 {
+    let polygon = |rings: &[Vec<[f64; 2]>]| match rings.split_first() {
+        Some((exterior, holes)) => {
+            let holes = holes.iter().map(|hole| planar_ring_area(hole)).sum::<f64>();
+            (planar_ring_area(exterior) - holes) / 2.
+        }
+        None => 0.,
+    };
+    match geometry {
+        FeatureGeometryType::GeometryCollection { geometries } => {
+            geometries.iter().map(planar_area).sum()
+        }
+        FeatureGeometryType::Polygon { coordinates } => polygon(coordinates),
+        FeatureGeometryType::MultiPolygon { coordinates } => {
+            coordinates.iter().map(|rings| polygon(rings)).sum()
+        }
+        _ => 0.,
     }
 }
 
@@ -544,6 +1470,15 @@ impl Geometry {
 mod tests {
     use super::*;
 
+    fn topology() -> TopoJSON {
+        TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: Default::default(),
+            arcs: Vec::new(),
+        }
+    }
+
     #[test]
     fn test_geovar_items() {
         Python::initialize();
@@ -556,15 +1491,15 @@ mod tests {
         };
 
         for key in ["id", "properties", "bbox"] {
-            let a = var()
-                .__getitem__(key)
+            let a = GeoVar::new()
+                .item(key)
                 .and_then(|v| if key == "bbox" { v.len() } else { v.int() })
                 .unwrap();
-            let b = var()
-                .__getitem__(key)
+            let b = GeoVar::new()
+                .item(key)
                 .and_then(|v| if key == "bbox" { v.len() } else { v.int() })
                 .unwrap();
-            let result = a.__eq__(&b).compare(&geom, &geom);
+            let result = a.__eq__((&b).into()).compare(&geom, &geom);
             assert!(result.map(|value| value.into_bool()).unwrap_or(false));
         }
     }
@@ -581,9 +1516,9 @@ mod tests {
         };
 
         let key = "bbox";
-        let a = var().__getitem__(key).and_then(|v| v.int()).unwrap();
-        let b = var().__getitem__(key).and_then(|v| v.int()).unwrap();
-        let result = a.__eq__(&b).compare(&geom, &geom);
+        let a = GeoVar::new().item(key).and_then(|v| v.int()).unwrap();
+        let b = GeoVar::new().item(key).and_then(|v| v.int()).unwrap();
+        let result = a.__eq__((&b).into()).compare(&geom, &geom);
         assert!(
             result
                 .err()
@@ -604,7 +1539,7 @@ mod tests {
         };
 
         let key = "bbox";
-        let a = var().__getitem__(key).and_then(|v| v.int()).unwrap();
+        let a = GeoVar::new().item(key).and_then(|v| v.int()).unwrap();
         let result = a.compare(&geom, &geom);
         assert!(
             result
@@ -625,16 +1560,616 @@ mod tests {
             bbox: Some(vec![]),
         };
 
-        let a = var().__getitem__("bbox").unwrap();
-        let b = var().__getitem__("bbox").unwrap();
-        let result = a.__eq__(&b).compare(&geom, &geom);
+        let a = GeoVar::new().item("geometry").unwrap();
+        let b = GeoVar::new().item("geometry").unwrap();
+        let result = a.__eq__((&b).into()).compare(&geom, &geom);
         assert!(
             result
                 .err()
-                .map(|err| err
-                    .to_string()
-                    .contains("A 'Ops::ItemGetter' must be followed"))
+                .map(|err| err.to_string().contains("Unknown or not implemented key"))
                 .unwrap_or(false),
         );
     }
+
+    fn state(id: &str, properties: &str) -> Geometry {
+        Geometry::Polygon {
+            arcs: vec![],
+            id: Some(id.to_string()),
+            properties: Some(properties.to_string()),
+            bbox: None,
+        }
+    }
+
+    #[test]
+    fn test_geovar_nested_properties() {
+        Python::initialize();
+
+        let geom1 = state(
+            "06001",
+            r#"{"STATE":"06","POP":1,"LAND":true,"META":{"CODE":null}}"#,
+        );
+        let geom2 = state(
+            "06003",
+            r#"{"STATE":"06","POP":1.0,"LAND":false,"META":{"CODE":null}}"#,
+        );
+        let geom3 = state(
+            "32001",
+            r#"{"STATE":"32","POP":2,"LAND":true,"META":{"CODE":"NV"}}"#,
+        );
+
+        let eq = |keys: &[&str], geom1: &Geometry, geom2: &Geometry| {
+            let path = |v: PyResult<GeoVar>| {
+                keys.iter()
+                    .fold(v.and_then(|v| v.item("properties")), |v, key| {
+                        v.and_then(|v| v.item(key))
+                    })
+                    .unwrap()
+            };
+            let a = path(Ok(GeoVar::new()));
+            let b = path(Ok(GeoVar::new()));
+            a.__eq__((&b).into())
+                .compare(geom1, geom2)
+                .unwrap()
+                .into_bool()
+        };
+
+        assert!(eq(&["STATE"], &geom1, &geom2));
+        assert!(!eq(&["STATE"], &geom1, &geom3));
+        assert!(eq(&["POP"], &geom1, &geom2));
+        assert!(!eq(&["LAND"], &geom1, &geom2));
+        assert!(eq(&["LAND"], &geom1, &geom3));
+        assert!(eq(&["META", "CODE"], &geom1, &geom2));
+        assert!(!eq(&["META", "CODE"], &geom1, &geom3));
+        assert!(eq(&["META"], &geom1, &geom2));
+    }
+
+    #[test]
+    fn test_geovar_err_nested_properties() {
+        Python::initialize();
+
+        let geom = state("06001", r#"{"STATE":"06","POP":1}"#);
+        for (keys, message) in [
+            (vec!["COUNTY"], "KeyError: 'COUNTY'"),
+            (
+                vec!["POP", "VALUE"],
+                "TypeError: 'int' object is not subscriptable",
+            ),
+        ] {
+            let a = keys
+                .iter()
+                .fold(GeoVar::new().item("properties"), |v, key| {
+                    v.and_then(|v| v.item(key))
+                })
+                .unwrap();
+            assert_eq!(
+                a.evaluate(&topology(), &geom)
+                    .err()
+                    .map(|err| err.to_string()),
+                Some(message.to_string())
+            );
+        }
+
+        let a = GeoVar::new()
+            .item("properties")
+            .and_then(|v| v.item("STATE"))
+            .and_then(|v| v.float())
+            .unwrap();
+        assert!(matches!(
+            a.evaluate(&topology(), &geom),
+            Ok(Value::Float(6.))
+        ));
+        let a = GeoVar::new().item("id").and_then(|v| v.len()).unwrap();
+        assert!(matches!(a.evaluate(&topology(), &geom), Ok(Value::Int(5))));
+    }
+
+    #[test]
+    fn test_geovar_string_ops() {
+        Python::initialize();
+
+        let geom1 = state("06001", r#"{"NAME":"Alameda"}"#);
+        let geom2 = state("06003", r#"{"NAME":"Alpine"}"#);
+        let geom3 = state("32001", r#"{"NAME":"Churchill"}"#);
+
+        Python::attach(|py| {
+            let eval = |code: &std::ffi::CStr| py.eval(code, None, None).unwrap();
+            let prefix = |v: GeoVar| v.__getitem__(&eval(c"slice(None, 2)")).unwrap();
+
+            let a = prefix(GeoVar::new().item("id").unwrap());
+            let b = prefix(GeoVar::new().item("id").unwrap());
+            let filter = a.__ne__((&b).into());
+            assert!(!filter.compare(&geom1, &geom2).unwrap().into_bool());
+            assert!(filter.compare(&geom1, &geom3).unwrap().into_bool());
+
+            let name = || {
+                GeoVar::new()
+                    .item("properties")
+                    .and_then(|v| v.item("NAME"))
+            };
+            for (v, expected) in [
+                (
+                    prefix(GeoVar::new().item("id").unwrap()),
+                    Value::Str("06".to_string()),
+                ),
+                (
+                    GeoVar::new()
+                        .item("id")
+                        .and_then(|v| v.__getitem__(&eval(c"slice(None, None, -2)")))
+                        .unwrap(),
+                    Value::Str("100".to_string()),
+                ),
+                (
+                    GeoVar::new()
+                        .item("id")
+                        .and_then(|v| v.__getitem__(&eval(c"-1")))
+                        .unwrap(),
+                    Value::Str("1".to_string()),
+                ),
+                (
+                    name().and_then(|v| v.lower()).unwrap(),
+                    Value::Str("alameda".to_string()),
+                ),
+                (
+                    name().and_then(|v| v.upper()).unwrap(),
+                    Value::Str("ALAMEDA".to_string()),
+                ),
+                (
+                    name()
+                        .and_then(|v| v.startswith("Ala".to_string()))
+                        .unwrap(),
+                    Value::Bool(true),
+                ),
+                (
+                    name().and_then(|v| v.endswith("Ala".to_string())).unwrap(),
+                    Value::Bool(false),
+                ),
+                (
+                    name().and_then(|v| v.__add__(&eval(c"' County'"))).unwrap(),
+                    Value::Str("Alameda County".to_string()),
+                ),
+                (
+                    prefix(GeoVar::new().item("id").unwrap())
+                        .isin(&eval(c"{'06', '32'}"))
+                        .unwrap(),
+                    Value::Bool(true),
+                ),
+                (
+                    GeoVar::new()
+                        .item("id")
+                        .and_then(|v| v.int())
+                        .and_then(|v| v.str())
+                        .unwrap(),
+                    Value::Str("6001".to_string()),
+                ),
+            ] {
+                assert_eq!(v.evaluate(&topology(), &geom1).unwrap(), expected);
+            }
+        });
+    }
+
+    #[test]
+    fn test_geovar_err_string_ops() {
+        Python::initialize();
+
+        let geom = state("06001", r#"{"POP":1}"#);
+
+        Python::attach(|py| {
+            let eval = |code: &std::ffi::CStr| py.eval(code, None, None).unwrap();
+            let pop = || GeoVar::new().item("properties").and_then(|v| v.item("POP"));
+            for (v, message) in [
+                (
+                    pop().and_then(|v| v.lower()).unwrap(),
+                    "AttributeError: 'int' object has no attribute 'lower'",
+                ),
+                (
+                    pop().and_then(|v| v.__add__(&eval(c"'x'"))).unwrap(),
+                    "TypeError: unsupported operand type(s) for +: 'int' and 'str'",
+                ),
+                (
+                    GeoVar::new()
+                        .item("id")
+                        .and_then(|v| v.__getitem__(&eval(c"5")))
+                        .unwrap(),
+                    "IndexError: string index out of range",
+                ),
+                (
+                    pop().and_then(|v| v.__getitem__(&eval(c"0"))).unwrap(),
+                    "TypeError: 'int' object is not subscriptable",
+                ),
+            ] {
+                assert_eq!(
+                    v.evaluate(&topology(), &geom)
+                        .err()
+                        .map(|err| err.to_string()),
+                    Some(message.to_string())
+                );
+            }
+            assert_eq!(
+                GeoVar::new()
+                    .item("id")
+                    .and_then(|v| v.__getitem__(&eval(c"slice(None, None, 0)")))
+                    .err()
+                    .map(|err| err.to_string()),
+                Some("ValueError: slice step cannot be zero".to_string())
+            );
+        });
+    }
+
+    #[test]
+    fn test_geovar_ordering() {
+        Python::initialize();
+
+        let geom1 = state("06001", r#"{"POP":20000,"NAME":"Alameda"}"#);
+        let geom2 = state("06003", r#"{"POP":5000,"NAME":"Alpine"}"#);
+
+        let a = GeoVar::new();
+        let b = GeoVar::new();
+        let pop = |v: &GeoVar| v.item("properties").and_then(|v| v.item("POP")).unwrap();
+        let name = |v: &GeoVar| v.item("properties").and_then(|v| v.item("NAME")).unwrap();
+        let large = |v: &GeoVar| pop(v).__gt__(Value::Int(10000).into());
+
+        let filter = large(&a).__and__((&large(&b)).into());
+        assert!(!filter.compare(&geom1, &geom2).unwrap().into_bool());
+        assert!(filter.compare(&geom1, &geom1).unwrap().into_bool());
+
+        // The variable appearing first in the expression is bound to the
+        // first geometry, whatever the order in which variables are created.
+        let filter = pop(&b).__lt__((&pop(&a)).into());
+        assert!(!filter.compare(&geom1, &geom2).unwrap().into_bool());
+        assert!(filter.compare(&geom2, &geom1).unwrap().into_bool());
+
+        // `var(0)` and `var(1)` are bound explicitly.
+        let [first, second] = [0, 1].map(|position| var(Some(position)).unwrap());
+        for filter in [
+            pop(&second).__lt__((&pop(&first)).into()),
+            pop(&first).__gt__((&pop(&second)).into()),
+            pop(&second).__lt__((&pop(&a)).into()),
+            pop(&b).__gt__((&pop(&second)).into()),
+        ] {
+            assert!(filter.compare(&geom1, &geom2).unwrap().into_bool());
+            assert!(!filter.compare(&geom2, &geom1).unwrap().into_bool());
+        }
+        Python::attach(|_| {
+            assert_eq!(
+                var(Some(2)).err().map(|err| err.to_string()),
+                Some("ValueError: 'position' must be 0 or 1".to_string())
+            );
+        });
+
+        for (filter, expected) in [
+            (pop(&a).__ge__(Value::Int(20000).into()), true),
+            (pop(&a).__le__(Value::Float(19999.5).into()), false),
+            (name(&a).__lt__((&name(&b)).into()), true),
+            (a.__eq__((&b).into()).__invert__(), true),
+            (large(&a).__or__(Value::Bool(false).into()), true),
+        ] {
+            assert_eq!(
+                filter.compare(&geom1, &geom2).unwrap().into_bool(),
+                expected
+            );
+        }
+
+        assert_eq!(
+            pop(&a)
+                .min((&pop(&b)).into())
+                .compare(&geom1, &geom2)
+                .unwrap(),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            pop(&a)
+                .min((&pop(&b)).into())
+                .evaluate(&topology(), &geom1)
+                .unwrap(),
+            Value::Int(20000)
+        );
+        assert_eq!(
+            pop(&a)
+                .max(Value::Int(30000).into())
+                .evaluate(&topology(), &geom1)
+                .unwrap(),
+            Value::Int(30000)
+        );
+    }
+
+    #[test]
+    fn test_geovar_modulo() {
+        Python::initialize();
+
+        let geom = state("06001", r#"{"X":-7,"Y":7.5}"#);
+
+        Python::attach(|py| {
+            let eval = |code: &std::ffi::CStr| py.eval(code, None, None).unwrap();
+            let x = || GeoVar::new().item("properties").and_then(|v| v.item("X"));
+            let y = || GeoVar::new().item("properties").and_then(|v| v.item("Y"));
+            for (v, expected) in [
+                (x().and_then(|v| v.__mod__(&eval(c"3"))), Value::Int(2)),
+                (x().and_then(|v| v.__mod__(&eval(c"-3"))), Value::Int(-1)),
+                (
+                    x().and_then(|v| v.__floordiv__(&eval(c"2"))),
+                    Value::Int(-4),
+                ),
+                (
+                    y().and_then(|v| v.__mod__(&eval(c"-2"))),
+                    Value::Float(-0.5),
+                ),
+                (
+                    y().and_then(|v| v.__floordiv__(&eval(c"2.0"))),
+                    Value::Float(3.),
+                ),
+                (x().and_then(|v| v.__abs__()), Value::Int(7)),
+            ] {
+                assert_eq!(v.unwrap().evaluate(&topology(), &geom).unwrap(), expected);
+            }
+        });
+    }
+
+    #[test]
+    fn test_geovar_binary_arithmetic() {
+        Python::initialize();
+
+        let geom1 = state("06001", r#"{"X":7,"Y":2.5}"#);
+        let geom2 = state("32003", r#"{"X":3,"Y":0.5}"#);
+
+        Python::attach(|py| {
+            let a = GeoVar::new();
+            let b = GeoVar::new();
+            let operand = |v: &GeoVar| {
+                Bound::new(
+                    py,
+                    GeoVar {
+                        inner: v.inner.clone(),
+                    },
+                )
+                .unwrap()
+                .into_any()
+            };
+            let x = |v: &GeoVar| v.item("properties").and_then(|v| v.item("X")).unwrap();
+            let y = |v: &GeoVar| v.item("properties").and_then(|v| v.item("Y")).unwrap();
+            let id = |v: &GeoVar| v.item("id").and_then(|v| v.int()).unwrap();
+            for (filter, expected) in [
+                (x(&a).__sub__(&operand(&x(&b))), Value::Int(4)),
+                (x(&a).__add__(&operand(&y(&b))), Value::Float(7.5)),
+                (x(&a).__mul__(&operand(&x(&b))), Value::Int(21)),
+                (y(&a).__truediv__(&operand(&y(&b))), Value::Float(5.)),
+                (x(&a).__mod__(&operand(&x(&b))), Value::Int(1)),
+                (x(&a).__floordiv__(&operand(&x(&b))), Value::Int(2)),
+                (
+                    a.item("id")
+                        .unwrap()
+                        .__add__(&operand(&b.item("id").unwrap())),
+                    Value::Str("0600132003".to_string()),
+                ),
+                // Operations apply to the result of the binary node.
+                (
+                    id(&a)
+                        .__sub__(&operand(&id(&b)))
+                        .and_then(|v| v.__abs__())
+                        .and_then(|v| {
+                            v.__floordiv__(&1000i64.into_pyobject(py).unwrap().into_any())
+                        }),
+                    Value::Int(26),
+                ),
+            ] {
+                let filter = filter.unwrap().__eq__(expected.into());
+                assert!(filter.compare(&geom1, &geom2).unwrap().into_bool());
+            }
+
+            let far = id(&a)
+                .__sub__(&operand(&id(&b)))
+                .and_then(|v| v.__abs__())
+                .unwrap()
+                .__ge__(Value::Int(1000).into());
+            assert!(far.compare(&geom1, &geom2).unwrap().into_bool());
+            assert!(!far.compare(&geom1, &geom1).unwrap().into_bool());
+
+            assert_eq!(
+                a.item("id")
+                    .unwrap()
+                    .__sub__(&operand(&x(&b)))
+                    .unwrap()
+                    .__eq__(Value::Int(0).into())
+                    .compare(&geom1, &geom2)
+                    .err()
+                    .map(|err| err.to_string()),
+                Some("TypeError: unsupported operand type(s) for -: 'str' and 'int'".to_string())
+            );
+        });
+    }
+
+    #[test]
+    fn test_geovar_err_ordering() {
+        Python::initialize();
+
+        let geom = state("06001", r#"{"X":0}"#);
+
+        Python::attach(|py| {
+            let a = GeoVar::new();
+            let b = GeoVar::new();
+            let x = a.item("properties").and_then(|v| v.item("X")).unwrap();
+            for (filter, message) in [
+                (
+                    a.__lt__((&b).into()),
+                    "TypeError: '<' not supported between instances of 'Geometry' and 'Geometry'",
+                ),
+                (
+                    a.item("id").unwrap().__gt__(Value::Int(0).into()),
+                    "TypeError: '>' not supported between instances of 'str' and 'int'",
+                ),
+                (
+                    x.__mod__(&0i64.into_pyobject(py).unwrap().into_any())
+                        .unwrap()
+                        .__eq__(Value::Int(0).into()),
+                    "ZeroDivisionError: integer modulo by zero",
+                ),
+                (
+                    a.item("id").and_then(|v| v.__abs__()).unwrap().__invert__(),
+                    "TypeError: bad operand type for abs(): 'str'",
+                ),
+            ] {
+                assert_eq!(
+                    filter
+                        .compare(&geom, &geom)
+                        .err()
+                        .map(|err| err.to_string()),
+                    Some(message.to_string())
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn test_geovar_plan() {
+        Python::initialize();
+
+        let geom1 = state("06001", r#"{"STATE":"06"}"#);
+        let geom2 = state("06003", r#"{"STATE":"06"}"#);
+        let geom3 = state("32001", r#"{"STATE":"32"}"#);
+
+        let a = GeoVar::new();
+        let b = GeoVar::new();
+        let state = |v: &GeoVar| v.item("properties").and_then(|v| v.item("STATE")).unwrap();
+        let filter = a
+            .__ne__((&b).into())
+            .__and__((&state(&a).__eq__((&state(&b)).into())).into());
+
+        let topology = topology();
+        let mut plan = filter.compile(&topology).unwrap();
+        // `a["properties"]["STATE"]` and `b["properties"]["STATE"]` share a
+        // single key and a single operand.
+        assert_eq!(plan.keys.len(), 1);
+        assert_eq!(plan.operands.len(), 1);
+        assert_eq!(plan.steps.len(), 7);
+
+        for (geom1, geom2, expected) in [
+            (&geom1, &geom2, true),
+            (&geom1, &geom3, false),
+            (&geom2, &geom2, false),
+            (&geom2, &geom1, true),
+        ] {
+            assert_eq!(plan.compare(geom1, geom2).unwrap(), expected);
+        }
+        assert_eq!(plan.cache.len(), 3);
+        assert!(
+            plan.cache
+                .values()
+                .all(|values| values.iter().all(|value| value.is_some()))
+        );
+
+        assert_eq!(
+            state(&a).compile(&topology).err().map(|e| e.to_string()),
+            Some(
+                "RuntimeError: Cannot compare geometries without two distinct values.".to_string()
+            )
+        );
+    }
+
+    //
+    // +-----------+
+    // |           |     +-----+
+    // |  +-----+  |     |     |
+    // |  |     |  |     +-----+
+    // |  +-----+  |
+    // |           |  ----------
+    // +-----------+
+    //
+    fn shapes_topology() -> TopoJSON {
+        TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: Default::default(),
+            arcs: vec![
+                vec![[0., 0.], [0., 3.], [3., 3.], [3., 0.], [0., 0.]],
+                vec![[1., 1.], [2., 1.], [2., 2.], [1., 2.], [1., 1.]],
+                vec![[4., 2.], [4., 3.], [5., 3.], [5., 2.], [4., 2.]],
+                vec![[3.5, 0.5], [5.5, 0.5]],
+            ],
+        }
+    }
+
+    #[test]
+    fn test_geovar_geometry_keys() {
+        Python::initialize();
+
+        let topology = shapes_topology();
+        let geom1 = Geometry::Polygon {
+            arcs: vec![vec![0], vec![1]],
+            id: None,
+            properties: None,
+            bbox: None,
+        };
+        let geom2 = Geometry::MultiPolygon {
+            arcs: vec![vec![vec![2]]],
+            id: None,
+            properties: None,
+            bbox: None,
+        };
+        let geom3 = Geometry::LineString {
+            arcs: vec![3],
+            id: None,
+            properties: None,
+            bbox: None,
+        };
+
+        for (geom, expected) in [
+            (
+                &geom1,
+                [
+                    Value::Str("Polygon".to_string()),
+                    Value::Int(2),
+                    Value::Int(2),
+                    Value::Float(8.),
+                ],
+            ),
+            (
+                &geom2,
+                [
+                    Value::Str("MultiPolygon".to_string()),
+                    Value::Int(1),
+                    Value::Int(1),
+                    Value::Float(1.),
+                ],
+            ),
+            (
+                &geom3,
+                [
+                    Value::Str("LineString".to_string()),
+                    Value::Int(1),
+                    Value::Int(0),
+                    Value::Float(0.),
+                ],
+            ),
+        ] {
+            for (key, expected) in ["type", "arc_count", "ring_count", "area"]
+                .iter()
+                .zip(expected)
+            {
+                assert_eq!(
+                    GeoVar::new()
+                        .item(key)
+                        .unwrap()
+                        .evaluate(&topology, geom)
+                        .unwrap(),
+                    expected
+                );
+            }
+        }
+
+        // Only borders between polygons of more than one unit of area.
+        let a = GeoVar::new();
+        let b = GeoVar::new();
+        let large = |v: &GeoVar| {
+            v.item("type")
+                .unwrap()
+                .__ne__(Value::Str("LineString".to_string()).into())
+                .__and__((&v.item("area").unwrap().__gt__(Value::Int(1).into())).into())
+        };
+        let filter = large(&a).__and__((&large(&b)).into());
+        let mut plan = filter.compile(&topology).unwrap();
+        assert!(plan.compare(&geom1, &geom1).unwrap());
+        assert!(!plan.compare(&geom1, &geom2).unwrap());
+        assert!(!plan.compare(&geom3, &geom1).unwrap());
+        // `type` and `area` are computed once per geometry.
+        assert_eq!(plan.keys, vec!["type", "area"]);
+        assert_eq!(plan.cache.len(), 3);
+    }
 }
@@ -1,23 +1,39 @@
+mod adjacency;
 mod api;
 mod bbox;
 mod bisect;
+mod borders;
+mod color;
+mod dissolve;
 mod feature;
+mod filter;
 mod geojsons;
+mod haversine;
+mod index;
 mod lambda;
+mod measure;
 mod merge;
 mod mesh;
+mod nearest;
 mod neighbors;
 mod quantize;
 #[cfg(test)]
 mod request;
 mod reverse;
+mod rtree;
+mod simplify;
 mod stitch;
 mod topojsons;
 mod transform;
+mod unquantize;
 mod untransform;
 
 use crate::geojsons::{Feature, FeatureCollection, FeatureGeometryType, GeoJSON};
+use crate::index::SpatialIndex;
 use crate::lambda::{GeoVar, var};
+use crate::measure::Measure;
+use crate::neighbors::WeightedNeighbors;
+use crate::quantize::QuantizationReport;
 use crate::topojsons::{TopoJSON, Transform};
 
 use std::fs;
@@ -42,13 +58,33 @@ fn topojson(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<Feature>()?;
     m.add_class::<FeatureGeometryType>()?;
     m.add_class::<GeoVar>()?;
+    m.add_class::<QuantizationReport>()?;
+    m.add_class::<Measure>()?;
+    m.add_class::<WeightedNeighbors>()?;
+    m.add_class::<SpatialIndex>()?;
     m.add_function(wrap_pyfunction!(read, m)?)?;
     m.add_function(wrap_pyfunction!(var, m)?)?;
     m.add_function(wrap_pyfunction!(api::feature, m)?)?;
     m.add_function(wrap_pyfunction!(api::merge, m)?)?;
+    m.add_function(wrap_pyfunction!(api::dissolve, m)?)?;
+    m.add_function(wrap_pyfunction!(api::filter, m)?)?;
     m.add_function(wrap_pyfunction!(api::mesh, m)?)?;
+    m.add_function(wrap_pyfunction!(api::merge_arcs, m)?)?;
+    m.add_function(wrap_pyfunction!(api::mesh_arcs, m)?)?;
+    m.add_function(wrap_pyfunction!(api::borders, m)?)?;
     m.add_function(wrap_pyfunction!(api::bbox, m)?)?;
+    m.add_function(wrap_pyfunction!(api::object_bbox, m)?)?;
+    m.add_function(wrap_pyfunction!(api::compute_geometry_bboxes, m)?)?;
+    m.add_function(wrap_pyfunction!(api::index, m)?)?;
+    m.add_function(wrap_pyfunction!(api::nearest, m)?)?;
+    m.add_function(wrap_pyfunction!(api::measure, m)?)?;
     m.add_function(wrap_pyfunction!(api::neighbors, m)?)?;
+    m.add_function(wrap_pyfunction!(api::neighbors_between, m)?)?;
+    m.add_function(wrap_pyfunction!(api::adjacency, m)?)?;
+    m.add_function(wrap_pyfunction!(api::color, m)?)?;
     m.add_function(wrap_pyfunction!(api::quantize, m)?)?;
+    m.add_function(wrap_pyfunction!(api::quantization_error, m)?)?;
+    m.add_function(wrap_pyfunction!(api::simplify_dp, m)?)?;
+    m.add_function(wrap_pyfunction!(api::unquantize, m)?)?;
     Ok(())
 }
@@ -0,0 +1,236 @@
+use pyo3::prelude::*;
+use rustc_hash::FxHashMap;
+
+use crate::feature::decode_arc;
+use crate::merge::ring_area;
+use crate::neighbors::line_length;
+use crate::topojsons::{Geometry, TopoJSON};
+
+#[pyclass]
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Measure {
+    #[pyo3(get)]
+    pub id: Option<String>,
+    #[pyo3(get)]
+    pub area: f64,
+    #[pyo3(get)]
+    pub perimeter: f64,
+    #[pyo3(get)]
+    pub length: f64,
+}
+
+/// Area and perimeter of the polygons and length of the lines of the
+/// geometries of `object`, or of `object` itself when it is not a collection.
+/// Measures are planar, or in metres and square metres on the sphere.
+pub fn wrap_measure(topology: &TopoJSON, object: &Geometry, spherical: bool) -> Vec<Measure> {
+    let members = object.members();
+    let mut measures = Measures {
+        topology,
+        spherical,
+        arcs: FxHashMap::default(),
+    };
+    members
+        .into_iter()
+        .map(|o| {
+            let mut measure = Measure {
+                id: o.id(),
+                ..Default::default()
+            };
+            measures.geometry(o, &mut measure);
+            measure
+        })
+        .collect()
+}
+
+struct Measures<'a> {
+    topology: &'a TopoJSON,
+    spherical: bool,
+    /// Decoded positions and length of each arc, computed once.
+    arcs: FxHashMap<usize, (Vec<[f64; 2]>, f64)>,
+}
+
+impl Measures<'_> {
+    fn arc(&mut self, i: usize) -> &(Vec<[f64; 2]>, f64) {
+        let (topology, spherical) = (self.topology, self.spherical);
+        self.arcs.entry(i).or_insert_with(|| {
+            let coordinates = decode_arc(topology, i);
+            let length = line_length(&coordinates, spherical);
+            (coordinates, length)
+        })
+    }
+
+    fn line(&mut self, arcs: &[i32]) -> f64 {
+        arcs.iter()
+            .map(|&a| self.arc(if a < 0 { !a } else { a } as usize).1)
+            .sum()
+    }
+
+    /// Area and perimeter of a ring.
+    fn ring(&mut self, arcs: &[i32]) -> (f64, f64) {
+        let mut points: Vec<[f64; 2]> = Vec::new();
+        let mut perimeter = 0.;
+        for &a in arcs {
+            let (coordinates, length) = self.arc(if a < 0 { !a } else { a } as usize);
+            perimeter += length;
+            if a < 0 {
+                points.extend(coordinates.iter().rev());
+            } else {
+                points.extend(coordinates);
+            }
+        }
+        (ring_area(&points, self.spherical), perimeter)
+    }
+
+    fn polygon(&mut self, rings: &[Vec<i32>], measure: &mut Measure) {
+        for (j, ring) in rings.iter().enumerate() {
+            let (area, perimeter) = self.ring(ring);
+            // The first ring is exterior, the others are holes.
+            measure.area += if j == 0 { area } else { -area };
+            measure.perimeter += perimeter;
+        }
+    }
+
+    fn geometry(&mut self, o: &Geometry, measure: &mut Measure) {
+        match o {
+            Geometry::GeometryCollection { geometries, .. } => {
+                geometries.iter().for_each(|o| self.geometry(o, measure))
+            }
+            Geometry::Point { .. } | Geometry::MultiPoint { .. } => {}
+            Geometry::LineString { arcs, .. } => measure.length += self.line(arcs),
+            Geometry::MultiLineString { arcs, .. } => {
+                measure.length += arcs.iter().map(|arcs| self.line(arcs)).sum::<f64>()
+            }
+            Geometry::Polygon { arcs, .. } => self.polygon(arcs, measure),
+            Geometry::MultiPolygon { arcs, .. } => {
+                arcs.iter().for_each(|rings| self.polygon(rings, measure))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::haversine::{EARTH_RADIUS, haversine};
+
+    use super::*;
+
+    //
+    //   (0,2)+-----------+ (3,2)      (3,7)+-------+ (7,7)
+    //                                      |  +-+  |
+    //   (0,1)+--+--+ (2,1)                 |  +-+  |
+    //        |A | B|                       |   C   |
+    //   (0,0)+--+--+ (2,0)            (3,3)+-------+ (7,3)
+    //
+    fn topology() -> TopoJSON {
+        TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: HashMap::new(),
+            arcs: vec![
+                vec![[1., 0.], [1., 1.]],
+                vec![[1., 1.], [0., 1.], [0., 0.], [1., 0.]],
+                vec![[1., 0.], [2., 0.], [2., 1.], [1., 1.]],
+                vec![[0., 2.], [3., 2.]],
+                vec![[3., 3.], [7., 3.], [7., 7.], [3., 7.], [3., 3.]],
+                vec![[4., 4.], [4., 5.], [5., 5.], [5., 4.], [4., 4.]],
+            ],
+        }
+    }
+
+    #[test]
+    fn test_measure_1() {
+        let topology = topology();
+        let collection = Geometry::GeometryCollection {
+            geometries: vec![
+                Geometry::Polygon {
+                    arcs: vec![vec![0, 1]],
+                    id: Some("A".to_string()),
+                    properties: None,
+                    bbox: None,
+                },
+                Geometry::Polygon {
+                    arcs: vec![vec![2, -1]],
+                    id: Some("B".to_string()),
+                    properties: None,
+                    bbox: None,
+                },
+                Geometry::LineString {
+                    arcs: vec![3],
+                    id: None,
+                    properties: None,
+                    bbox: None,
+                },
+                Geometry::MultiPolygon {
+                    arcs: vec![vec![vec![4], vec![5]]],
+                    id: Some("C".to_string()),
+                    properties: None,
+                    bbox: None,
+                },
+                Geometry::Point {
+                    coordinates: [0., 0.],
+                    id: None,
+                    properties: None,
+                    bbox: None,
+                },
+            ],
+            id: None,
+            properties: None,
+            bbox: None,
+        };
+        let measure = |id: Option<&str>, area: f64, perimeter: f64, length: f64| Measure {
+            id: id.map(|id| id.to_string()),
+            area,
+            perimeter,
+            length,
+        };
+        assert_eq!(
+            wrap_measure(&topology, &collection, false),
+            vec![
+                measure(Some("A"), 1., 4., 0.),
+                measure(Some("B"), 1., 4., 0.),
+                measure(None, 0., 0., 3.),
+                measure(Some("C"), 15., 20., 0.),
+                measure(None, 0., 0., 0.),
+            ]
+        );
+
+        // Any other object is measured as a single geometry.
+        let line = Geometry::MultiLineString {
+            arcs: vec![vec![3], vec![0]],
+            id: None,
+            properties: None,
+            bbox: None,
+        };
+        assert_eq!(
+            wrap_measure(&topology, &line, false),
+            vec![measure(None, 0., 0., 4.)]
+        );
+    }
+
+    #[test]
+    fn test_measure_2() {
+        let topology = TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: HashMap::new(),
+            arcs: vec![vec![[0., 0.], [1., 0.], [1., 1.], [0., 1.], [0., 0.]]],
+        };
+        let polygon = Geometry::Polygon {
+            arcs: vec![vec![0]],
+            id: None,
+            properties: None,
+            bbox: None,
+        };
+        let measures = wrap_measure(&topology, &polygon, true);
+        let expected = EARTH_RADIUS * EARTH_RADIUS * 1f64.to_radians() * 1f64.to_radians().sin();
+        assert!((measures[0].area / expected - 1.).abs() < 1e-3);
+        let perimeter = haversine(&[0., 0.], &[1., 0.])
+            + haversine(&[1., 0.], &[1., 1.])
+            + haversine(&[1., 1.], &[0., 1.])
+            + haversine(&[0., 1.], &[0., 0.]);
+        assert!((measures[0].perimeter - perimeter).abs() < 1e-6);
+        assert_eq!(measures[0].length, 0.);
+    }
+}
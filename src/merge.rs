@@ -1,46 +1,95 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::f64::consts::{FRAC_PI_4, PI, TAU};
 use std::rc::Rc;
 
-use crate::feature::object_func;
+use crate::feature::{decode_ring, object_func};
 use crate::geojsons::FeatureGeometryType;
+use crate::haversine::EARTH_RADIUS;
 use crate::stitch::stitch;
 use crate::topojsons::{Geometry, TopoJSON};
 
-pub fn wrap_merge(topology: &TopoJSON, objects: &[&Geometry]) -> FeatureGeometryType {
-    object_func(topology, &MergeArcs::call(topology, objects))
+pub fn wrap_merge(
+    topology: &TopoJSON,
+    objects: &[&Geometry],
+    spherical: bool,
+) -> FeatureGeometryType {
+    object_func(topology, &wrap_merge_arcs(topology, objects, spherical))
 }
 
-fn planar_ring_area(ring: &[[f64; 2]]) -> f64 {
-    let mut i = 0;
-    let n = ring.len();
-    let b = ring.last().unwrap();
+pub fn wrap_merge_arcs(topology: &TopoJSON, objects: &[&Geometry], spherical: bool) -> Geometry {
+    MergeArcs::call(topology, objects, spherical)
+}
+
+/// Twice the planar area of a ring, as topojson-client's `planarRingArea`.
+pub(crate) fn planar_ring_area(ring: &[[f64; 2]]) -> f64 {
+    planar_ring_signed_area(ring).abs()
+}
+
+/// Area of a ring, planar in the units of the coordinates or in square metres
+/// on the sphere.
+pub(crate) fn ring_area(ring: &[[f64; 2]], spherical: bool) -> f64 {
+    if spherical {
+        spherical_ring_area(ring) * EARTH_RADIUS * EARTH_RADIUS
+    } else {
+        planar_ring_area(ring) / 2.
+    }
+}
+
+/// Twice the signed planar area of a ring, positive when the ring is
+/// counterclockwise.
+pub(crate) fn planar_ring_signed_area(ring: &[[f64; 2]]) -> f64 {
+    let Some(mut b) = ring.last() else {
+        return 0.;
+    };
     let mut area: f64 = 0.;
-    while i < n {
+    for p in ring {
         let a = b;
-        let b = &ring[i];
+        b = p;
         area += a[0] * b[1] - a[1] * b[0];
-        i += 1;
     }
-    area.abs()
+    area
 }
 
-fn area(topology: &TopoJSON, ring: &[i32]) -> f64 {
-    if let FeatureGeometryType::Polygon { coordinates } = object_func(
-        topology,
-        &Geometry::Polygon {
-            arcs: vec![ring.to_vec()],
-            id: None,
-            properties: None,
-            bbox: None,
-        },
-    ) {
-        planar_ring_area(&coordinates[0])
-    } else {
-        unreachable!(
-            "Object function with 'Geometry::Polygon' must return 'FeatureGeometryType::Polygon'"
-        )
+/// Spherical area in steradians of a ring of `[longitude, latitude]` positions
+/// in degrees. The sum is d3-geo's `geoArea`, which measures the side of the
+/// ring given by its winding order, but unlike `geoArea` the smallest of the
+/// two areas delimited by the ring is returned: a ring enclosing more than a
+/// hemisphere gets the area of its complement. Merging only compares the areas
+/// of rings to find the exterior ones, which must not depend on the winding.
+fn spherical_ring_area(ring: &[[f64; 2]]) -> f64 {
+    let Some(first) = ring.first() else {
+        return 0.;
+    };
+    let mut lambda0 = first[0].to_radians();
+    let phi = first[1].to_radians() / 2. + FRAC_PI_4;
+    let mut cos_phi0 = phi.cos();
+    let mut sin_phi0 = phi.sin();
+
+    let mut sum: f64 = 0.;
+    for p in ring[1..].iter().chain(std::iter::once(first)) {
+        let lambda = p[0].to_radians();
+        let phi = p[1].to_radians() / 2. + FRAC_PI_4;
+        let d_lambda = lambda - lambda0;
+        let sd_lambda = if d_lambda >= 0. { 1. } else { -1. };
+        let ad_lambda = sd_lambda * d_lambda;
+        let cos_phi = phi.cos();
+        let sin_phi = phi.sin();
+        let k = sin_phi0 * sin_phi;
+        let u = cos_phi0 * cos_phi + k * ad_lambda.cos();
+        let v = k * sd_lambda * ad_lambda.sin();
+        sum += v.atan2(u);
+        lambda0 = lambda;
+        cos_phi0 = cos_phi;
+        sin_phi0 = sin_phi;
     }
+
+    let area = 2. * if sum < 0. { TAU + sum } else { sum };
+    area.min(4. * PI - area)
+}
+
+fn area(topology: &TopoJSON, ring: &[i32], spherical: bool) -> f64 {
+    ring_area(&decode_ring(topology, ring), spherical)
 }
 
 struct MarkedPolygon<'a> {
@@ -72,11 +121,16 @@ struct MergeArcs<'a> {
     polygons_by_arcs: HashMap<usize, Vec<SharedPolygon<'a>>>,
     polygons: Vec<SharedPolygon<'a>>,
     groups: Vec<Vec<SharedPolygon<'a>>>,
+    spherical: bool,
 }
 
 impl<'a> MergeArcs<'a> {
-    fn call(topology: &TopoJSON, objects: &[&Geometry]) -> Geometry {
-        MergeArcs::default().merge(topology, objects)
+    fn call(topology: &TopoJSON, objects: &[&Geometry], spherical: bool) -> Geometry {
+        MergeArcs {
+            spherical,
+            ..Default::default()
+        }
+        .merge(topology, objects)
     }
 
     fn merge(mut self, topology: &TopoJSON, objects: &'a [&Geometry]) -> Geometry {
@@ -125,10 +179,19 @@ impl<'a> MergeArcs<'a> {
             let mut arcs = stitch(topology, arcs);
 
             let n = arcs.len();
-            if n > 1 {
-                let mut k = area(topology, &arcs[0]);
+            if self.spherical {
+                // The exterior ring comes first, followed by holes sorted by
+                // decreasing area.
+                let mut rings: Vec<(f64, Vec<i32>)> = arcs
+                    .into_iter()
+                    .map(|ring| (area(topology, &ring, true), ring))
+                    .collect();
+                rings.sort_by(|a, b| b.0.total_cmp(&a.0));
+                arcs = rings.into_iter().map(|(_, ring)| ring).collect();
+            } else if n > 1 {
+                let mut k = area(topology, &arcs[0], false);
                 for i in 1..n {
-                    let ki = area(topology, &arcs[i]);
+                    let ki = area(topology, &arcs[i], false);
                     if ki > k {
                         arcs.swap(0, i);
                         k = ki;
@@ -179,6 +242,10 @@ impl<'a> MergeArcs<'a> {
 
 #[cfg(test)]
 mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use crate::topojsons::quantized_arcs;
+
     use super::*;
 
     #[test]
@@ -189,7 +256,7 @@ mod tests {
             transform: None,
             arcs: Vec::new(),
         };
-        let merge = wrap_merge(&topology, &Vec::new());
+        let merge = wrap_merge(&topology, &Vec::new(), false);
         assert_eq!(
             merge,
             FeatureGeometryType::MultiPolygon {
@@ -232,14 +299,18 @@ mod tests {
                     bbox: None,
                 },
             )]),
-            arcs: vec![
+            arcs: quantized_arcs(vec![
                 vec![[1, 1], [1, 0]],
                 vec![[1, 0], [0, 0], [0, 1], [1, 1]],
                 vec![[1, 1], [2, 1], [2, 0], [1, 0]],
-            ],
+            ]),
         };
         if let Geometry::GeometryCollection { geometries, .. } = &topology.objects["collection"] {
-            let merge = wrap_merge(&topology, geometries.iter().collect::<Vec<_>>().as_slice());
+            let merge = wrap_merge(
+                &topology,
+                geometries.iter().collect::<Vec<_>>().as_slice(),
+                false,
+            );
             assert_eq!(
                 merge,
                 FeatureGeometryType::MultiPolygon {
@@ -293,13 +364,17 @@ mod tests {
                     bbox: None,
                 },
             )]),
-            arcs: vec![
+            arcs: quantized_arcs(vec![
                 vec![[0, 0], [0, 1], [1, 1], [1, 0], [0, 0]],
                 vec![[2, 0], [2, 1], [3, 1], [3, 0], [2, 0]],
-            ],
+            ]),
         };
         if let Geometry::GeometryCollection { geometries, .. } = &topology.objects["collection"] {
-            let merge = wrap_merge(&topology, geometries.iter().collect::<Vec<_>>().as_slice());
+            let merge = wrap_merge(
+                &topology,
+                geometries.iter().collect::<Vec<_>>().as_slice(),
+                false,
+            );
             assert_eq!(
                 merge,
                 FeatureGeometryType::MultiPolygon {
@@ -350,13 +425,17 @@ mod tests {
                     bbox: None,
                 },
             )]),
-            arcs: vec![
+            arcs: quantized_arcs(vec![
                 vec![[0, 0], [0, 3], [3, 3], [3, 0], [0, 0]],
                 vec![[1, 1], [2, 1], [2, 2], [1, 2], [1, 1]],
-            ],
+            ]),
         };
         if let Geometry::GeometryCollection { geometries, .. } = &topology.objects["collection"] {
-            let merge = wrap_merge(&topology, geometries.iter().collect::<Vec<_>>().as_slice());
+            let merge = wrap_merge(
+                &topology,
+                geometries.iter().collect::<Vec<_>>().as_slice(),
+                false,
+            );
             assert_eq!(
                 merge,
                 FeatureGeometryType::MultiPolygon {
@@ -404,21 +483,23 @@ mod tests {
                     bbox: None,
                 },
             )]),
-            arcs: vec![
+            arcs: quantized_arcs(vec![
                 vec![[3, 3], [3, 0]],
                 vec![[3, 0], [0, 0], [0, 3], [3, 3]],
                 vec![[1, 1], [2, 1], [2, 2], [1, 2], [1, 1]],
                 vec![[3, 3], [6, 3], [6, 0], [3, 0]],
                 vec![[4, 1], [5, 1], [5, 2], [4, 2], [4, 1]],
-            ],
+            ]),
         };
         if let Geometry::GeometryCollection { geometries, .. } = &topology.objects["collection"] {
             // Special case: since `HashMap` are unordered, the coordinates may be unordered too.
             // So instead of checking if coordinates are the same, the test checks if sub parts of
             // coordinates are present in final result
-            if let FeatureGeometryType::MultiPolygon { coordinates } =
-                wrap_merge(&topology, geometries.iter().collect::<Vec<_>>().as_slice())
-            {
+            if let FeatureGeometryType::MultiPolygon { coordinates } = wrap_merge(
+                &topology,
+                geometries.iter().collect::<Vec<_>>().as_slice(),
+                false,
+            ) {
                 for subpart in [
                     vec![
                         [3., 0.],
@@ -478,17 +559,21 @@ mod tests {
                     bbox: None,
                 },
             )]),
-            arcs: vec![
+            arcs: quantized_arcs(vec![
                 vec![[2, 3], [2, 2]],
                 vec![[2, 2], [1, 2], [1, 1], [2, 1]],
                 vec![[2, 1], [2, 0]],
                 vec![[2, 0], [0, 0], [0, 3], [2, 3]],
                 vec![[2, 1], [3, 1], [3, 2], [2, 2]],
                 vec![[2, 3], [4, 3], [4, 0], [2, 0]],
-            ],
+            ]),
         };
         if let Geometry::GeometryCollection { geometries, .. } = &topology.objects["collection"] {
-            let merge = wrap_merge(&topology, geometries.iter().collect::<Vec<_>>().as_slice());
+            let merge = wrap_merge(
+                &topology,
+                geometries.iter().collect::<Vec<_>>().as_slice(),
+                false,
+            );
             assert_eq!(
                 merge,
                 FeatureGeometryType::MultiPolygon {
@@ -567,7 +652,7 @@ mod tests {
                     bbox: None,
                 },
             )]),
-            arcs: vec![
+            arcs: quantized_arcs(vec![
                 vec![[2, 3], [2, 2]],
                 vec![[2, 2], [1, 2], [1, 1], [2, 1]],
                 vec![[2, 1], [2, 0]],
@@ -575,10 +660,14 @@ mod tests {
                 vec![[2, 1], [3, 1], [3, 2], [2, 2]],
                 vec![[2, 3], [4, 3], [4, 0], [2, 0]],
                 vec![[2, 2], [2, 1]],
-            ],
+            ]),
         };
         if let Geometry::GeometryCollection { geometries, .. } = &topology.objects["collection"] {
-            let merge = wrap_merge(&topology, geometries.iter().collect::<Vec<_>>().as_slice());
+            let merge = wrap_merge(
+                &topology,
+                geometries.iter().collect::<Vec<_>>().as_slice(),
+                false,
+            );
             assert_eq!(
                 merge,
                 FeatureGeometryType::MultiPolygon {
@@ -597,4 +686,110 @@ mod tests {
             panic!("Topology must have a collection of geometries.")
         }
     }
+
+    #[test]
+    fn test_merge_8() {
+        let square = [[0., 0.], [0., 1.], [1., 1.], [1., 0.], [0., 0.]];
+        let expected = 1f64.to_radians().powi(2);
+        assert!((spherical_ring_area(&square) - expected).abs() < 1e-7);
+        let reversed: Vec<[f64; 2]> = square.iter().rev().copied().collect();
+        assert!((spherical_ring_area(&reversed) - expected).abs() < 1e-7);
+
+        let crossing = [[179., 0.], [179., 1.], [-179., 1.], [-179., 0.], [179., 0.]];
+        assert!((spherical_ring_area(&crossing) - 2. * expected).abs() < 1e-7);
+
+        // One winding of an octant encloses 7π/2, which `geoArea` would return
+        // but which is clamped to the octant itself.
+        let octant = [[0., 0.], [90., 0.], [0., 90.], [0., 0.]];
+        let reversed: Vec<[f64; 2]> = octant.iter().rev().copied().collect();
+        assert!((spherical_ring_area(&octant) - FRAC_PI_2).abs() < 1e-9);
+        assert!((spherical_ring_area(&reversed) - FRAC_PI_2).abs() < 1e-9);
+
+        let equator = [[0., 0.], [90., 0.], [180., 0.], [-90., 0.], [0., 0.]];
+        assert!((spherical_ring_area(&equator) - TAU).abs() < 1e-9);
+    }
+
+    //
+    //        +-----+                          +-+  +-+
+    //        |     |                          | |  | |
+    //        |     |                          +-+  +-+
+    //        +-----+         ...   -180 | 180
+    //        0    10
+    //
+    #[test]
+    fn test_merge_9() {
+        let topology = TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: HashMap::from_iter([(
+                "collection".to_string(),
+                Geometry::GeometryCollection {
+                    geometries: vec![Geometry::Polygon {
+                        arcs: vec![vec![0], vec![1]],
+                        id: None,
+                        properties: None,
+                        bbox: None,
+                    }],
+                    id: None,
+                    properties: None,
+                    bbox: None,
+                },
+            )]),
+            arcs: vec![
+                vec![[0., 0.], [0., 10.], [10., 10.], [10., 0.], [0., 0.]],
+                vec![[179., 0.], [179., 1.], [-179., 1.], [-179., 0.], [179., 0.]],
+            ],
+        };
+        if let Geometry::GeometryCollection { geometries, .. } = &topology.objects["collection"] {
+            let objects = geometries.iter().collect::<Vec<_>>();
+            for (spherical, exterior) in [(false, 1), (true, 0)] {
+                if let FeatureGeometryType::MultiPolygon { coordinates } =
+                    wrap_merge(&topology, &objects, spherical)
+                {
+                    assert_eq!(coordinates[0][0], topology.arcs[exterior]);
+                } else {
+                    panic!("Feature Geometry Type must be 'MultiPolygon'.")
+                }
+            }
+        } else {
+            panic!("Topology must have a collection of geometries.")
+        }
+    }
+
+    #[test]
+    fn test_merge_arcs_1() {
+        let topology = TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: HashMap::new(),
+            arcs: vec![
+                vec![[1., 1.], [1., 0.]],
+                vec![[1., 0.], [0., 0.], [0., 1.], [1., 1.]],
+                vec![[1., 1.], [2., 1.], [2., 0.], [1., 0.]],
+            ],
+        };
+        let objects = [
+            Geometry::Polygon {
+                arcs: vec![vec![0, 1]],
+                id: None,
+                properties: None,
+                bbox: None,
+            },
+            Geometry::Polygon {
+                arcs: vec![vec![-1, 2]],
+                id: None,
+                properties: None,
+                bbox: None,
+            },
+        ];
+        assert_eq!(
+            wrap_merge_arcs(&topology, &objects.iter().collect::<Vec<_>>(), false),
+            Geometry::MultiPolygon {
+                arcs: vec![vec![vec![1, 2]]],
+                id: None,
+                properties: None,
+                bbox: None,
+            }
+        );
+    }
 }
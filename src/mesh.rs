@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 
-use pyo3::PyResult;
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::PyList;
 
 use crate::feature::object_func;
 use crate::geojsons::FeatureGeometryType;
@@ -8,21 +10,60 @@ use crate::lambda::GeoVar;
 use crate::stitch::stitch;
 use crate::topojsons::{Geometry, TopoJSON};
 
+/// Number of pairs of geometries evaluated per call of a Python filter.
+const CHUNK_SIZE: usize = 4096;
+
+/// Filter of the arcs of a mesh: either a `GeoVar` expression, evaluated in
+/// Rust, or any Python callable `(a, b) -> bool`.
+#[derive(FromPyObject)]
+pub enum Filter<'py> {
+    GeoVar(PyRef<'py, GeoVar>),
+    Callable(Bound<'py, PyAny>),
+}
+
 pub fn wrap_mesh(
     topology: &TopoJSON,
     object: Option<&Geometry>,
-    filter: Option<&GeoVar>,
+    filter: Option<&Filter>,
+    compiled: bool,
 ) -> PyResult<FeatureGeometryType> {
     Ok(object_func(
         topology,
-        &MeshArcs::call(topology, object, filter)?,
+        &wrap_mesh_arcs(topology, object, filter, compiled)?,
     ))
 }
 
-struct ArcItem<'a> {
-    i: i32,
-    #[allow(unused)]
-    geometry: &'a Geometry,
+/// Arcs of the mesh of `object`. A `GeoVar` filter is compiled into a plan
+/// memoizing the operands of each geometry, or interpreted on each arc when
+/// `compiled` is false, e.g. to measure the speedup of the plan.
+pub fn wrap_mesh_arcs(
+    topology: &TopoJSON,
+    object: Option<&Geometry>,
+    filter: Option<&Filter>,
+    compiled: bool,
+) -> PyResult<Geometry> {
+    MeshArcs::call(topology, object, filter, compiled)
+}
+
+/// Geometries of `object` using each arc, by increasing arc index. The
+/// geometries are the innermost geometries of collections.
+pub(crate) fn geoms_by_arc(object: &Geometry) -> Vec<(usize, Vec<ArcItem<'_>>)> {
+    let mut mesh = MeshArcs::default();
+    mesh.geometry(object);
+    let GeometryByArcs {
+        max_index,
+        mut hmap,
+    } = mesh.geoms_by_arc;
+    (0..=max_index)
+        .filter_map(|k| hmap.remove(&k).map(|geoms| (k, geoms)))
+        .collect()
+}
+
+/// Geometry using an arc, with the arc index `i` as traversed by the
+/// geometry (`!i` when reversed).
+pub(crate) struct ArcItem<'a> {
+    pub(crate) i: i32,
+    pub(crate) geometry: &'a Geometry,
 }
 
 #[derive(Default)]
@@ -42,10 +83,11 @@ impl<'a> MeshArcs<'a> {
     fn call(
         topology: &TopoJSON,
         object: Option<&'a Geometry>,
-        filter: Option<&'a GeoVar>,
+        filter: Option<&Filter>,
+        compiled: bool,
     ) -> PyResult<Geometry> {
         let arcs = match object {
-            Some(object) => MeshArcs::default().extract(object, filter)?,
+            Some(object) => MeshArcs::default().extract(topology, object, filter, compiled)?,
             None => (0..topology.arcs.len()).map(|x| x as i32).collect(),
         };
         Ok(Geometry::MultiLineString {
@@ -56,17 +98,47 @@ impl<'a> MeshArcs<'a> {
         })
     }
 
-    fn extract(mut self, object: &'a Geometry, filter: Option<&'a GeoVar>) -> PyResult<Vec<i32>> {
+    fn extract(
+        mut self,
+        topology: &TopoJSON,
+        object: &'a Geometry,
+        filter: Option<&Filter>,
+        compiled: bool,
+    ) -> PyResult<Vec<i32>> {
         self.geometry(object);
 
         let geoms_by_arc =
             (0..=self.geoms_by_arc.max_index).filter_map(|k| self.geoms_by_arc.hmap.get(&k));
         match filter {
-            Some(geo_var) => {
+            Some(Filter::GeoVar(geo_var)) if !compiled => {
                 for geoms in geoms_by_arc {
                     let geom1 = geoms.first().unwrap().geometry;
                     let geom2 = geoms.last().unwrap().geometry;
-                    if geo_var.compare(geom1, geom2)?.into_bool() {
+                    if geo_var.interpret(topology, geom1, geom2)? {
+                        self.arcs.push(geoms[0].i);
+                    }
+                }
+            }
+            Some(Filter::GeoVar(geo_var)) => {
+                let mut plan = geo_var.compile(topology)?;
+                for geoms in geoms_by_arc {
+                    let geom1 = geoms.first().unwrap().geometry;
+                    let geom2 = geoms.last().unwrap().geometry;
+                    if plan.compare(geom1, geom2)? {
+                        self.arcs.push(geoms[0].i);
+                    }
+                }
+            }
+            Some(Filter::Callable(callable)) => {
+                if !callable.is_callable() {
+                    return Err(PyTypeError::new_err(
+                        "'filter' must be a 'GeoVar' or a callable",
+                    ));
+                }
+                let geoms_by_arc = geoms_by_arc.collect::<Vec<_>>();
+                let keep = Self::call_filter(callable, &geoms_by_arc)?;
+                for (geoms, keep) in geoms_by_arc.into_iter().zip(keep) {
+                    if keep {
                         self.arcs.push(geoms[0].i);
                     }
                 }
@@ -79,6 +151,51 @@ impl<'a> MeshArcs<'a> {
         Ok(self.arcs)
     }
 
+    /// Evaluates `callable` on the first and last geometries of each arc.
+    /// Each geometry is converted once and each distinct pair is evaluated
+    /// once, by chunks of pairs passed to the builtin `map` so that the calls
+    /// of a chunk are made from Python.
+    fn call_filter(callable: &Bound<PyAny>, geoms_by_arc: &[&Vec<ArcItem>]) -> PyResult<Vec<bool>> {
+        let py = callable.py();
+        let mut indexes: HashMap<*const Geometry, usize> = HashMap::new();
+        let mut objects: Vec<Bound<PyAny>> = Vec::new();
+        let mut index = |geom: &Geometry| -> PyResult<usize> {
+            if let Some(&i) = indexes.get(&(geom as *const Geometry)) {
+                return Ok(i);
+            }
+            objects.push(geom.clone().into_pyobject(py)?.into_any());
+            indexes.insert(geom as *const Geometry, objects.len() - 1);
+            Ok(objects.len() - 1)
+        };
+
+        let mut pairs: HashMap<[usize; 2], usize> = HashMap::new();
+        let mut distinct: Vec<[usize; 2]> = Vec::new();
+        let keys = geoms_by_arc
+            .iter()
+            .map(|geoms| {
+                let pair = [
+                    index(geoms.first().unwrap().geometry)?,
+                    index(geoms.last().unwrap().geometry)?,
+                ];
+                Ok(*pairs.entry(pair).or_insert_with(|| {
+                    distinct.push(pair);
+                    distinct.len() - 1
+                }))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let map = PyModule::import(py, "builtins")?.getattr("map")?;
+        let mut results = Vec::with_capacity(distinct.len());
+        for chunk in distinct.chunks(CHUNK_SIZE) {
+            let [a, b] =
+                [0, 1].map(|k| PyList::new(py, chunk.iter().map(|pair| &objects[pair[k]])));
+            for result in map.call1((callable, a?, b?))?.try_iter()? {
+                results.push(result?.is_truthy()?);
+            }
+        }
+        Ok(keys.into_iter().map(|key| results[key]).collect())
+    }
+
     fn extract_0(&mut self, i: i32) {
         let j = if i < 0 { !i } else { i } as usize;
         let geom = self.geom.expect("Undefined 'geom' during runtime");
@@ -119,6 +236,10 @@ impl<'a> MeshArcs<'a> {
 
 #[cfg(test)]
 mod tests {
+    use pyo3::types::PyDict;
+
+    use crate::topojsons::quantized_arcs;
+
     use super::*;
 
     #[test]
@@ -130,7 +251,7 @@ mod tests {
             transform: None,
         };
         assert_eq!(
-            wrap_mesh(&topology, None, None)?,
+            wrap_mesh(&topology, None, None, true)?,
             FeatureGeometryType::MultiLineString {
                 coordinates: Vec::new()
             }
@@ -165,10 +286,10 @@ mod tests {
                     bbox: None,
                 },
             )]),
-            arcs: vec![vec![[1, 0], [2, 0]], vec![[0, 0], [1, 0]]],
+            arcs: quantized_arcs(vec![vec![[1, 0], [2, 0]], vec![[0, 0], [1, 0]]]),
         };
         assert_eq!(
-            wrap_mesh(&topology, None, None)?,
+            wrap_mesh(&topology, None, None, true)?,
             FeatureGeometryType::MultiLineString {
                 coordinates: vec![vec![[0., 0.], [1., 0.], [2., 0.]]]
             }
@@ -203,10 +324,10 @@ mod tests {
                     bbox: None,
                 },
             )]),
-            arcs: vec![vec![[2, 0], [3, 0]], vec![[0, 0], [1, 0]]],
+            arcs: quantized_arcs(vec![vec![[2, 0], [3, 0]], vec![[0, 0], [1, 0]]]),
         };
         if let FeatureGeometryType::MultiLineString { coordinates } =
-            wrap_mesh(&topology, None, None)?
+            wrap_mesh(&topology, None, None, true)?
         {
             for values in [vec![[2., 0.], [3., 0.]], vec![[0., 0.], [1., 0.]]] {
                 assert!(coordinates.contains(&values));
@@ -216,4 +337,179 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_mesh_arcs_1() -> PyResult<()> {
+        let topology = TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: HashMap::new(),
+            arcs: vec![vec![[1., 0.], [2., 0.]], vec![[0., 0.], [1., 0.]]],
+        };
+        let object = Geometry::MultiLineString {
+            arcs: vec![vec![0], vec![1]],
+            id: None,
+            properties: None,
+            bbox: None,
+        };
+        assert_eq!(
+            wrap_mesh_arcs(&topology, Some(&object), None, true)?,
+            Geometry::MultiLineString {
+                arcs: vec![vec![1, 0]],
+                id: None,
+                properties: None,
+                bbox: None,
+            }
+        );
+        Ok(())
+    }
+
+    //
+    // +----+----+
+    // |    |    |
+    // | A  | B  |
+    // |    |    |
+    // +----+----+
+    //
+    fn filter_topology() -> TopoJSON {
+        TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: HashMap::from_iter([(
+                "collection".to_string(),
+                Geometry::GeometryCollection {
+                    geometries: vec![
+                        Geometry::Polygon {
+                            arcs: vec![vec![0, 1]],
+                            id: Some("A".to_string()),
+                            properties: None,
+                            bbox: None,
+                        },
+                        Geometry::Polygon {
+                            arcs: vec![vec![-1, 2]],
+                            id: Some("B".to_string()),
+                            properties: None,
+                            bbox: None,
+                        },
+                    ],
+                    id: None,
+                    properties: None,
+                    bbox: None,
+                },
+            )]),
+            arcs: vec![
+                vec![[1., 1.], [1., 0.]],
+                vec![[1., 0.], [0., 0.], [0., 1.], [1., 1.]],
+                vec![[1., 1.], [2., 1.], [2., 0.], [1., 0.]],
+            ],
+        }
+    }
+
+    #[test]
+    fn test_mesh_filter_1() -> PyResult<()> {
+        Python::initialize();
+        let topology = filter_topology();
+        let object = &topology.objects["collection"];
+        Python::attach(|py| {
+            for (code, arcs) in [
+                (c"lambda a, b: a.id != b.id", vec![vec![0]]),
+                (c"lambda a, b: a is b", vec![vec![1, 2]]),
+            ] {
+                let filter = Filter::Callable(py.eval(code, None, None)?);
+                assert_eq!(
+                    wrap_mesh_arcs(&topology, Some(object), Some(&filter), true)?,
+                    Geometry::MultiLineString {
+                        arcs,
+                        id: None,
+                        properties: None,
+                        bbox: None,
+                    }
+                );
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_mesh_filter_2() {
+        Python::initialize();
+        let topology = filter_topology();
+        let object = &topology.objects["collection"];
+        Python::attach(|py| {
+            let filter = Filter::Callable(1i64.into_pyobject(py).unwrap().into_any());
+            assert_eq!(
+                wrap_mesh_arcs(&topology, Some(object), Some(&filter), true)
+                    .err()
+                    .map(|e| e.to_string()),
+                Some("TypeError: 'filter' must be a 'GeoVar' or a callable".to_string())
+            );
+        });
+    }
+
+    #[test]
+    fn test_mesh_filter_3() -> PyResult<()> {
+        Python::initialize();
+        let topology = filter_topology();
+        let object = &topology.objects["collection"];
+        Python::attach(|py| {
+            let globals = PyDict::new(py);
+            py.run(
+                c"calls = []\nfilter = lambda a, b: calls.append((a.id, b.id)) or True",
+                Some(&globals),
+                None,
+            )?;
+            let filter = Filter::Callable(globals.get_item("filter")?.unwrap());
+            wrap_mesh_arcs(&topology, Some(object), Some(&filter), true)?;
+            assert_eq!(
+                globals
+                    .get_item("calls")?
+                    .unwrap()
+                    .extract::<Vec<(String, String)>>()?,
+                vec![
+                    ("A".to_string(), "B".to_string()),
+                    ("A".to_string(), "A".to_string()),
+                    ("B".to_string(), "B".to_string()),
+                ]
+            );
+
+            let filter = Filter::Callable(py.eval(c"lambda a, b: 1 / 0", None, None)?);
+            assert_eq!(
+                wrap_mesh_arcs(&topology, Some(object), Some(&filter), true)
+                    .err()
+                    .map(|e| e.to_string()),
+                Some("ZeroDivisionError: division by zero".to_string())
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_mesh_filter_4() -> PyResult<()> {
+        Python::initialize();
+        let topology = filter_topology();
+        let object = &topology.objects["collection"];
+        let a = GeoVar::new();
+        let b = GeoVar::new();
+        let different = a.item("id")?.__ne__((&b.item("id")?).into());
+        let same = a.__eq__((&b).into());
+        Python::attach(|py| {
+            for (geo_var, arcs) in [(different, vec![vec![0]]), (same, vec![vec![1, 2]])] {
+                let geo_var = Bound::new(py, geo_var)?;
+                let filter = Filter::GeoVar(geo_var.borrow());
+                // The interpreter and the compiled plan keep the same arcs.
+                for compiled in [true, false] {
+                    assert_eq!(
+                        wrap_mesh_arcs(&topology, Some(object), Some(&filter), compiled)?,
+                        Geometry::MultiLineString {
+                            arcs: arcs.clone(),
+                            id: None,
+                            properties: None,
+                            bbox: None,
+                        }
+                    );
+                }
+            }
+            Ok(())
+        })
+    }
 }
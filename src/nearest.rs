@@ -0,0 +1,488 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rustc_hash::FxHashMap;
+
+use crate::feature::decode_arc;
+use crate::haversine::{EARTH_RADIUS, haversine};
+use crate::rtree::RTree;
+use crate::topojsons::{Geometry, TopoJSON};
+
+/// Arc or point of a geometry, owner of indexed segments.
+#[derive(Clone, Copy)]
+enum Source {
+    Arc(usize),
+    Point(usize),
+}
+
+/// The `k` geometries of `object`, or `object` itself when it is not a
+/// collection, closest to `(x, y)` with the distance to their boundary, their
+/// lines or their points, by increasing distance. The segments of the arcs
+/// used by the geometries are decoded once and indexed in an R-tree.
+pub fn wrap_nearest(
+    topology: &TopoJSON,
+    object: &Geometry,
+    p: [f64; 2],
+    k: usize,
+    spherical: bool,
+) -> PyResult<Vec<(usize, Option<String>, f64)>> {
+    if k == 0 {
+        return Err(PyValueError::new_err("'k' must be at least 1"));
+    }
+    let members = object.members();
+    let mut owners = Owners::default();
+    members
+        .iter()
+        .enumerate()
+        .for_each(|(i, o)| owners.geometry(o, i));
+
+    let mut arcs: Vec<usize> = owners.arcs.keys().copied().collect();
+    arcs.sort_unstable();
+    let mut segments: Vec<([f64; 2], [f64; 2], Source)> = Vec::new();
+    for a in arcs {
+        let coordinates = decode_arc(topology, a);
+        segments.extend(coordinates.windows(2).map(|s| (s[0], s[1], Source::Arc(a))));
+    }
+    let point = |q: &[f64; 2]| match &topology.transform {
+        Some(transform) => [
+            q[0] * transform.scale[0] + transform.translate[0],
+            q[1] * transform.scale[1] + transform.translate[1],
+        ],
+        None => *q,
+    };
+    segments.extend(
+        owners
+            .points
+            .iter()
+            .map(|&(q, i)| (point(q), point(q), Source::Point(i))),
+    );
+
+    let tree = RTree::new(
+        segments
+            .iter()
+            .enumerate()
+            .map(|(i, &(a, b, _))| {
+                let bbox = if spherical {
+                    spherical_bbox(a, b)
+                } else {
+                    [
+                        a[0].min(b[0]),
+                        a[1].min(b[1]),
+                        a[0].max(b[0]),
+                        a[1].max(b[1]),
+                    ]
+                };
+                (bbox, i)
+            })
+            .collect(),
+    );
+    let mut nearest: Vec<(usize, Option<String>, f64)> = Vec::with_capacity(k);
+    let mut found = vec![false; members.len()];
+    tree.nearest(
+        |b| {
+            if spherical {
+                spherical_bbox_distance(p, b)
+            } else {
+                (p[0] - p[0].clamp(b[0], b[2])).hypot(p[1] - p[1].clamp(b[1], b[3]))
+            }
+        },
+        |i| {
+            let (a, b, _) = segments[i];
+            if spherical {
+                spherical_distance(p, a, b)
+            } else {
+                let q = closest(p, a, b);
+                (q[0] - p[0]).hypot(q[1] - p[1])
+            }
+        },
+        |i, d| {
+            let point;
+            let owners = match segments[i].2 {
+                Source::Arc(a) => owners.arcs[&a].as_slice(),
+                Source::Point(j) => {
+                    point = [j];
+                    &point
+                }
+            };
+            for &j in owners {
+                if !found[j] && nearest.len() < k {
+                    found[j] = true;
+                    nearest.push((j, members[j].id(), d));
+                }
+            }
+            nearest.len() < k
+        },
+    );
+    Ok(nearest)
+}
+
+/// Closest position of the segment `[a, b]` to `p`.
+fn closest(p: [f64; 2], a: [f64; 2], b: [f64; 2]) -> [f64; 2] {
+    let [dx, dy] = [b[0] - a[0], b[1] - a[1]];
+    let length = dx * dx + dy * dy;
+    if length == 0. {
+        return a;
+    }
+    let t = (((p[0] - a[0]) * dx + (p[1] - a[1]) * dy) / length).clamp(0., 1.);
+    [a[0] + t * (b[0] - a[0]), a[1] + t * (b[1] - a[1])]
+}
+
+/// Unit vector of a position `[longitude, latitude]` in degrees.
+fn cartesian(p: [f64; 2]) -> [f64; 3] {
+    let (lambda, phi) = (p[0].to_radians(), p[1].to_radians());
+    [
+        phi.cos() * lambda.cos(),
+        phi.cos() * lambda.sin(),
+        phi.sin(),
+    ]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(a: [f64; 3]) -> Option<[f64; 3]> {
+    let length = dot(a, a).sqrt();
+    (length > 1e-12).then(|| a.map(|x| x / length))
+}
+
+/// Returns `true` when `v`, in the plane of the great circle of normal `n`
+/// through `a` and `b`, lies between `a` and `b`.
+fn between(a: [f64; 3], b: [f64; 3], n: [f64; 3], v: [f64; 3]) -> bool {
+    dot(cross(a, v), n) >= 0. && dot(cross(v, b), n) >= 0.
+}
+
+/// Distance in metres from `p` to the great-circle segment `[a, b]`.
+fn spherical_distance(p: [f64; 2], a: [f64; 2], b: [f64; 2]) -> f64 {
+    let [p, a, b] = [p, a, b].map(cartesian);
+    let angle = |u: [f64; 3], v: [f64; 3]| dot(cross(u, v), cross(u, v)).sqrt().atan2(dot(u, v));
+    let ends = angle(p, a).min(angle(p, b));
+    EARTH_RADIUS
+        * match normalize(cross(a, b)) {
+            Some(n) => {
+                // Projection of `p` on the plane of the great circle.
+                let d = dot(p, n);
+                let v = [p[0] - d * n[0], p[1] - d * n[1], p[2] - d * n[2]];
+                if between(a, b, n, v) {
+                    d.abs().min(1.).asin()
+                } else {
+                    ends
+                }
+            }
+            None => ends,
+        }
+}
+
+/// Bounding box `[x0, y0, x1, y1]` of the great-circle segment `[a, b]`, whose
+/// latitudes may exceed the ones of its ends. A segment crossing the
+/// antimeridian or a pole spans all the longitudes.
+fn spherical_bbox(a: [f64; 2], b: [f64; 2]) -> [f64; 4] {
+    let [mut y0, mut y1] = [a[1].min(b[1]), a[1].max(b[1])];
+    let [u, v] = [a, b].map(cartesian);
+    if let Some(n) = normalize(cross(u, v)) {
+        // Northernmost position of the great circle, projection of the pole.
+        if let Some(north) = normalize([-n[2] * n[0], -n[2] * n[1], 1. - n[2] * n[2]]) {
+            let latitude = north[2].min(1.).asin().to_degrees();
+            if between(u, v, n, north) {
+                y1 = y1.max(latitude);
+            }
+            if between(u, v, n, north.map(|x| -x)) {
+                y0 = y0.min(-latitude);
+            }
+        }
+    }
+    if (b[0] - a[0]).abs() >= 180. {
+        [-180., y0, 180., y1]
+    } else {
+        [a[0].min(b[0]), y0, a[0].max(b[0]), y1]
+    }
+}
+
+/// Distance in metres from `p` to the closest position of the bounding box
+/// `[x0, y0, x1, y1]` of longitudes and latitudes, across the antimeridian.
+fn spherical_bbox_distance(p: [f64; 2], [x0, y0, x1, y1]: [f64; 4]) -> f64 {
+    if (p[0] - x0).rem_euclid(360.) <= x1 - x0 {
+        return EARTH_RADIUS * (p[1] - p[1].clamp(y0, y1)).abs().to_radians();
+    }
+    // The closest position lies on the nearest meridian of the box, where
+    // the distance to `p` has a single minimum.
+    let wrap = |x: f64| ((p[0] - x + 180.).rem_euclid(360.) - 180.).abs();
+    let x = if wrap(x0) <= wrap(x1) { x0 } else { x1 };
+    let (delta, phi) = (wrap(x).to_radians(), p[1].to_radians());
+    if delta.cos() >= 0. {
+        let y = phi.sin().atan2(phi.cos() * delta.cos()).to_degrees();
+        haversine(&p, &[x, y.clamp(y0, y1)])
+    } else {
+        haversine(&p, &[x, y0]).min(haversine(&p, &[x, y1]))
+    }
+}
+
+/// Geometries using each arc and positions of the points of each geometry.
+#[derive(Default)]
+struct Owners<'a> {
+    arcs: FxHashMap<usize, Vec<usize>>,
+    points: Vec<(&'a [f64; 2], usize)>,
+}
+
+impl<'a> Owners<'a> {
+    fn line(&mut self, arcs: &[i32], i: usize) {
+        for &a in arcs {
+            let owners = self
+                .arcs
+                .entry(if a < 0 { !a } else { a } as usize)
+                .or_default();
+            if owners.last() != Some(&i) {
+                owners.push(i);
+            }
+        }
+    }
+
+    fn geometry(&mut self, o: &'a Geometry, i: usize) {
+        match o {
+            Geometry::GeometryCollection { geometries, .. } => {
+                geometries.iter().for_each(|o| self.geometry(o, i))
+            }
+            Geometry::Point { coordinates, .. } => self.points.push((coordinates, i)),
+            Geometry::MultiPoint { coordinates, .. } => {
+                self.points.extend(coordinates.iter().map(|p| (p, i)))
+            }
+            Geometry::LineString { arcs, .. } => self.line(arcs, i),
+            Geometry::MultiLineString { arcs, .. } | Geometry::Polygon { arcs, .. } => {
+                arcs.iter().for_each(|arcs| self.line(arcs, i))
+            }
+            Geometry::MultiPolygon { arcs, .. } => {
+                arcs.iter().flatten().for_each(|arcs| self.line(arcs, i))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::topojsons::Transform;
+
+    use super::*;
+
+    //
+    // (0,1)+---+        + (3,2)
+    //      | A |        | B
+    // (0,0)+---+        + (3,0)
+    //
+    //        + C (0.5, 3.5)
+    //
+    fn collection() -> Geometry {
+        Geometry::GeometryCollection {
+            geometries: vec![
+                Geometry::Polygon {
+                    arcs: vec![vec![0]],
+                    id: Some("A".to_string()),
+                    properties: None,
+                    bbox: None,
+                },
+                Geometry::LineString {
+                    arcs: vec![1],
+                    id: None,
+                    properties: None,
+                    bbox: None,
+                },
+                Geometry::Point {
+                    coordinates: [0.5, 3.5],
+                    id: Some("C".to_string()),
+                    properties: None,
+                    bbox: None,
+                },
+            ],
+            id: None,
+            properties: None,
+            bbox: None,
+        }
+    }
+
+    fn topology() -> TopoJSON {
+        TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: HashMap::new(),
+            arcs: vec![
+                vec![[0., 0.], [1., 0.], [1., 1.], [0., 1.], [0., 0.]],
+                vec![[3., 0.], [3., 2.]],
+            ],
+        }
+    }
+
+    #[test]
+    fn test_nearest_1() -> PyResult<()> {
+        let topology = topology();
+        let collection = collection();
+        assert_eq!(
+            wrap_nearest(&topology, &collection, [0.5, 0.5], 3, false)?,
+            vec![
+                (0, Some("A".to_string()), 0.5),
+                (1, None, 2.5),
+                (2, Some("C".to_string()), 3.)
+            ]
+        );
+        assert_eq!(
+            wrap_nearest(&topology, &collection, [3., 4.], 1, false)?,
+            vec![(1, None, 2.)]
+        );
+        assert_eq!(
+            wrap_nearest(&topology, &collection, [0.5, 0.5], 10, false)?.len(),
+            3
+        );
+
+        let nearest = wrap_nearest(&topology, &collection, [0.5, 0.5], 1, true)?;
+        assert_eq!(nearest[0].0, 0);
+        // Distance to the great circle of the meridian 0.
+        let expected = (0.5f64.to_radians().cos() * 0.5f64.to_radians().sin()).asin();
+        assert!((nearest[0].2 - EARTH_RADIUS * expected).abs() < 1e-6);
+
+        Python::initialize();
+        assert_eq!(
+            wrap_nearest(&topology, &collection, [0.5, 0.5], 0, false)
+                .err()
+                .map(|e| e.to_string()),
+            Some("ValueError: 'k' must be at least 1".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_nearest_2() -> PyResult<()> {
+        // Same topology, quantized with a scale of 0.5.
+        let topology = TopoJSON {
+            bbox: Vec::new(),
+            transform: Some(Transform {
+                scale: [0.5, 0.5],
+                translate: [0., 0.],
+            }),
+            objects: HashMap::new(),
+            arcs: vec![
+                vec![[0., 0.], [2., 0.], [0., 2.], [-2., 0.], [0., -2.]],
+                vec![[6., 0.], [0., 4.]],
+            ],
+        };
+        let mut collection = collection();
+        if let Geometry::GeometryCollection { geometries, .. } = &mut collection {
+            geometries[2] = Geometry::Point {
+                coordinates: [1., 7.],
+                id: Some("C".to_string()),
+                properties: None,
+                bbox: None,
+            };
+        }
+        assert_eq!(
+            wrap_nearest(&topology, &collection, [0.5, 0.5], 3, false)?,
+            wrap_nearest(&self::topology(), &self::collection(), [0.5, 0.5], 3, false)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_nearest_3() -> PyResult<()> {
+        let polygon = |i: i32| Geometry::Polygon {
+            arcs: vec![vec![i]],
+            id: None,
+            properties: None,
+            bbox: None,
+        };
+        let ring = |x0: f64, x1: f64| {
+            vec![
+                [x0, -0.05],
+                [x1, -0.05],
+                [x1, 0.05],
+                [x0, 0.05],
+                [x0, -0.05],
+            ]
+        };
+        let topology = TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: HashMap::new(),
+            arcs: vec![ring(179., 179.5), ring(-179.95, -179.85)],
+        };
+        let collection = Geometry::GeometryCollection {
+            geometries: vec![polygon(0), polygon(1)],
+            id: None,
+            properties: None,
+            bbox: None,
+        };
+
+        // Across the antimeridian.
+        let nearest = wrap_nearest(&topology, &collection, [179.9, 0.], 2, true)?;
+        assert_eq!(nearest.iter().map(|n| n.0).collect::<Vec<_>>(), vec![1, 0]);
+        assert!((nearest[0].2 - haversine(&[179.9, 0.], &[-179.95, 0.])).abs() < 1e-6);
+        assert!((nearest[1].2 - haversine(&[179.9, 0.], &[179.5, 0.])).abs() < 1e-6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_nearest_4() -> PyResult<()> {
+        // At high latitudes, the great circle from (-60, 80) to (60, 80)
+        // reaches about 84.96° of latitude.
+        let topology = TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: HashMap::new(),
+            arcs: vec![vec![[-60., 80.], [60., 80.]]],
+        };
+        let collection = Geometry::GeometryCollection {
+            geometries: vec![
+                Geometry::LineString {
+                    arcs: vec![0],
+                    id: None,
+                    properties: None,
+                    bbox: None,
+                },
+                Geometry::Point {
+                    coordinates: [0., 81.],
+                    id: None,
+                    properties: None,
+                    bbox: None,
+                },
+            ],
+            id: None,
+            properties: None,
+            bbox: None,
+        };
+        let nearest = wrap_nearest(&topology, &collection, [0., 89.5], 2, true)?;
+        assert_eq!(nearest.iter().map(|n| n.0).collect::<Vec<_>>(), vec![0, 1]);
+        let vertex = (80f64.to_radians().tan() / 60f64.to_radians().cos()).atan();
+        let expected = EARTH_RADIUS * (89.5f64.to_radians() - vertex);
+        assert!((nearest[0].2 - expected).abs() < 1e-3);
+        assert!((nearest[1].2 - haversine(&[0., 89.5], &[0., 81.])).abs() < 1e-6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_nearest_5() {
+        // The distance to the bounding box of a segment is a lower bound of
+        // the distance to the segment, as required by `RTree::nearest`.
+        let mut seed: u64 = 42;
+        let mut random = |lo: f64, hi: f64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            lo + (hi - lo) * ((seed >> 11) as f64 / (1u64 << 53) as f64)
+        };
+        for _ in 0..10000 {
+            let a = [random(-180., 180.), random(-90., 90.)];
+            let b = [
+                a[0] + random(-20., 20.),
+                (a[1] + random(-20., 20.)).clamp(-90., 90.),
+            ];
+            let p = [random(-180., 180.), random(-90., 90.)];
+            let bbox = spherical_bbox(a, b);
+            assert!(spherical_bbox_distance(p, bbox) <= spherical_distance(p, a, b) + 1e-6);
+        }
+    }
+}
@@ -1,43 +1,283 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
 use rustc_hash::FxHashMap;
 
+use crate::adjacency::Id;
 use crate::bisect::bisect;
-use crate::topojsons::Geometry;
+use crate::feature::decode_arc;
+use crate::haversine::haversine;
+use crate::stitch::point_key;
+use crate::topojsons::{Geometry, TopoJSON};
+
+/// Adjacency of `neighbors`: geometries sharing an arc (rook) or also
+/// geometries touching at a node of the topology (queen).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Mode {
+    Rook,
+    Queen,
+}
+
+impl TryFrom<&str> for Mode {
+    type Error = PyErr;
+
+    fn try_from(value: &str) -> PyResult<Self> {
+        match value {
+            "rook" => Ok(Self::Rook),
+            "queen" => Ok(Self::Queen),
+            _ => Err(PyValueError::new_err(format!(
+                "Unknown mode {:?}, expected 'rook' or 'queen'",
+                value
+            ))),
+        }
+    }
+}
+
+/// Weight of a pair of neighbors: length of their shared border or number of
+/// arcs they share.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Weights {
+    Length,
+    Count,
+}
+
+impl TryFrom<&str> for Weights {
+    type Error = PyErr;
+
+    fn try_from(value: &str) -> PyResult<Self> {
+        match value {
+            "length" => Ok(Self::Length),
+            "count" => Ok(Self::Count),
+            _ => Err(PyValueError::new_err(format!(
+                "Unknown weights {:?}, expected 'length' or 'count'",
+                value
+            ))),
+        }
+    }
+}
+
+/// Neighbors of each object with, at the same positions, the weight, the
+/// length of the shared border and the number of shared arcs of each pair.
+#[pyclass]
+#[derive(Debug, PartialEq, Clone)]
+pub struct WeightedNeighbors {
+    #[pyo3(get)]
+    pub neighbors: Vec<Vec<i32>>,
+    #[pyo3(get)]
+    pub weights: Vec<Vec<f64>>,
+    #[pyo3(get)]
+    pub lengths: Vec<Vec<f64>>,
+    #[pyo3(get)]
+    pub counts: Vec<Vec<usize>>,
+}
+
+/// Result of `neighbors`: the indexes of the neighbors, with their weights
+/// when weights are requested.
+#[derive(IntoPyObject)]
+pub enum NeighborsOutput {
+    Indexes(Vec<Vec<i32>>),
+    Weighted(WeightedNeighbors),
+}
 
 pub fn wrap_neighbors(objects: &[&Geometry]) -> Vec<Vec<i32>> {
-    Neighbors::call(objects)
+    Neighbors::call(objects, None)
+}
+
+pub fn wrap_neighbors_queen(topology: &TopoJSON, objects: &[&Geometry]) -> Vec<Vec<i32>> {
+    Neighbors::call(objects, Some(nodes(topology)))
+}
+
+/// For each object of `objects_a`, the objects of `objects_b` sharing an arc
+/// with it, identified by their index in `objects_b` or, when `ids` is
+/// `true`, by their id if any.
+pub fn wrap_neighbors_between(
+    objects_a: &[&Geometry],
+    objects_b: &[&Geometry],
+    ids: bool,
+) -> Vec<Vec<Id>> {
+    let objects: Vec<&Geometry> = objects_a.iter().chain(objects_b).copied().collect();
+    let mut neighbors = Neighbors::new(objects.len(), None);
+    neighbors.index(&objects);
+    neighbors
+        .between(objects_a.len())
+        .into_iter()
+        .map(|n| {
+            n.into_iter()
+                .map(|j| {
+                    let j = j as usize;
+                    match objects_b[j].id() {
+                        Some(id) if ids => Id::Id(id),
+                        _ => Id::Index(j),
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Weighted neighbors of the objects. Only the arcs shared by at least two
+/// objects are decoded to measure their length, in the units of the decoded
+/// coordinates or in metres when `spherical` is `true`.
+pub fn wrap_weighted_neighbors(
+    topology: &TopoJSON,
+    objects: &[&Geometry],
+    mode: Mode,
+    weights: Weights,
+    spherical: bool,
+) -> WeightedNeighbors {
+    let nodes = match mode {
+        Mode::Rook => None,
+        Mode::Queen => Some(nodes(topology)),
+    };
+    let mut neighbors = Neighbors::new(objects.len(), nodes);
+    neighbors.index(objects);
+    let shared = neighbors.shared(topology, spherical);
+    let neighbors = neighbors.splice();
+
+    let pairs: Vec<Vec<(usize, f64)>> = neighbors
+        .iter()
+        .enumerate()
+        .map(|(i, n)| {
+            n.iter()
+                .map(|&j| {
+                    let j = j as usize;
+                    shared
+                        .get(&[i.min(j), i.max(j)])
+                        .copied()
+                        .unwrap_or_default()
+                })
+                .collect()
+        })
+        .collect();
+    let lengths: Vec<Vec<f64>> = pairs
+        .iter()
+        .map(|pairs| pairs.iter().map(|&(_, length)| length).collect())
+        .collect();
+    let counts: Vec<Vec<usize>> = pairs
+        .iter()
+        .map(|pairs| pairs.iter().map(|&(count, _)| count).collect())
+        .collect();
+    let weights = match weights {
+        Weights::Length => lengths.clone(),
+        Weights::Count => counts
+            .iter()
+            .map(|counts| counts.iter().map(|&count| count as f64).collect())
+            .collect(),
+    };
+    WeightedNeighbors {
+        neighbors,
+        weights,
+        lengths,
+        counts,
+    }
+}
+
+/// Length of an arc, planar or in metres on the sphere.
+pub(crate) fn arc_length(topology: &TopoJSON, arc: usize, spherical: bool) -> f64 {
+    line_length(&decode_arc(topology, arc), spherical)
+}
+
+/// Length of a line of decoded positions, planar or in metres on the sphere.
+pub(crate) fn line_length(coordinates: &[[f64; 2]], spherical: bool) -> f64 {
+    coordinates
+        .windows(2)
+        .map(|p| {
+            if spherical {
+                haversine(&p[0], &p[1])
+            } else {
+                (p[1][0] - p[0][0]).hypot(p[1][1] - p[0][1])
+            }
+        })
+        .sum()
+}
+
+/// Position of a node, compared exactly.
+type Node = [u64; 2];
+
+/// First and last positions of each arc, decoded from the deltas of a
+/// quantized topology but not transformed so that they are compared exactly.
+fn nodes(topology: &TopoJSON) -> Vec<Option<[Node; 2]>> {
+    topology
+        .arcs
+        .iter()
+        .map(|arc| {
+            let (first, last) = arc.first().zip(arc.last())?;
+            let last = match topology.transform {
+                Some(_) => arc.iter().fold([0., 0.], |p, d| [p[0] + d[0], p[1] + d[1]]),
+                None => *last,
+            };
+            Some([point_key(*first), point_key(last)])
+        })
+        .collect()
+}
+
+/// Inserts `i` into the sorted neighbors `n`, once.
+fn splice(n: &mut Vec<i32>, i: usize) {
+    let i = i as i32;
+    let position = bisect(n, &i);
+    if n.get(position) != Some(&i) {
+        n.insert(position, i);
+    }
 }
 
 struct Neighbors {
     indexes_by_arc: FxHashMap<usize, Vec<usize>>,
+    indexes_by_node: FxHashMap<Node, Vec<usize>>,
+    nodes: Option<Vec<Option<[Node; 2]>>>,
     neighbors: Vec<Vec<i32>>,
 }
 
 impl Neighbors {
-    fn call(objects: &[&Geometry]) -> Vec<Vec<i32>> {
-        Neighbors::new(objects.len()).neighbors(objects)
+    fn call(objects: &[&Geometry], nodes: Option<Vec<Option<[Node; 2]>>>) -> Vec<Vec<i32>> {
+        Neighbors::new(objects.len(), nodes).neighbors(objects)
     }
 
     fn neighbors(mut self, objects: &[&Geometry]) -> Vec<Vec<i32>> {
+        self.index(objects);
+        self.splice()
+    }
+
+    fn index(&mut self, objects: &[&Geometry]) {
         objects
             .iter()
             .enumerate()
             .for_each(|(i, o)| self.geometry(o, i));
+    }
 
-        let mut splice_neighbors = |i1: usize, i2: usize| {
-            let n = &mut self.neighbors[i1];
-            let i2 = i2 as i32;
-            let i = bisect(n, &i2);
-            match n.get(i) {
-                Some(&neighbor) => {
-                    if neighbor != i2 {
-                        n.insert(i, i2);
-                    }
+    /// Number of shared arcs and length of the shared border of each pair
+    /// `[i, j]` of objects sharing arcs, with `i < j`. The arcs are visited in
+    /// order so that the summed lengths do not depend on the hash order.
+    fn shared(&self, topology: &TopoJSON, spherical: bool) -> FxHashMap<[usize; 2], (usize, f64)> {
+        let mut shared: FxHashMap<[usize; 2], (usize, f64)> = FxHashMap::default();
+        let mut arcs: Vec<usize> = self.indexes_by_arc.keys().copied().collect();
+        arcs.sort_unstable();
+        for arc in arcs {
+            let mut indexes = self.indexes_by_arc[&arc].clone();
+            indexes.sort_unstable();
+            indexes.dedup();
+            if indexes.len() < 2 {
+                continue;
+            }
+            let length = arc_length(topology, arc, spherical);
+            let m = indexes.len();
+            for j in 0..m {
+                for k in (j + 1)..m {
+                    let pair = shared.entry([indexes[j], indexes[k]]).or_default();
+                    pair.0 += 1;
+                    pair.1 += length;
                 }
-                None => n.insert(i, i2),
             }
-        };
+        }
+        shared
+    }
 
-        for indexes in self.indexes_by_arc.values() {
+    fn splice(mut self) -> Vec<Vec<i32>> {
+        let mut splice_neighbors = |i1: usize, i2: usize| splice(&mut self.neighbors[i1], i2);
+
+        for indexes in self
+            .indexes_by_arc
+            .values()
+            .chain(self.indexes_by_node.values())
+        {
             let m = indexes.len();
             for j in 0..m {
                 for k in (j + 1)..m {
@@ -52,17 +292,45 @@ impl Neighbors {
         self.neighbors
     }
 
-    fn new(len_objects: usize) -> Self {
+    /// Neighbors of the `len_a` first objects among the other objects, which
+    /// are indexed from `0`.
+    fn between(self, len_a: usize) -> Vec<Vec<i32>> {
+        let mut neighbors = vec![Vec::default(); len_a];
+        for indexes in self.indexes_by_arc.values() {
+            for &ia in indexes.iter().filter(|&&i| i < len_a) {
+                for &ib in indexes.iter().filter(|&&i| i >= len_a) {
+                    splice(&mut neighbors[ia], ib - len_a);
+                }
+            }
+        }
+        neighbors
+    }
+
+    fn new(len_objects: usize, nodes: Option<Vec<Option<[Node; 2]>>>) -> Self {
         Self {
             indexes_by_arc: FxHashMap::default(),
+            indexes_by_node: FxHashMap::default(),
+            nodes,
             neighbors: vec![Vec::default(); len_objects],
         }
     }
 
+    /// Records that the geometry `i` touches the node `n`, once per node.
+    fn node(&mut self, n: Node, i: usize) {
+        let indexes = self.indexes_by_node.entry(n).or_default();
+        if !indexes.contains(&i) {
+            indexes.push(i);
+        }
+    }
+
     fn line(&mut self, arcs: &[i32], i: usize) {
         arcs.iter().for_each(|&a| {
             let a = if a < 0 { !a } else { a } as usize;
             self.indexes_by_arc.entry(a).or_default().push(i);
+            if let Some([first, last]) = self.nodes.as_ref().and_then(|nodes| nodes[a]) {
+                self.node(first, i);
+                self.node(last, i);
+            }
         });
     }
 
@@ -70,22 +338,33 @@ impl Neighbors {
         arcs.iter().for_each(|arc| self.line(arc, i));
     }
 
+    fn point(&mut self, p: &[f64; 2], i: usize) {
+        if self.nodes.is_some() {
+            self.node(point_key(*p), i);
+        }
+    }
+
     fn geometry(&mut self, o: &Geometry, i: usize) {
         match o {
             Geometry::GeometryCollection { geometries, .. } => {
                 geometries.iter().for_each(|o| self.geometry(o, i))
             }
+            Geometry::Point { coordinates, .. } => self.point(coordinates, i),
+            Geometry::MultiPoint { coordinates, .. } => {
+                coordinates.iter().for_each(|p| self.point(p, i))
+            }
             Geometry::LineString { arcs, .. } => self.line(arcs, i),
             Geometry::MultiLineString { arcs, .. } => self.polygon(arcs, i),
             Geometry::Polygon { arcs, .. } => self.polygon(arcs, i),
             Geometry::MultiPolygon { arcs, .. } => arcs.iter().for_each(|arc| self.polygon(arc, i)),
-            _ => panic!("Invalid geometry type used during neighbors operation"),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::topojsons::Transform;
+
     use super::*;
 
     #[test]
@@ -243,4 +522,290 @@ mod tests {
             vec![vec![1], vec![0]]
         );
     }
+
+    #[test]
+    fn test_neighbors_8() {
+        let objects = [
+            Geometry::Point {
+                coordinates: [0., 0.],
+                id: None,
+                properties: None,
+                bbox: None,
+            },
+            Geometry::MultiPoint {
+                coordinates: vec![[0., 0.], [1., 1.]],
+                id: None,
+                properties: None,
+                bbox: None,
+            },
+        ];
+        assert_eq!(
+            wrap_neighbors(objects.iter().collect::<Vec<_>>().as_slice()),
+            vec![Vec::<i32>::new(); 2]
+        );
+    }
+
+    //
+    //       B-----+
+    //       |     |
+    //       |  1  |
+    //       |     |
+    // +-----A-----+
+    // |     |
+    // |  0  |     C
+    // |     |
+    // +-----+
+    //
+    fn queen_topology(transform: Option<Transform>, arcs: Vec<Vec<[f64; 2]>>) -> TopoJSON {
+        TopoJSON {
+            bbox: Vec::new(),
+            transform,
+            objects: Default::default(),
+            arcs,
+        }
+    }
+
+    fn queen_objects() -> Vec<Geometry> {
+        let polygon = |arcs| Geometry::Polygon {
+            arcs,
+            id: None,
+            properties: None,
+            bbox: None,
+        };
+        let point = |coordinates| Geometry::Point {
+            coordinates,
+            id: None,
+            properties: None,
+            bbox: None,
+        };
+        vec![
+            polygon(vec![vec![0]]),
+            polygon(vec![vec![1]]),
+            point([1., 1.]),
+            point([2., 0.]),
+        ]
+    }
+
+    #[test]
+    fn test_neighbors_9() {
+        let objects = queen_objects();
+        let objects = objects.iter().collect::<Vec<_>>();
+        let topology = queen_topology(
+            None,
+            vec![
+                vec![[1., 1.], [1., 0.], [0., 0.], [0., 1.], [1., 1.]],
+                vec![[1., 1.], [1., 2.], [2., 2.], [2., 1.], [1., 1.]],
+            ],
+        );
+        assert_eq!(wrap_neighbors(&objects), vec![Vec::<i32>::new(); 4]);
+        assert_eq!(
+            wrap_neighbors_queen(&topology, &objects),
+            vec![vec![1, 2], vec![0, 2], vec![0, 1], vec![]]
+        );
+    }
+
+    #[test]
+    fn test_neighbors_10() {
+        let objects = queen_objects();
+        let objects = objects.iter().collect::<Vec<_>>();
+        let topology = queen_topology(
+            Some(Transform {
+                scale: [0.5, 0.5],
+                translate: [10., 10.],
+            }),
+            vec![
+                vec![[1., 1.], [0., -1.], [-1., 0.], [0., 1.], [1., 0.]],
+                vec![[1., 1.], [0., 1.], [1., 0.], [0., -1.], [-1., 0.]],
+            ],
+        );
+        assert_eq!(
+            wrap_neighbors_queen(&topology, &objects),
+            vec![vec![1, 2], vec![0, 2], vec![0, 1], vec![]]
+        );
+    }
+
+    #[test]
+    fn test_neighbors_11() {
+        Python::initialize();
+        assert_eq!(Mode::try_from("queen").unwrap(), Mode::Queen);
+        assert_eq!(
+            Mode::try_from("bishop").err().map(|e| e.to_string()),
+            Some("ValueError: Unknown mode \"bishop\", expected 'rook' or 'queen'".to_string())
+        );
+    }
+
+    //
+    //             C-----+
+    //             |     |
+    //             |  2  |
+    //             |     |
+    // +-----A-----B-----+
+    // |     |     |
+    // |  0  |  1  |
+    // |     |     |
+    // +-----D-----+
+    //
+    fn weighted_topology() -> (TopoJSON, Vec<Geometry>) {
+        let topology = queen_topology(
+            None,
+            vec![
+                vec![[1., 0.], [1., 1.]],
+                vec![[1., 1.], [0., 1.], [0., 0.], [1., 0.]],
+                vec![[1., 1.], [2., 1.]],
+                vec![[2., 1.], [2., 0.], [1., 0.]],
+                vec![[2., 1.], [2., 2.], [3., 2.], [3., 1.], [2., 1.]],
+            ],
+        );
+        let objects = [vec![vec![1, 0]], vec![vec![-1, -4, -3]], vec![vec![4]]]
+            .map(|arcs| Geometry::Polygon {
+                arcs,
+                id: None,
+                properties: None,
+                bbox: None,
+            })
+            .into_iter()
+            .collect();
+        (topology, objects)
+    }
+
+    #[test]
+    fn test_neighbors_12() {
+        let (topology, objects) = weighted_topology();
+        let objects = objects.iter().collect::<Vec<_>>();
+        assert_eq!(
+            wrap_weighted_neighbors(&topology, &objects, Mode::Rook, Weights::Length, false),
+            WeightedNeighbors {
+                neighbors: vec![vec![1], vec![0], vec![]],
+                weights: vec![vec![1.], vec![1.], vec![]],
+                lengths: vec![vec![1.], vec![1.], vec![]],
+                counts: vec![vec![1], vec![1], vec![]],
+            }
+        );
+        assert_eq!(
+            wrap_weighted_neighbors(&topology, &objects, Mode::Queen, Weights::Count, false),
+            WeightedNeighbors {
+                neighbors: vec![vec![1], vec![0, 2], vec![1]],
+                weights: vec![vec![1.], vec![1., 0.], vec![0.]],
+                lengths: vec![vec![1.], vec![1., 0.], vec![0.]],
+                counts: vec![vec![1], vec![1, 0], vec![0]],
+            }
+        );
+        // 1 and 2 only touch at B: they share no border.
+        assert_eq!(
+            wrap_weighted_neighbors(&topology, &objects, Mode::Queen, Weights::Length, false)
+                .weights,
+            vec![vec![1.], vec![1., 0.], vec![0.]]
+        );
+    }
+
+    #[test]
+    fn test_neighbors_13() {
+        let (topology, objects) = weighted_topology();
+        let objects = objects.iter().collect::<Vec<_>>();
+        let weighted =
+            wrap_weighted_neighbors(&topology, &objects, Mode::Rook, Weights::Length, true);
+        assert!((weighted.lengths[0][0] - 111_195.08).abs() < 0.01);
+        assert_eq!(weighted.lengths[0][0], weighted.lengths[1][0]);
+
+        Python::initialize();
+        assert_eq!(
+            Weights::try_from("area").err().map(|e| e.to_string()),
+            Some("ValueError: Unknown weights \"area\", expected 'length' or 'count'".to_string())
+        );
+    }
+
+    //
+    // +---+---+
+    // | 0 | 1 |   A: polygons 0, 1 and 2 (arcs 0 to 5)
+    // +---+---+   B: river "r" along arc 1, coast along arcs 3 and 4
+    // | 2 |
+    // +---+
+    //
+    #[test]
+    fn test_neighbors_14() {
+        let a: Vec<Geometry> = [vec![vec![0, 1]], vec![vec![-2, 2, 3]], vec![vec![4, 5]]]
+            .map(|arcs| Geometry::Polygon {
+                arcs,
+                id: None,
+                properties: None,
+                bbox: None,
+            })
+            .into_iter()
+            .collect();
+        let b: Vec<Geometry> = [(vec![1], Some("r".to_string())), (vec![-4, 4], None)]
+            .map(|(arcs, id)| Geometry::LineString {
+                arcs,
+                id,
+                properties: None,
+                bbox: None,
+            })
+            .into_iter()
+            .collect();
+        let a = a.iter().collect::<Vec<_>>();
+        let b = b.iter().collect::<Vec<_>>();
+        assert_eq!(
+            wrap_neighbors_between(&a, &b, false),
+            vec![
+                vec![Id::Index(0)],
+                vec![Id::Index(0), Id::Index(1)],
+                vec![Id::Index(1)]
+            ]
+        );
+        assert_eq!(
+            wrap_neighbors_between(&a, &b, true),
+            vec![
+                vec![Id::Id("r".to_string())],
+                vec![Id::Id("r".to_string()), Id::Index(1)],
+                vec![Id::Index(1)]
+            ]
+        );
+        assert_eq!(wrap_neighbors_between(&b, &[], false), vec![vec![]; 2]);
+    }
+
+    //
+    // +---6---+---7---+
+    // |   2   1   3   |   Squares 0 and 3, and 1 and 2, only touch at the
+    // +---2---+---3---+   centre node shared by arcs 0 to 3.
+    // |   0   0   1   |
+    // +---4---+---5---+
+    //
+    #[test]
+    fn test_neighbors_15() {
+        let objects: Vec<Geometry> = [
+            vec![vec![4, 0, -3]],
+            vec![vec![5, -4, -1]],
+            vec![vec![2, 1, 6]],
+            vec![vec![3, 7, -2]],
+        ]
+        .map(|arcs| Geometry::Polygon {
+            arcs,
+            id: None,
+            properties: None,
+            bbox: None,
+        })
+        .into_iter()
+        .collect();
+        let objects = objects.iter().collect::<Vec<_>>();
+        let topology = queen_topology(
+            None,
+            vec![
+                vec![[1., 0.], [1., 1.]],
+                vec![[1., 1.], [1., 2.]],
+                vec![[0., 1.], [1., 1.]],
+                vec![[1., 1.], [2., 1.]],
+                vec![[0., 1.], [0., 0.], [1., 0.]],
+                vec![[1., 0.], [2., 0.], [2., 1.]],
+                vec![[1., 2.], [0., 2.], [0., 1.]],
+                vec![[2., 1.], [2., 2.], [1., 2.]],
+            ],
+        );
+        assert_eq!(
+            wrap_neighbors(&objects),
+            vec![vec![1, 2], vec![0, 3], vec![0, 3], vec![1, 2]]
+        );
+        assert_eq!(
+            wrap_neighbors_queen(&topology, &objects),
+            vec![vec![1, 2, 3], vec![0, 2, 3], vec![0, 1, 3], vec![0, 1, 2]]
+        );
+    }
 }
@@ -1,23 +1,129 @@
-use crate::bbox::bbox;
+use crate::bbox::{bbox, wrap_bbox};
+use crate::haversine::{haversine, is_geographic};
 use crate::topojsons::{Geometry, TopoJSON, Transform};
+use crate::transform::{IdentityTransformer, ScaleTransformer, Transformer};
 use crate::untransform::ScaleUntransformer;
 
-use pyo3::PyResult;
 use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
 
-pub fn wrap_quantize(topology: &TopoJSON, transform: &f64) -> PyResult<TopoJSON> {
-    Quantize::call(topology, transform)
+/// Largest quantization number tried when searching for a maximum error.
+const MAX_QUANTIZATION: f64 = 2147483648.;
+
+/// Quantizes the topology. When `spherical` is `true`, the bounds are the
+/// antimeridian-aware bounding box of the topology and, when this bounding box
+/// crosses the antimeridian (`x0 > x1`), the longitudes smaller than `x0` are
+/// shifted by 360° so that the quantized longitudes are contiguous.
+pub fn wrap_quantize(topology: &TopoJSON, transform: &f64, spherical: bool) -> PyResult<TopoJSON> {
+    match &topology.transform {
+        Some(t) => Quantize::call(topology, ScaleTransformer::new(t), transform, spherical),
+        None => Quantize::call(topology, IdentityTransformer::new(), transform, spherical),
+    }
+}
+
+pub fn wrap_quantization_error(
+    topology: &TopoJSON,
+    transform: &f64,
+    spherical: bool,
+) -> PyResult<QuantizationReport> {
+    Ok(match &topology.transform {
+        Some(t) => {
+            Quantize::new(topology, ScaleTransformer::new(t), transform, spherical)?.error(topology)
+        }
+        None => Quantize::new(topology, IdentityTransformer::new(), transform, spherical)?
+            .error(topology),
+    })
+}
+
+/// Quantizes the topology with a quantization number whose maximum displacement
+/// does not exceed `max_error`. It is found by doubling, then bisecting below the
+/// first power of two that passes. The maximum displacement does not always
+/// decrease when the quantization number increases, so a smaller passing number
+/// may exist.
+pub fn wrap_quantize_max_error(
+    topology: &TopoJSON,
+    max_error: &f64,
+    spherical: bool,
+) -> PyResult<TopoJSON> {
+    if max_error.is_nan() || *max_error < 0. {
+        return Err(PyRuntimeError::new_err(
+            "'max_error' must be a non-negative number",
+        ));
+    }
+    let error =
+        |n: f64| wrap_quantization_error(topology, &n, spherical).map(|report| report.max_error);
+
+    let mut hi = 2.;
+    while error(hi)? > *max_error {
+        if hi >= MAX_QUANTIZATION {
+            return Err(PyRuntimeError::new_err(format!(
+                "No quantization number smaller than {MAX_QUANTIZATION} satisfies 'max_error'"
+            )));
+        }
+        hi *= 2.;
+    }
+
+    let mut lo = hi / 2.;
+    if hi > 2. {
+        while hi - lo > 1. {
+            let mid = ((lo + hi) / 2.).floor();
+            if error(mid)? > *max_error {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+    }
+    wrap_quantize(topology, &hi, spherical)
+}
+
+#[pyclass]
+#[derive(Debug, PartialEq, Clone)]
+pub struct QuantizationReport {
+    #[pyo3(get)]
+    pub transform: Transform,
+    #[pyo3(get)]
+    pub max_error: f64,
+    #[pyo3(get)]
+    pub mean_error: f64,
+    #[pyo3(get)]
+    pub max_error_meters: Option<f64>,
+    #[pyo3(get)]
+    pub mean_error_meters: Option<f64>,
+    #[pyo3(get)]
+    pub collapsed_arcs: usize,
+}
+
+#[derive(Default)]
+struct Displacements {
+    max: f64,
+    sum: f64,
+    max_meters: f64,
+    sum_meters: f64,
+    count: usize,
 }
 
-struct Quantize {
+struct Quantize<T>
+where
+    T: Transformer,
+{
     r#box: Vec<f64>,
     transform: Option<Transform>,
+    transformer: T,
     untransformer: ScaleUntransformer,
+    /// Longitude below which positions are shifted by 360° when the bounds
+    /// cross the antimeridian.
+    antimeridian: Option<f64>,
 }
 
-impl Quantize {
-    fn call(topology: &TopoJSON, transform: &f64) -> PyResult<TopoJSON> {
-        Quantize::new(topology, transform)?.quantize(topology)
+impl<T: Transformer> Quantize<T> {
+    fn call(
+        topology: &TopoJSON,
+        transformer: T,
+        transform: &f64,
+        spherical: bool,
+    ) -> PyResult<TopoJSON> {
+        Quantize::new(topology, transformer, transform, spherical)?.quantize(topology)
     }
 
     fn quantize(mut self, topology: &TopoJSON) -> PyResult<TopoJSON> {
@@ -39,23 +145,27 @@ impl Quantize {
         })
     }
 
-    fn new(topology: &TopoJSON, transform: &f64) -> PyResult<Quantize> {
-        if topology.transform.is_some() {
-            return Err(PyRuntimeError::new_err("Already quantized"));
-        };
-
+    fn new(
+        topology: &TopoJSON,
+        transformer: T,
+        transform: &f64,
+        spherical: bool,
+    ) -> PyResult<Self> {
         let n = transform.floor();
         if n < 2. || n.is_nan() {
             return Err(PyRuntimeError::new_err("'transform' must be larger than 2"));
         }
-        let r#box = if topology.bbox.is_empty() {
+        let r#box = if spherical {
+            wrap_bbox(topology, true).to_vec()
+        } else if topology.bbox.is_empty() {
             bbox(topology).to_vec()
         } else {
             topology.bbox.to_vec()
         };
         let x0 = r#box[0];
         let y0 = r#box[1];
-        let x1 = r#box[2];
+        let antimeridian = (spherical && x0 > r#box[2]).then_some(x0);
+        let x1 = r#box[2] + if antimeridian.is_some() { 360. } else { 0. };
         let y1 = r#box[3];
 
         let transform = Transform {
@@ -78,12 +188,94 @@ impl Quantize {
         Ok(Self {
             r#box,
             transform: Some(transform),
+            transformer,
             untransformer,
+            antimeridian,
         })
     }
 
+    /// Shifts the longitudes west of the bounds across the antimeridian.
+    fn shift(antimeridian: Option<f64>, point: [f64; 2]) -> [f64; 2] {
+        match antimeridian {
+            Some(x0) if point[0] < x0 => [point[0] + 360., point[1]],
+            _ => point,
+        }
+    }
+
+    fn error(mut self, topology: &TopoJSON) -> QuantizationReport {
+        let transform = self
+            .transform
+            .clone()
+            .expect("Undefined 'transform' during runtime");
+        let mut decoder = ScaleTransformer::new(&transform);
+        let mut displacements = Displacements::default();
+        let mut collapsed_arcs = 0;
+
+        let mut displace = |point: [f64; 2], quantized: [f64; 2]| {
+            let decoded = decoder.call(&quantized, 0);
+            let d = (decoded[0] - point[0]).hypot(decoded[1] - point[1]);
+            let d_meters = haversine(&point, &decoded);
+            displacements.max = displacements.max.max(d);
+            displacements.sum += d;
+            displacements.max_meters = displacements.max_meters.max(d_meters);
+            displacements.sum_meters += d_meters;
+            displacements.count += 1;
+        };
+
+        for arc in topology.arcs.iter() {
+            let mut points = Vec::with_capacity(arc.len());
+            let mut quantized_points = Vec::with_capacity(arc.len());
+            for (i, point) in arc.iter().enumerate() {
+                let point = Self::shift(self.antimeridian, self.transformer.call(point, i));
+                let quantized = self.untransformer.call(&point, 0);
+                displace(point, quantized);
+                points.push(point);
+                quantized_points.push(quantized);
+            }
+            if points.iter().any(|p| *p != points[0])
+                && quantized_points.iter().all(|p| *p == quantized_points[0])
+            {
+                collapsed_arcs += 1;
+            }
+        }
+
+        let mut points = Vec::new();
+        topology
+            .objects
+            .values()
+            .for_each(|o| Self::points(o, &mut points));
+        for point in points {
+            let point = Self::shift(self.antimeridian, self.transformer.call(point, 0));
+            let quantized = self.untransformer.call(&point, 0);
+            displace(point, quantized);
+        }
+
+        let n = displacements.count.max(1) as f64;
+        let geographic = is_geographic(&self.r#box);
+        QuantizationReport {
+            transform,
+            max_error: displacements.max,
+            mean_error: displacements.sum / n,
+            max_error_meters: geographic.then_some(displacements.max_meters),
+            mean_error_meters: geographic.then_some(displacements.sum_meters / n),
+            collapsed_arcs,
+        }
+    }
+
+    fn points<'a>(o: &'a Geometry, points: &mut Vec<&'a [f64; 2]>) {
+        match o {
+            Geometry::GeometryCollection { geometries, .. } => {
+                geometries.iter().for_each(|o| Self::points(o, points))
+            }
+            Geometry::Point { coordinates, .. } => points.push(coordinates),
+            Geometry::MultiPoint { coordinates, .. } => points.extend(coordinates.iter()),
+            _ => (),
+        }
+    }
+
     fn quantize_point(&mut self, point: &[f64; 2]) -> [f64; 2] {
-        self.untransformer.call(point, 0)
+        let point = Self::shift(self.antimeridian, self.transformer.call(point, 0));
+        self.untransformer.call(&point, 0)
     }
 
     fn quantize_geometry(&mut self, input: &Geometry) -> Geometry {
@@ -131,10 +323,11 @@ impl Quantize {
         }
     }
 
-    fn quantize_arc(&mut self, input: &[[i32; 2]]) -> Vec<[i32; 2]> {
+    fn quantize_arc(&mut self, input: &[[f64; 2]]) -> Vec<[f64; 2]> {
+        let antimeridian = self.antimeridian;
         let mut untransform = |i: usize| {
-            let arc = &input[i];
-            self.untransformer.call(&arc.map(|x| x as f64), i)
+            let point = Self::shift(antimeridian, self.transformer.call(&input[i], i));
+            self.untransformer.call(&point, i)
         };
 
         let mut output = vec![untransform(0)];
@@ -147,13 +340,14 @@ impl Quantize {
         if output.len() == 1 {
             output.push([0., 0.]);
         }
-        output.iter().map(|array| array.map(|x| x as i32)).collect()
+        output
     }
 }
 
 #[cfg(test)]
 mod tests {
     use pyo3::Python;
+    use std::collections::HashMap;
 
     use crate::request::request;
 
@@ -168,7 +362,7 @@ mod tests {
         let expected_topology =
             serde_json::from_str::<TopoJSON>(&request(expected_filetest).await?).unwrap();
         assert_eq!(
-            wrap_quantize(&topology, transform)
+            wrap_quantize(&topology, transform, false)
                 .map_err(|e| format!("Error during quantize operation: {}", e.to_string()))?,
             expected_topology
         );
@@ -225,7 +419,7 @@ mod tests {
             serde_json::from_str::<TopoJSON>(&request("test/topojson/polygon.json").await?)
                 .unwrap();
         before.bbox.clear();
-        let after = wrap_quantize(&before, &1e4)
+        let after = wrap_quantize(&before, &1e4, false)
             .map_err(|e| format!("Error during quantize operation: {}", e.to_string()))?;
 
         let expected_topology =
@@ -239,21 +433,13 @@ mod tests {
 
     #[tokio::test]
     async fn test_quantize_6() -> Result<(), String> {
-        Python::initialize();
-        let topology =
-            serde_json::from_str::<TopoJSON>(&request("test/topojson/polygon-q1e4.json").await?)
-                .unwrap();
-        if let Err(py_runtime_error) = wrap_quantize(&topology, &1e4) {
-            assert_eq!(
-                py_runtime_error.to_string(),
-                String::from("RuntimeError: Already quantized")
-            );
-            Ok(())
-        } else {
-            Err(String::from(
-                "Quantized must return an error: 'Already quantized'",
-            ))
-        }
+        quantize(
+            "test/topojson/polygon-q1e4.json",
+            &1e4,
+            "test/topojson/polygon-q1e4.json",
+        )
+        .await?;
+        Ok(())
     }
 
     #[tokio::test]
@@ -263,7 +449,7 @@ mod tests {
             serde_json::from_str::<TopoJSON>(&request("test/topojson/polygon.json").await?)
                 .unwrap();
         for transform in [0., 1.5, f64::NAN, -2.] {
-            if let Err(py_runtime_error) = wrap_quantize(&topology, &transform) {
+            if let Err(py_runtime_error) = wrap_quantize(&topology, &transform, false) {
                 assert_eq!(
                     py_runtime_error.to_string(),
                     String::from("RuntimeError: 'transform' must be larger than 2")
@@ -277,4 +463,161 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_quantize_8() -> PyResult<()> {
+        let topology = TopoJSON {
+            bbox: vec![0., 0., 4., 4.],
+            transform: Some(Transform {
+                scale: [1., 1.],
+                translate: [0., 0.],
+            }),
+            objects: HashMap::from_iter([(
+                "foo".to_string(),
+                Geometry::Point {
+                    coordinates: [1., 1.],
+                    id: None,
+                    properties: None,
+                    bbox: None,
+                },
+            )]),
+            arcs: vec![
+                vec![[0., 0.], [1., 0.], [1., 0.], [2., 4.]],
+                vec![[0., 0.], [1., 1.]],
+                vec![[3., 4.], [1., 0.]],
+            ],
+        };
+        let expected = TopoJSON {
+            bbox: vec![0., 0., 4., 4.],
+            transform: Some(Transform {
+                scale: [2., 2.],
+                translate: [0., 0.],
+            }),
+            objects: HashMap::from_iter([(
+                "foo".to_string(),
+                Geometry::Point {
+                    coordinates: [1., 1.],
+                    id: None,
+                    properties: None,
+                    bbox: None,
+                },
+            )]),
+            arcs: vec![
+                vec![[0., 0.], [1., 0.], [1., 2.]],
+                vec![[0., 0.], [1., 1.]],
+                // A degenerate arc keeps its first position and a zero delta.
+                vec![[2., 2.], [0., 0.]],
+            ],
+        };
+        assert_eq!(wrap_quantize(&topology, &3., false)?, expected);
+        Ok(())
+    }
+
+    fn line_topology(arcs: Vec<Vec<[f64; 2]>>) -> TopoJSON {
+        TopoJSON {
+            bbox: vec![],
+            transform: None,
+            objects: HashMap::from_iter([(
+                "foo".to_string(),
+                Geometry::MultiLineString {
+                    arcs: (0..arcs.len() as i32).map(|i| vec![i]).collect(),
+                    id: None,
+                    properties: None,
+                    bbox: None,
+                },
+            )]),
+            arcs,
+        }
+    }
+
+    #[test]
+    fn test_quantize_9() -> PyResult<()> {
+        let topology = line_topology(vec![
+            vec![[0., 0.], [1., 0.3], [2., 2.]],
+            vec![[0., 0.], [0.2, 0.2]],
+        ]);
+        let report = wrap_quantization_error(&topology, &3., false)?;
+        assert_eq!(
+            report.transform,
+            Transform {
+                scale: [1., 1.],
+                translate: [0., 0.]
+            }
+        );
+        assert!((report.max_error - 0.3).abs() < 1e-12);
+        assert!((report.mean_error - (0.3 + 0.2 * 2f64.sqrt()) / 5.).abs() < 1e-12);
+        assert!((report.max_error_meters.unwrap() - 33_358.52).abs() < 0.01);
+        assert_eq!(report.collapsed_arcs, 1);
+
+        let mut projected = line_topology(vec![vec![[0., 0.], [960., 600.]]]);
+        projected.bbox = vec![0., 0., 960., 600.];
+        let report = wrap_quantization_error(&projected, &1e4, false)?;
+        assert_eq!(report.max_error_meters, None);
+        assert_eq!(report.mean_error_meters, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quantize_10() -> PyResult<()> {
+        let topology = line_topology(vec![vec![[0., 0.], [0.3, 0.3], [1., 1.]]]);
+        let quantized = wrap_quantize_max_error(&topology, &0.1, false)?;
+        assert_eq!(quantized, wrap_quantize(&topology, &4., false)?);
+        assert!(wrap_quantization_error(&topology, &4., false)?.max_error <= 0.1);
+        assert!(wrap_quantization_error(&topology, &3., false)?.max_error > 0.1);
+
+        assert_eq!(
+            wrap_quantize_max_error(&topology, &1., false)?,
+            wrap_quantize(&topology, &2., false)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_quantize_11() -> PyResult<()> {
+        Python::initialize();
+        let topology = line_topology(vec![vec![[0., 0.], [1., 1.]]]);
+        for max_error in [-1., f64::NAN] {
+            assert_eq!(
+                wrap_quantize_max_error(&topology, &max_error, false)
+                    .err()
+                    .map(|e| e.to_string()),
+                Some(String::from(
+                    "RuntimeError: 'max_error' must be a non-negative number"
+                ))
+            );
+        }
+        // Positions on the grid satisfy a zero maximum error.
+        assert_eq!(
+            wrap_quantize_max_error(&topology, &0., false)?.transform,
+            wrap_quantize(&topology, &2., false)?.transform
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_quantize_12() -> PyResult<()> {
+        let topology = line_topology(vec![vec![[170., 0.], [-170., 2.]], vec![[179., 1.]]]);
+        let quantized = wrap_quantize(&topology, &3., true)?;
+        assert_eq!(quantized.bbox, vec![170., 0., -170., 2.]);
+        assert_eq!(
+            quantized.transform,
+            Some(Transform {
+                scale: [10., 1.],
+                translate: [170., 0.]
+            })
+        );
+        assert_eq!(
+            quantized.arcs,
+            vec![vec![[0., 0.], [2., 2.]], vec![[1., 1.], [0., 0.]]]
+        );
+        assert_eq!(wrap_quantization_error(&topology, &3., true)?.max_error, 1.);
+        assert_eq!(
+            wrap_quantize(&topology, &3., false)?.transform,
+            Some(Transform {
+                scale: [174.5, 1.],
+                translate: [-170., 0.]
+            })
+        );
+        Ok(())
+    }
 }
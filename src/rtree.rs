@@ -0,0 +1,249 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of children of a node.
+const NODE_SIZE: usize = 16;
+
+const EMPTY: [f64; 4] = [f64::INFINITY, f64::INFINITY, -f64::INFINITY, -f64::INFINITY];
+
+fn union(a: [f64; 4], b: [f64; 4]) -> [f64; 4] {
+    [
+        a[0].min(b[0]),
+        a[1].min(b[1]),
+        a[2].max(b[2]),
+        a[3].max(b[3]),
+    ]
+}
+
+fn intersects(a: [f64; 4], b: [f64; 4]) -> bool {
+    a[0] <= b[2] && b[0] <= a[2] && a[1] <= b[3] && b[1] <= a[3]
+}
+
+/// Static R-tree of bounding boxes `[x0, y0, x1, y1]`, packed with the
+/// Sort-Tile-Recursive algorithm.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RTree {
+    /// Bounding box and item of each entry, in packing order.
+    items: Vec<([f64; 4], usize)>,
+    /// Levels of nodes from the leaves to the root: the bounding box of each
+    /// node and the range of its children in the level below, or in `items`
+    /// for the leaves.
+    levels: Vec<Vec<([f64; 4], usize, usize)>>,
+}
+
+impl RTree {
+    pub fn new(mut items: Vec<([f64; 4], usize)>) -> Self {
+        let mut levels = Vec::new();
+        if !items.is_empty() {
+            levels.push(pack(&mut items, |item| item.0));
+            while levels.last().is_some_and(|nodes| nodes.len() > 1) {
+                let nodes = levels.last_mut().unwrap();
+                let upper = pack(nodes, |node| node.0);
+                levels.push(upper);
+            }
+        }
+        Self { items, levels }
+    }
+
+    /// Items whose bounding box intersects `bbox`, in no particular order.
+    pub fn search(&self, bbox: [f64; 4]) -> Vec<usize> {
+        let mut items = Vec::new();
+        let Some(root) = self.levels.last() else {
+            return items;
+        };
+        let mut stack: Vec<(usize, usize)> = (0..root.len())
+            .map(|i| (self.levels.len() - 1, i))
+            .collect();
+        while let Some((level, i)) = stack.pop() {
+            let (node, start, end) = self.levels[level][i];
+            if !intersects(node, bbox) {
+                continue;
+            }
+            if level == 0 {
+                items.extend(
+                    self.items[start..end]
+                        .iter()
+                        .filter(|(item, _)| intersects(*item, bbox))
+                        .map(|&(_, item)| item),
+                );
+            } else {
+                stack.extend((start..end).map(|j| (level - 1, j)));
+            }
+        }
+        items
+    }
+}
+
+/// Node or item of a nearest search, ordered by increasing distance in a
+/// `BinaryHeap`.
+struct Candidate {
+    distance: f64,
+    level: Option<usize>,
+    i: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .distance
+            .total_cmp(&self.distance)
+            .then_with(|| other.i.cmp(&self.i))
+    }
+}
+
+impl RTree {
+    /// Visits the items by increasing distance until `visit` returns `false`.
+    /// The distance to a bounding box must not exceed the distance to the
+    /// items it contains.
+    pub fn nearest(
+        &self,
+        bbox_distance: impl Fn([f64; 4]) -> f64,
+        mut item_distance: impl FnMut(usize) -> f64,
+        mut visit: impl FnMut(usize, f64) -> bool,
+    ) {
+        let Some(root) = self.levels.last() else {
+            return;
+        };
+        let mut heap: BinaryHeap<Candidate> = root
+            .iter()
+            .enumerate()
+            .map(|(i, &(node, ..))| Candidate {
+                distance: bbox_distance(node),
+                level: Some(self.levels.len() - 1),
+                i,
+            })
+            .collect();
+        while let Some(Candidate { distance, level, i }) = heap.pop() {
+            match level {
+                None => {
+                    if !visit(i, distance) {
+                        return;
+                    }
+                }
+                Some(0) => {
+                    let (_, start, end) = self.levels[0][i];
+                    heap.extend(self.items[start..end].iter().map(|&(_, item)| Candidate {
+                        distance: item_distance(item),
+                        level: None,
+                        i: item,
+                    }));
+                }
+                Some(level) => {
+                    let (_, start, end) = self.levels[level][i];
+                    heap.extend((start..end).map(|j| Candidate {
+                        distance: bbox_distance(self.levels[level - 1][j].0),
+                        level: Some(level - 1),
+                        i: j,
+                    }));
+                }
+            }
+        }
+    }
+}
+
+/// Sorts the entries into vertical slices of nodes sorted by their center,
+/// then groups them into nodes.
+fn pack<E>(entries: &mut [E], bbox: impl Fn(&E) -> [f64; 4]) -> Vec<([f64; 4], usize, usize)> {
+    let center = |e: &E, k: usize| {
+        let b = bbox(e);
+        b[k] + b[k + 2]
+    };
+    let nodes = entries.len().div_ceil(NODE_SIZE);
+    let slices = (nodes as f64).sqrt().ceil() as usize;
+    entries.sort_by(|a, b| center(a, 0).total_cmp(&center(b, 0)));
+    for slice in entries.chunks_mut(NODE_SIZE * slices.max(1)) {
+        slice.sort_by(|a, b| center(a, 1).total_cmp(&center(b, 1)));
+    }
+    entries
+        .chunks(NODE_SIZE)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let node = chunk.iter().fold(EMPTY, |node, e| union(node, bbox(e)));
+            (node, i * NODE_SIZE, i * NODE_SIZE + chunk.len())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rtree_1() {
+        assert_eq!(
+            RTree::new(Vec::new()).search([0., 0., 1., 1.]),
+            Vec::<usize>::new()
+        );
+
+        // Grid of 50 x 50 unit squares, searched by brute force.
+        let items: Vec<([f64; 4], usize)> = (0..2500)
+            .map(|i| {
+                let [x, y] = [(i % 50) as f64, (i / 50) as f64];
+                ([x, y, x + 1., y + 1.], i)
+            })
+            .collect();
+        let tree = RTree::new(items.clone());
+        assert_eq!(tree.levels.len(), 3);
+        for bbox in [
+            [10.5, 10.5, 10.5, 10.5],
+            [3.2, 40.1, 7.9, 44.],
+            [-5., -5., 100., 0.5],
+            [60., 60., 70., 70.],
+        ] {
+            let mut found = tree.search(bbox);
+            found.sort_unstable();
+            let expected: Vec<usize> = items
+                .iter()
+                .filter(|(item, _)| intersects(*item, bbox))
+                .map(|&(_, i)| i)
+                .collect();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn test_rtree_2() {
+        let items: Vec<([f64; 4], usize)> = (0..2500)
+            .map(|i| {
+                let [x, y] = [(i % 50) as f64 * 2., (i / 50) as f64 * 2.];
+                ([x, y, x + 1., y + 1.], i)
+            })
+            .collect();
+        let tree = RTree::new(items.clone());
+        let p = [31.5, 70.2];
+        let distance = |b: [f64; 4]| {
+            let dx = (b[0] - p[0]).max(0.).max(p[0] - b[2]);
+            let dy = (b[1] - p[1]).max(0.).max(p[1] - b[3]);
+            dx.hypot(dy)
+        };
+        let mut visited = Vec::new();
+        tree.nearest(
+            distance,
+            |i| distance(items[i].0),
+            |i, d| {
+                visited.push((i, d));
+                visited.len() < 5
+            },
+        );
+        let mut expected: Vec<(usize, f64)> =
+            items.iter().map(|&(b, i)| (i, distance(b))).collect();
+        expected.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        assert_eq!(visited, expected[..5]);
+    }
+}
@@ -0,0 +1,343 @@
+use crate::topojsons::{Geometry, TopoJSON};
+use crate::transform::{IdentityTransformer, ScaleTransformer, Transformer};
+
+use pyo3::PyResult;
+use pyo3::exceptions::PyRuntimeError;
+
+pub fn wrap_simplify_dp(topology: &TopoJSON, tolerance: &f64) -> PyResult<TopoJSON> {
+    if tolerance.is_nan() || *tolerance < 0. {
+        return Err(PyRuntimeError::new_err(
+            "'tolerance' must be a non-negative number",
+        ));
+    }
+    Ok(match &topology.transform {
+        Some(transform) => SimplifyDp::call(topology, ScaleTransformer::new(transform), tolerance),
+        None => SimplifyDp::call(topology, IdentityTransformer::new(), tolerance),
+    })
+}
+
+/// Douglas-Peucker weights of each point of a line: a point is kept for any
+/// tolerance strictly smaller than its weight. The weight of a point is
+/// bounded by the weight of the point which splits its parent segment, so that
+/// the kept points for a given tolerance are the same as the ones of the
+/// classical recursive algorithm.
+fn weights(points: &[[f64; 2]]) -> Vec<f64> {
+    let n = points.len();
+    let mut weights = vec![0.; n];
+    if n == 0 {
+        return weights;
+    }
+    weights[0] = f64::INFINITY;
+    weights[n - 1] = f64::INFINITY;
+
+    let mut stack = vec![(0, n - 1, f64::INFINITY)];
+    while let Some((first, last, parent)) = stack.pop() {
+        if last <= first + 1 {
+            continue;
+        }
+        let (k, d) = (first + 1..last)
+            .map(|k| {
+                (
+                    k,
+                    segment_distance(&points[k], &points[first], &points[last]),
+                )
+            })
+            .fold((first + 1, -1.), |acc, x| if x.1 > acc.1 { x } else { acc });
+        let weight = d.min(parent);
+        weights[k] = weight;
+        stack.push((first, k, weight));
+        stack.push((k, last, weight));
+    }
+    weights
+}
+
+fn segment_distance(p: &[f64; 2], a: &[f64; 2], b: &[f64; 2]) -> f64 {
+    let dx = b[0] - a[0];
+    let dy = b[1] - a[1];
+    let d2 = dx * dx + dy * dy;
+    let t = if d2 == 0. {
+        0.
+    } else {
+        (((p[0] - a[0]) * dx + (p[1] - a[1]) * dy) / d2).clamp(0., 1.)
+    };
+    let x = a[0] + t * dx - p[0];
+    let y = a[1] + t * dy - p[1];
+    (x * x + y * y).sqrt()
+}
+
+struct SimplifyDp {
+    weights: Vec<Vec<f64>>,
+    kept: Vec<Vec<bool>>,
+}
+
+impl SimplifyDp {
+    fn call<T: Transformer>(topology: &TopoJSON, transformer: T, tolerance: &f64) -> TopoJSON {
+        SimplifyDp::new(topology, transformer, tolerance).simplify(topology)
+    }
+
+    fn new<T: Transformer>(topology: &TopoJSON, mut transformer: T, tolerance: &f64) -> Self {
+        let weights: Vec<Vec<f64>> = topology
+            .arcs
+            .iter()
+            .map(|arc| {
+                let points: Vec<[f64; 2]> = arc
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| transformer.call(p, i))
+                    .collect();
+                weights(&points)
+            })
+            .collect();
+        let kept = weights
+            .iter()
+            .map(|weights| weights.iter().map(|w| w > tolerance).collect())
+            .collect();
+        Self { weights, kept }
+    }
+
+    fn simplify(mut self, topology: &TopoJSON) -> TopoJSON {
+        topology
+            .objects
+            .values()
+            .for_each(|geometry| self.geometry(geometry));
+
+        let arcs = topology
+            .arcs
+            .iter()
+            .zip(self.kept.iter())
+            .map(|(arc, kept)| match topology.transform {
+                Some(_) => Self::encode(arc, kept),
+                None => arc
+                    .iter()
+                    .zip(kept.iter())
+                    .filter(|(_, kept)| **kept)
+                    .map(|(p, _)| *p)
+                    .collect(),
+            })
+            .collect();
+
+        TopoJSON {
+            bbox: topology.bbox.clone(),
+            transform: topology.transform.clone(),
+            objects: topology.objects.clone(),
+            arcs,
+        }
+    }
+
+    /// Re-encodes the kept points of a quantized arc as deltas.
+    fn encode(arc: &[[f64; 2]], kept: &[bool]) -> Vec<[f64; 2]> {
+        let mut position = [0., 0.];
+        let mut previous = [0., 0.];
+        let mut output = Vec::new();
+        for (p, kept) in arc.iter().zip(kept.iter()) {
+            position = [position[0] + p[0], position[1] + p[1]];
+            if *kept {
+                output.push([position[0] - previous[0], position[1] - previous[1]]);
+                previous = position;
+            }
+        }
+        output
+    }
+
+    fn geometry(&mut self, o: &Geometry) {
+        match o {
+            Geometry::GeometryCollection { geometries, .. } => {
+                geometries.iter().for_each(|o| self.geometry(o))
+            }
+            Geometry::Polygon { arcs, .. } => arcs.iter().for_each(|ring| self.ring(ring)),
+            Geometry::MultiPolygon { arcs, .. } => arcs
+                .iter()
+                .for_each(|polygon| polygon.iter().for_each(|ring| self.ring(ring))),
+            _ => (),
+        }
+    }
+
+    /// Keeps the most significant removed points of the arcs of a ring until
+    /// the ring has at least 4 points.
+    fn ring(&mut self, ring: &[i32]) {
+        let mut n = 1 + ring
+            .iter()
+            .map(|&i| {
+                let kept = &self.kept[if i < 0 { !i } else { i } as usize];
+                kept.iter().filter(|&&k| k).count().saturating_sub(1)
+            })
+            .sum::<usize>();
+
+        while n < 4 {
+            let candidate = ring
+                .iter()
+                .flat_map(|&i| {
+                    let j = if i < 0 { !i } else { i } as usize;
+                    self.weights[j]
+                        .iter()
+                        .zip(self.kept[j].iter())
+                        .enumerate()
+                        .filter(|(_, (_, kept))| !**kept)
+                        .map(move |(k, (w, _))| (j, k, *w))
+                })
+                .fold(None, |acc: Option<(usize, usize, f64)>, x| match acc {
+                    Some(acc) if acc.2 >= x.2 => Some(acc),
+                    _ => Some(x),
+                });
+            match candidate {
+                Some((j, k, _)) => self.kept[j][k] = true,
+                None => break,
+            }
+            n += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::topojsons::{Transform, quantized_arcs};
+    use pyo3::Python;
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn simple_topology(object: Geometry, arcs: Vec<Vec<[i32; 2]>>) -> TopoJSON {
+        TopoJSON {
+            bbox: vec![],
+            transform: None,
+            objects: HashMap::from_iter([("foo".to_string(), object)]),
+            arcs: quantized_arcs(arcs),
+        }
+    }
+
+    //
+    //       C
+    //      / \       ==>
+    // A---B   D---E       A-----------E
+    //
+    #[test]
+    fn test_simplify_dp_1() {
+        let topology = simple_topology(
+            Geometry::LineString {
+                arcs: vec![0],
+                id: None,
+                properties: None,
+                bbox: None,
+            },
+            vec![vec![[0, 0], [1, 0], [2, 1], [3, 0], [4, 0]]],
+        );
+        let simplified = wrap_simplify_dp(&topology, &1.).unwrap();
+        assert_eq!(simplified.arcs, quantized_arcs(vec![vec![[0, 0], [4, 0]]]));
+        let simplified = wrap_simplify_dp(&topology, &0.5).unwrap();
+        assert_eq!(
+            simplified.arcs,
+            quantized_arcs(vec![vec![[0, 0], [2, 1], [4, 0]]])
+        );
+        assert_eq!(simplified.objects, topology.objects);
+    }
+
+    //
+    // +----+----+
+    // |    |    |
+    // |    |    |
+    // |    |    |
+    // +----+----+
+    //
+    #[test]
+    fn test_simplify_dp_2() {
+        let topology = simple_topology(
+            Geometry::GeometryCollection {
+                geometries: vec![
+                    Geometry::Polygon {
+                        arcs: vec![vec![0, 1]],
+                        id: None,
+                        properties: None,
+                        bbox: None,
+                    },
+                    Geometry::Polygon {
+                        arcs: vec![vec![-1, 2]],
+                        id: None,
+                        properties: None,
+                        bbox: None,
+                    },
+                ],
+                id: None,
+                properties: None,
+                bbox: None,
+            },
+            vec![
+                vec![[1, 2], [1, 1], [1, 0]],
+                vec![[1, 0], [0, 0], [0, 2], [1, 2]],
+                vec![[1, 2], [2, 2], [2, 0], [1, 0]],
+            ],
+        );
+        let simplified = wrap_simplify_dp(&topology, &10.).unwrap();
+        assert_eq!(simplified.arcs[0], vec![[1., 2.], [1., 0.]]);
+        assert_eq!(simplified.arcs[1].len(), 3);
+        assert_eq!(simplified.arcs[2].len(), 3);
+    }
+
+    //
+    // A-----B       A-----B
+    // |     |  ==>  |     |
+    // D-----C       D     C
+    //
+    #[test]
+    fn test_simplify_dp_3() {
+        let topology = simple_topology(
+            Geometry::Polygon {
+                arcs: vec![vec![0]],
+                id: None,
+                properties: None,
+                bbox: None,
+            },
+            vec![vec![[0, 0], [0, 1], [1, 1], [1, 0], [0, 0]]],
+        );
+        let simplified = wrap_simplify_dp(&topology, &10.).unwrap();
+        assert_eq!(simplified.arcs[0].len(), 4);
+        assert_eq!(simplified.arcs[0].first(), simplified.arcs[0].last());
+    }
+
+    #[test]
+    fn test_simplify_dp_4() {
+        let mut topology = simple_topology(
+            Geometry::LineString {
+                arcs: vec![0],
+                id: None,
+                properties: None,
+                bbox: None,
+            },
+            vec![vec![[0, 0], [1, 0], [1, 1], [1, -1], [1, 0]]],
+        );
+        topology.transform = Some(Transform {
+            scale: [2., 2.],
+            translate: [10., 10.],
+        });
+        let simplified = wrap_simplify_dp(&topology, &1.5).unwrap();
+        assert_eq!(
+            simplified.arcs,
+            quantized_arcs(vec![vec![[0, 0], [2, 1], [2, -1]]])
+        );
+        assert_eq!(simplified.transform, topology.transform);
+    }
+
+    #[test]
+    fn test_simplify_dp_5() {
+        Python::initialize();
+        let topology = simple_topology(
+            Geometry::LineString {
+                arcs: vec![0],
+                id: None,
+                properties: None,
+                bbox: None,
+            },
+            vec![vec![[0, 0], [1, 0]]],
+        );
+        for tolerance in [-1., f64::NAN] {
+            assert_eq!(
+                wrap_simplify_dp(&topology, &tolerance)
+                    .err()
+                    .map(|e| e.to_string()),
+                Some(String::from(
+                    "RuntimeError: 'tolerance' must be a non-negative number"
+                ))
+            );
+        }
+        assert!(wrap_simplify_dp(&topology, &0.).is_ok());
+    }
+}
@@ -9,13 +9,13 @@ pub fn stitch(topology: &TopoJSON, arcs: Vec<i32>) -> Vec<Vec<i32>> {
 
 #[derive(Debug, PartialEq, Clone)]
 struct Fragment {
-    start: [i32; 2],
-    end: [i32; 2],
+    start: [u64; 2],
+    end: [u64; 2],
     arcs: Vec<i32>,
 }
 
 impl Fragment {
-    fn new(start: [i32; 2], end: [i32; 2], arcs: Vec<i32>) -> Self {
+    fn new(start: [u64; 2], end: [u64; 2], arcs: Vec<i32>) -> Self {
         Self { start, end, arcs }
     }
 
@@ -45,8 +45,8 @@ impl AddFragments for Rc<RefCell<Fragment>> {
 #[derive(Default)]
 struct Stitch {
     stitched_arcs: HashSet<usize>,
-    fragment_by_start: IndexMap<[i32; 2], Rc<RefCell<Fragment>>>,
-    fragment_by_end: IndexMap<[i32; 2], Rc<RefCell<Fragment>>>,
+    fragment_by_start: IndexMap<[u64; 2], Rc<RefCell<Fragment>>>,
+    fragment_by_end: IndexMap<[u64; 2], Rc<RefCell<Fragment>>>,
     fragments: Vec<Vec<i32>>,
 }
 
@@ -55,6 +55,12 @@ fn arc_index(i: i32) -> usize {
     (if i < 0 { !i } else { i }) as usize
 }
 
+/// Hashable key of a position (`-0.` and `0.` share the same key).
+#[inline]
+pub(crate) fn point_key(p: [f64; 2]) -> [u64; 2] {
+    p.map(|x| (x + 0.).to_bits())
+}
+
 impl Stitch {
     fn call(topology: &TopoJSON, arcs: Vec<i32>) -> Vec<Vec<i32>> {
         Self::default().fragments(topology, arcs)
@@ -73,7 +79,7 @@ impl Stitch {
             .or_insert(fragment.clone());
     }
 
-    fn replace_from(&mut self, fg: Rc<RefCell<Fragment>>, start: [i32; 2], end: [i32; 2]) {
+    fn replace_from(&mut self, fg: Rc<RefCell<Fragment>>, start: [u64; 2], end: [u64; 2]) {
         {
             let mut fg_borrow = fg.borrow_mut();
             fg_borrow.start = start;
@@ -93,7 +99,7 @@ impl Stitch {
             .enumerate()
             .filter(|&(_, &i)| {
                 let arc = &topology.arcs[if i < 0 { !i as usize } else { i as usize }];
-                arc.len() < 3 && arc[1][0] == 0 && arc[1][1] == 0
+                arc.len() < 3 && arc[1][0] == 0. && arc[1][1] == 0.
             })
             .map(|(j, &i)| {
                 let r = (j, empty_index, i);
@@ -184,7 +190,7 @@ impl Stitch {
         self.fragments
     }
 
-    fn ends(&self, topology: &TopoJSON, &i: &i32) -> [[i32; 2]; 2] {
+    fn ends(&self, topology: &TopoJSON, &i: &i32) -> [[u64; 2]; 2] {
         let arc = &topology.arcs[if i < 0 { !i } else { i } as usize];
         let p0 = *arc.first().unwrap();
 
@@ -196,6 +202,7 @@ impl Stitch {
         } else {
             *arc.last().unwrap()
         };
+        let [p0, p1] = [point_key(p0), point_key(p1)];
         if i < 0 { [p1, p0] } else { [p0, p1] }
     }
 
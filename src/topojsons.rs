@@ -28,6 +28,41 @@ where
     Ok(Some(value.to_string()))
 }
 
+/// Coordinate serialized as an integer when it has no fractional part, which
+/// keeps quantized arcs compact.
+struct Coordinate(f64);
+
+impl Serialize for Coordinate {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if self.0.fract() == 0. && self.0.abs() < 9007199254740992. {
+            serializer.serialize_i64(self.0 as i64)
+        } else {
+            serializer.serialize_f64(self.0)
+        }
+    }
+}
+
+fn serialize_arcs<S>(arcs: &[Vec<[f64; 2]>], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    struct Arc<'a>(&'a [[f64; 2]]);
+
+    impl Serialize for Arc<'_> {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.collect_seq(self.0.iter().map(|p| p.map(Coordinate)))
+        }
+    }
+
+    serializer.collect_seq(arcs.iter().map(|arc| Arc(arc)))
+}
+
 #[pyclass]
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct TopoJSON {
@@ -38,7 +73,8 @@ pub struct TopoJSON {
     #[pyo3(get)]
     pub objects: HashMap<String, Geometry>,
     #[pyo3(get)]
-    pub arcs: Vec<Vec<[i32; 2]>>,
+    #[serde(serialize_with = "serialize_arcs")]
+    pub arcs: Vec<Vec<[f64; 2]>>,
 }
 
 #[pyclass]
@@ -120,6 +156,14 @@ pub enum Geometry {
 }
 
 impl Geometry {
+    /// Geometries of a collection, or the geometry itself for any other type.
+    pub fn members(&self) -> Vec<&Geometry> {
+        match self {
+            Geometry::GeometryCollection { geometries, .. } => geometries.iter().collect(),
+            o => vec![o],
+        }
+    }
+
     pub fn id(&self) -> Option<String> {
         match self {
             Geometry::GeometryCollection { id, .. } => id.clone(),
@@ -144,6 +188,18 @@ impl Geometry {
         }
     }
 
+    pub fn properties_mut(&mut self) -> &mut Option<String> {
+        match self {
+            Geometry::GeometryCollection { properties, .. } => properties,
+            Geometry::Point { properties, .. } => properties,
+            Geometry::MultiPoint { properties, .. } => properties,
+            Geometry::LineString { properties, .. } => properties,
+            Geometry::MultiLineString { properties, .. } => properties,
+            Geometry::Polygon { properties, .. } => properties,
+            Geometry::MultiPolygon { properties, .. } => properties,
+        }
+    }
+
     pub fn bbox(&self) -> Option<Vec<f64>> {
         match self {
             Geometry::GeometryCollection { bbox, .. } => bbox.clone(),
@@ -155,4 +211,51 @@ impl Geometry {
             Geometry::MultiPolygon { bbox, .. } => bbox.clone(),
         }
     }
+
+    pub fn bbox_mut(&mut self) -> &mut Option<Vec<f64>> {
+        match self {
+            Geometry::GeometryCollection { bbox, .. } => bbox,
+            Geometry::Point { bbox, .. } => bbox,
+            Geometry::MultiPoint { bbox, .. } => bbox,
+            Geometry::LineString { bbox, .. } => bbox,
+            Geometry::MultiLineString { bbox, .. } => bbox,
+            Geometry::Polygon { bbox, .. } => bbox,
+            Geometry::MultiPolygon { bbox, .. } => bbox,
+        }
+    }
+}
+
+/// Arcs of a test fixture, written with the integer positions of a quantized
+/// topology.
+#[cfg(test)]
+pub(crate) fn quantized_arcs(arcs: Vec<Vec<[i32; 2]>>) -> Vec<Vec<[f64; 2]>> {
+    arcs.into_iter()
+        .map(|arc| arc.into_iter().map(|p| p.map(f64::from)).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topojsons_1() {
+        let topology = TopoJSON {
+            bbox: Vec::new(),
+            transform: None,
+            objects: HashMap::new(),
+            arcs: vec![vec![
+                [3., -2.],
+                [0.1 + 0.2, -1. / 3.],
+                [1e20, 4503599627370495.5],
+            ]],
+        };
+        let json = serde_json::to_string(&topology).unwrap();
+        assert_eq!(
+            json,
+            r#"{"bbox":[],"transform":null,"objects":{},"arcs":[[[3,-2],[0.30000000000000004,-0.3333333333333333],[1e+20,4503599627370495.5]]]}"#
+        );
+        // Non-integral coordinates are written and parsed back exactly.
+        assert_eq!(serde_json::from_str::<TopoJSON>(&json).unwrap(), topology);
+    }
 }
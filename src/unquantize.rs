@@ -0,0 +1,190 @@
+use crate::topojsons::{Geometry, TopoJSON};
+use crate::transform::{IdentityTransformer, ScaleTransformer, Transformer};
+
+pub fn wrap_unquantize(topology: &TopoJSON) -> TopoJSON {
+    match &topology.transform {
+        Some(transform) => Unquantize::call(topology, ScaleTransformer::new(transform)),
+        None => Unquantize::call(topology, IdentityTransformer::new()),
+    }
+}
+
+struct Unquantize<T>
+where
+    T: Transformer,
+{
+    transformer: T,
+}
+
+impl<T: Transformer> Unquantize<T> {
+    fn call(topology: &TopoJSON, transformer: T) -> TopoJSON {
+        Unquantize { transformer }.unquantize(topology)
+    }
+
+    fn unquantize(mut self, topology: &TopoJSON) -> TopoJSON {
+        let objects = topology
+            .objects
+            .iter()
+            .map(|(key, input)| (key.to_string(), self.unquantize_geometry(input)))
+            .collect();
+        let arcs = topology
+            .arcs
+            .iter()
+            .map(|input| self.unquantize_arc(input))
+            .collect();
+        TopoJSON {
+            bbox: topology.bbox.clone(),
+            transform: None,
+            objects,
+            arcs,
+        }
+    }
+
+    fn unquantize_point(&mut self, point: &[f64; 2]) -> [f64; 2] {
+        self.transformer.call(point, 0)
+    }
+
+    fn unquantize_geometry(&mut self, input: &Geometry) -> Geometry {
+        match input {
+            Geometry::GeometryCollection {
+                geometries,
+                id,
+                properties,
+                bbox,
+            } => Geometry::GeometryCollection {
+                geometries: geometries
+                    .iter()
+                    .map(|geometry| self.unquantize_geometry(geometry))
+                    .collect(),
+                id: id.clone(),
+                properties: properties.clone(),
+                bbox: bbox.clone(),
+            },
+            Geometry::Point {
+                coordinates,
+                id,
+                properties,
+                bbox,
+            } => Geometry::Point {
+                coordinates: self.unquantize_point(coordinates),
+                id: id.clone(),
+                properties: properties.clone(),
+                bbox: bbox.clone(),
+            },
+            Geometry::MultiPoint {
+                coordinates,
+                id,
+                properties,
+                bbox,
+            } => Geometry::MultiPoint {
+                coordinates: coordinates
+                    .iter()
+                    .map(|point| self.unquantize_point(point))
+                    .collect(),
+                id: id.clone(),
+                properties: properties.clone(),
+                bbox: bbox.clone(),
+            },
+            _ => input.clone(),
+        }
+    }
+
+    fn unquantize_arc(&mut self, input: &[[f64; 2]]) -> Vec<[f64; 2]> {
+        input
+            .iter()
+            .enumerate()
+            .map(|(i, point)| self.transformer.call(point, i))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::quantize::wrap_quantize;
+    use crate::topojsons::Transform;
+
+    use super::*;
+
+    fn simple_topology() -> TopoJSON {
+        TopoJSON {
+            bbox: vec![10., 10., 12., 12.],
+            transform: Some(Transform {
+                scale: [2., 2.],
+                translate: [10., 10.],
+            }),
+            objects: HashMap::from_iter([
+                (
+                    "foo".to_string(),
+                    Geometry::LineString {
+                        arcs: vec![0],
+                        id: None,
+                        properties: None,
+                        bbox: None,
+                    },
+                ),
+                (
+                    "bar".to_string(),
+                    Geometry::MultiPoint {
+                        coordinates: vec![[0., 0.], [1., 1.]],
+                        id: None,
+                        properties: None,
+                        bbox: None,
+                    },
+                ),
+            ]),
+            arcs: vec![vec![[0., 0.], [1., 0.], [0., 1.]]],
+        }
+    }
+
+    #[test]
+    fn test_unquantize_1() {
+        let topology = wrap_unquantize(&simple_topology());
+        assert_eq!(topology.transform, None);
+        assert_eq!(topology.bbox, vec![10., 10., 12., 12.]);
+        assert_eq!(
+            topology.arcs,
+            vec![vec![[10., 10.], [12., 10.], [12., 12.]]]
+        );
+        assert_eq!(
+            topology.objects["bar"],
+            Geometry::MultiPoint {
+                coordinates: vec![[10., 10.], [12., 12.]],
+                id: None,
+                properties: None,
+                bbox: None,
+            }
+        );
+        assert_eq!(topology.objects["foo"], simple_topology().objects["foo"]);
+    }
+
+    #[test]
+    fn test_unquantize_2() {
+        let mut topology = simple_topology();
+        topology.transform = None;
+        assert_eq!(wrap_unquantize(&topology), topology);
+    }
+
+    #[test]
+    fn test_unquantize_3() {
+        let topology = simple_topology();
+        assert_eq!(
+            wrap_quantize(&wrap_unquantize(&topology), &2., false).unwrap(),
+            topology
+        );
+    }
+
+    #[test]
+    fn test_unquantize_4() {
+        let mut topology = simple_topology();
+        topology.transform = Some(Transform {
+            scale: [0.5, 0.25],
+            translate: [0., 0.],
+        });
+        topology.objects.clear();
+        assert_eq!(
+            serde_json::to_string(&wrap_unquantize(&topology)).unwrap(),
+            r#"{"bbox":[10.0,10.0,12.0,12.0],"transform":null,"objects":{},"arcs":[[[0,0],[0.5,0],[0.5,0.25]]]}"#
+        );
+    }
+}
@@ -1,4 +1,4 @@
-from collections.abc import Callable
+from collections.abc import Callable, Iterable
 from typing import Optional
 
 class TopoJSON:
@@ -13,7 +13,7 @@ class TopoJSON:
     bbox: list[float]
     transform: Optional[Transform]
     objects: dict[str, Geometry]
-    arcs: list[list[list[int]]]
+    arcs: list[list[list[float]]]
 
     def feature(self, key: str) -> GeoJSON:
         """
@@ -52,7 +52,9 @@ class TopoJSON:
           collection of features, each with a geometry collection.
         """
 
-    def merge(self, key: str) -> FeatureGeometryType_MultiLineString:
+    def merge(
+        self, key: str, spherical: bool = False
+    ) -> FeatureGeometryType_MultiLineString:
         """
         Returns the GeoJSON MultiPolygon geometry object representing the union
         for the specified array of Polygon and MultiPolygon objects in the
@@ -65,6 +67,14 @@ class TopoJSON:
         key : str
             Key to access the object by doing `topology.objects[key]`. The
             object must be a `Geometry_GeometryCollection`.
+        spherical : bool
+            When `True`, exterior rings and holes are identified by comparing
+            the ring areas on the sphere (spherical excess, as d3-geo's
+            `geoArea`) instead of in the plane. Use it for longitude and
+            latitude topologies, notably for rings crossing the antimeridian.
+            Unlike `geoArea`, the smallest of the two areas delimited by a ring
+            is used, whatever its winding order. Planar areas are used by
+            default.
 
         Returns
         -------
@@ -79,10 +89,130 @@ class TopoJSON:
             Selected object is not a `Geometry_GeometryCollection`.
         """
 
+    def merge_arcs(
+        self, key: str, spherical: bool = False, into: Optional[str] = None
+    ) -> Geometry_MultiPolygon:
+        """
+        Equivalent to `merge`, but returns a TopoJSON MultiPolygon geometry
+        object which references the arcs of the topology instead of a GeoJSON
+        geometry object with coordinates.
+
+        Parameters
+        ----------
+        key : str
+            Key to access the object by doing `topology.objects[key]`. The
+            object must be a `Geometry_GeometryCollection`.
+        spherical : bool
+            Computes ring areas on the sphere, see `merge`.
+        into : Optional[str]
+            When specified, the returned geometry is also inserted in the
+            topology as `topology.objects[into]`, replacing any existing
+            object with this key. The new object shares the arcs of the
+            topology.
+
+        Returns
+        -------
+        Geometry_MultiPolygon
+            TopoJSON MultiPolygon geometry object
+
+        Raises
+        ------
+        KeyError
+            When `key` is not found in `objects`
+        TypeError
+            Selected object is not a `Geometry_GeometryCollection`.
+        """
+
+    def dissolve(
+        self,
+        key: str,
+        by: str | GeoVar,
+        aggregate: Optional[dict[str, str]] = None,
+        spherical: bool = False,
+    ) -> GeoJSON_FeatureCollection:
+        """
+        Returns a GeoJSON FeatureCollection with one feature per group of
+        geometries sharing the same key. The Polygon and MultiPolygon geometries
+        of each group are merged as with `merge`, so that interior borders shared
+        by adjacent polygons of the same group are removed. For example, states
+        can be derived from counties with `by=(var()["id"].int() / 1000).int()`.
+
+        The key of each group is stored as the feature id and in its properties,
+        under the property name when `by` is a string or under `"key"` when `by`
+        is a `GeoVar`.
+
+        Parameters
+        ----------
+        key : str
+            Key to access the object by doing `topology.objects[key]`. The
+            object must be a `Geometry_GeometryCollection`.
+        by : str | GeoVar
+            Name of the property used as key or expression evaluated on each
+            geometry.
+        aggregate : Optional[dict[str, str]]
+            Properties to aggregate for each group, mapped to the aggregation to
+            apply on their non-null values: `"sum"`, `"first"` or `"count"`.
+        spherical : bool
+            Computes ring areas on the sphere, see `merge`.
+
+        Returns
+        -------
+        GeoJSON_FeatureCollection
+            GeoJSON FeatureCollection with a MultiPolygon feature per group
+
+        Raises
+        ------
+        KeyError
+            When `key` is not found in `objects`
+        TypeError
+            Selected object is not a `Geometry_GeometryCollection` or a summed
+            property is not numeric.
+        ValueError
+            Unknown aggregation, or aggregation of the property storing the
+            key.
+        """
+
+    def filter(
+        self, key: str, predicate: GeoVar, into: Optional[str] = None
+    ) -> Optional[TopoJSON]:
+        """
+        Keeps the geometries of a collection for which the predicate is true,
+        such as `var()["id"].startswith("06")`. All the variables of the
+        predicate are bound to the evaluated geometry.
+
+        Parameters
+        ----------
+        key : str
+            Key to access the object by doing `topology.objects[key]`. The
+            object must be a `Geometry_GeometryCollection`.
+        predicate : GeoVar
+            Expression evaluated on each geometry
+        into : Optional[str]
+            When specified, the kept geometries are inserted in the topology as
+            `topology.objects[into]`, replacing any existing object with this
+            key, and nothing is returned.
+
+        Returns
+        -------
+        Optional[TopoJSON]
+            When `into` is not specified, a copy of the topology where
+            `objects[key]` is the `Geometry_GeometryCollection` of the kept
+            geometries. The other objects are kept, while the arcs no longer
+            referenced by any object are dropped. The collection keeps its id
+            and properties, and its bbox when no geometry is dropped.
+
+        Raises
+        ------
+        KeyError
+            When `key` is not found in `objects`
+        TypeError
+            Selected object is not a `Geometry_GeometryCollection`.
+        """
+
     def mesh(
         self,
         key: Optional[str] = None,
-        filter: Optional[GeoVar] = None,
+        filter: Optional[GeoVar | Callable[[Geometry, Geometry], bool]] = None,
     ) -> FeatureGeometryType_MultiLineString:
         """
         Returns the GeoJSON MultiLineString geometry object representing the
@@ -97,7 +227,7 @@ class TopoJSON:
         ----------
         key : str
             Key to access the object by doing `topology.objects[key]`.
-        filter : Optional[GeoVar]
+        filter : Optional[GeoVar | Callable[[Geometry, Geometry], bool]]
             The filter variable is transformed into a function and it is called
             once for each candidate arc and takes two arguments, a and b, two
             geometry objects that share that arc. Each arc is only included in the
@@ -106,6 +236,13 @@ class TopoJSON:
             candidate arc is their boundary. If an arc is only used by a single
             geometry then a and b are identical.
 
+            The filter may also be any Python callable `(a, b) -> bool` taking
+            two geometry objects, which allows conditions that `GeoVar` cannot
+            express. Each geometry is converted once and the distinct pairs of
+            geometries are evaluated in batches, with a single call into Python
+            per batch; the callable is still called once per distinct pair, so a
+            `GeoVar` remains faster as it is evaluated without calling Python.
+
         Returns
         -------
         FeatureGeometryType_MultiLineString
@@ -117,7 +254,74 @@ class TopoJSON:
             When `key` is not found in `objects`
         """
 
-    def compute_bbox(self) -> list[float]:
+    def mesh_arcs(
+        self,
+        key: Optional[str] = None,
+        filter: Optional[GeoVar | Callable[[Geometry, Geometry], bool]] = None,
+        into: Optional[str] = None,
+    ) -> Geometry_MultiLineString:
+        """
+        Equivalent to `mesh`, but returns a TopoJSON MultiLineString geometry
+        object which references the arcs of the topology instead of a GeoJSON
+        geometry object with coordinates.
+
+        Parameters
+        ----------
+        key : str
+            Key to access the object by doing `topology.objects[key]`.
+        filter : Optional[GeoVar | Callable[[Geometry, Geometry], bool]]
+            Filter applied on the candidate arcs, see `mesh`.
+        into : Optional[str]
+            When specified, the returned geometry is also inserted in the
+            topology as `topology.objects[into]`, replacing any existing
+            object with this key. The new object shares the arcs of the
+            topology.
+
+        Returns
+        -------
+        Geometry_MultiLineString
+            TopoJSON MultiLineString geometry object
+
+        Raises
+        ------
+        KeyError
+            When `key` is not found in `objects`
+        """
+
+    def borders(
+        self,
+        key: str,
+        spherical: bool = False,
+    ) -> GeoJSON_FeatureCollection:
+        """
+        Returns the borders between the geometries of an object, e.g. to label
+        borders or compute border statistics. Each feature stitches the arcs
+        separating a pair of geometries, or the exterior arcs of a geometry,
+        with the properties `left_id` and `right_id`, the ids of the geometries
+        on the left and on the right of the line (their index without id, `None`
+        on the right for the exterior), and `length`, the length of the border.
+        Arcs used twice by the same geometry are not borders.
+
+        Parameters
+        ----------
+        key : str
+            Key to access the object by doing `topology.objects[key]`.
+        spherical : bool
+            Measures the borders on the sphere, in metres, for coordinates in
+            longitudes and latitudes.
+
+        Returns
+        -------
+        GeoJSON_FeatureCollection
+            Border lines with their left and right geometries
+
+        Raises
+        ------
+        KeyError
+            When `key` is not found in `objects`
+        """
+
+    def compute_bbox(self, spherical: bool = False) -> list[float]:
         """
         Returns the computed bounding box of the specified topology $[x_0, y_0,
         x_1, y_1]$ where $x_0$ is the minimum x-value, $y_0$ is the minimum
@@ -127,13 +331,178 @@ class TopoJSON:
 
         (This method ignores the existing topology.bbox, if any.)
 
+        Parameters
+        ----------
+        spherical : bool
+            Finds the smallest longitude interval covering the arcs and points,
+            for coordinates in longitudes and latitudes: the returned bounding
+            box has $x_0 > x_1$ when it crosses the antimeridian, as specified
+            by RFC 7946.
+
         Returns
         -------
         list[float]
             Computed bounding box
         """
 
-    def neighbors(self, keys: list[str]) -> list[list[int]]:
+    def object_bbox(self, key: str, spherical: bool = False) -> list[float]:
+        """
+        Returns the computed bounding box $[x_0, y_0, x_1, y_1]$ of an object,
+        decoding only the arcs it references. If the object has no points and
+        no arcs, the returned bounding box is
+        $[\\infty, \\infty, -\\infty, -\\infty]$.
+
+        Parameters
+        ----------
+        key : str
+            Key to access the object by doing `topology.objects[key]`.
+        spherical : bool
+            Finds the smallest longitude interval covering the object, see
+            `compute_bbox`.
+
+        Returns
+        -------
+        list[float]
+            Computed bounding box
+
+        Raises
+        ------
+        KeyError
+            When `key` is not found in `objects`
+        """
+
+    def compute_geometry_bboxes(
+        self,
+        key: str,
+        spherical: bool = False,
+    ) -> None:
+        """
+        Fills the `bbox` of an object and of all its geometries, so that the
+        features returned by `feature` carry their bounding box. Only the arcs
+        referenced by the object are decoded, once each. Geometries without
+        points and arcs get no bounding box.
+
+        Parameters
+        ----------
+        key : str
+            Key to access the object by doing `topology.objects[key]`.
+        spherical : bool
+            Finds the smallest longitude interval covering each geometry, see
+            `compute_bbox`.
+
+        Raises
+        ------
+        KeyError
+            When `key` is not found in `objects`
+        """
+
+    def index(self, key: str) -> SpatialIndex:
+        """
+        Builds a spatial index over the polygons of the geometries of an object
+        to locate the geometry containing a position. Geometries without
+        polygons are never located.
+
+        Parameters
+        ----------
+        key : str
+            Key to access the object by doing `topology.objects[key]`. The
+            geometries of a `Geometry_GeometryCollection` are indexed one by
+            one, any other object is indexed as a single geometry.
+
+        Returns
+        -------
+        SpatialIndex
+            Reusable and picklable spatial index
+
+        Raises
+        ------
+        KeyError
+            When `key` is not found in `objects`
+        """
+
+    def measure(self, key: str, spherical: bool = False) -> list[Measure]:
+        """
+        Measures the area and perimeter of the polygons and the length of the
+        lines of each geometry of an object, directly from the arcs. The length
+        of each arc is computed once, even when it is shared by several
+        geometries.
+
+        Parameters
+        ----------
+        key : str
+            Key to access the object by doing `topology.objects[key]`. The
+            geometries of a `Geometry_GeometryCollection` are measured one by
+            one, any other object is measured as a single geometry.
+        spherical : bool
+            Measures lengths in metres and areas in square metres on the
+            sphere, for longitude and latitude topologies. The area of a ring
+            is the smallest of the two areas it delimits, so a ring enclosing
+            more than a hemisphere is measured as its complement. Planar
+            measures in the units of the coordinates are used by default.
+
+        Returns
+        -------
+        list[Measure]
+            Measures of each geometry
+
+        Raises
+        ------
+        KeyError
+            When `key` is not found in `objects`
+        """
+
+    def nearest(
+        self,
+        key: str,
+        x: float,
+        y: float,
+        k: int = 1,
+        spherical: bool = False,
+    ) -> list[tuple[int, Optional[str], float]]:
+        """
+        Finds the `k` geometries of an object nearest to a position. The
+        distance is measured to the boundary of polygons, to lines and to
+        points, and each shared arc is only measured once. The segment index is
+        built on every call.
+
+        Parameters
+        ----------
+        key : str
+            Key to access the object by doing `topology.objects[key]`. The
+            geometries of a `Geometry_GeometryCollection` are searched one by
+            one, any other object is searched as a single geometry.
+        x : float
+            Horizontal coordinate (or longitude) of the position
+        y : float
+            Vertical coordinate (or latitude) of the position
+        k : int
+            Number of geometries to return
+        spherical : bool
+            Measures the distance along the sphere, in metres, instead of in
+            planar coordinates. Segments are great-circle arcs and the
+            distance is measured across the antimeridian.
+
+        Returns
+        -------
+        list[tuple[int, Optional[str], float]]
+            Index, id and distance of the nearest geometries, sorted by
+            distance
+
+        Raises
+        ------
+        KeyError
+            When `key` is not found in `objects`
+        ValueError
+            When `k` is zero
+        """
+
+    def neighbors(
+        self,
+        keys: list[str],
+        mode: str = "rook",
+        weights: Optional[str] = None,
+        spherical: bool = False,
+    ) -> list[list[int]] | WeightedNeighbors:
         """
         Returns an array representing the set of neighboring objects for each
         object in the specified objects array. The returned array has the same
@@ -150,24 +519,175 @@ class TopoJSON:
         keys : list[str]
             List of keys for accessing the object by doing
             `topology.objects[key]`.
+        mode : str
+            `"rook"` for objects sharing an arc or `"queen"` for objects
+            sharing an arc or touching at a node of the topology, i.e. an
+            endpoint of an arc. Points have no neighbors in `"rook"` mode and
+            are neighbors of the objects touching their position in `"queen"`
+            mode.
+        weights : Optional[str]
+            When specified, `"length"` or `"count"` to weight each pair of
+            neighbors with the length of their shared border or with the number
+            of arcs they share. Only the shared arcs are decoded. Neighbors
+            only touching at a node in `"queen"` mode have a weight of 0.
+        spherical : bool
+            Measures the shared borders on the sphere, in metres, for
+            coordinates in longitudes and latitudes.
 
         Returns
         -------
-        list[list[int]]
-            Neighboring objects
+        list[list[int]] | WeightedNeighbors
+            Neighboring objects, with their weights when `weights` is specified
 
         Raises
         ------
         KeyError
             When `key` is not found in `objects`
+        ValueError
+            Unknown mode or weights.
         """
 
-    def quantize(self, transform: float) -> TopoJSON:
+    def neighbors_between(
+        self,
+        key_a: str,
+        key_b: str,
+        ids: bool = False,
+    ) -> list[list[int | str]]:
+        """
+        Returns, for each geometry of the object `key_a`, the geometries of the
+        object `key_b` sharing an arc with it, e.g. the counties touching a
+        river layer. The geometries of a `Geometry_GeometryCollection` are
+        related one by one, any other object is related as a single geometry.
+        Each array of neighbors is sorted by index in ascending order.
+
+        Parameters
+        ----------
+        key_a : str
+            Key to access the first object by doing `topology.objects[key_a]`.
+        key_b : str
+            Key to access the second object by doing `topology.objects[key_b]`.
+        ids : bool
+            Identifies the geometries of `key_b` by their id instead of their
+            index. Geometries without id keep their index.
+
+        Returns
+        -------
+        list[list[int | str]]
+            Neighbors in `key_b` of each geometry of `key_a`
+
+        Raises
+        ------
+        KeyError
+            When `key_a` or `key_b` is not found in `objects`
+        """
+
+    def adjacency(
+        self,
+        key: str,
+        format: str = "csr",
+        mode: str = "rook",
+        spherical: bool = False,
+    ) -> (
+        tuple[list[int], list[int], list[float]]
+        | list[tuple[str | int, str | int, float]]
+        | dict[str | int, dict[str | int, float]]
+    ):
+        """
+        Returns the adjacency of the geometries of a collection, weighted by the
+        length of their shared borders. Geometries are identified by their id
+        or, without id, by their index in the collection.
+
+        Parameters
+        ----------
+        key : str
+            Key to access the object by doing `topology.objects[key]`. The
+            object must be a `Geometry_GeometryCollection`.
+        format : str
+            `"csr"` for the arrays `(indptr, indices, data)` of a sparse matrix,
+            e.g. `scipy.sparse.csr_array((data, indices, indptr))`, `"edges"`
+            for a list of `(id_a, id_b, shared_length)` tuples listing each pair
+            of neighbors once, or `"dict"` for `{id_a: {id_b: shared_length}}`.
+        mode : str
+            `"rook"` or `"queen"` adjacency, see `neighbors`.
+        spherical : bool
+            Measures the shared borders on the sphere, in metres, for
+            coordinates in longitudes and latitudes.
+
+        Returns
+        -------
+        tuple[list[int], list[int], list[float]] | list[tuple[str | int, str | int, float]] | dict[str | int, dict[str | int, float]]
+            Adjacency in the requested format
+
+        Raises
+        ------
+        KeyError
+            When `key` is not found in `objects`
+        TypeError
+            Selected object is not a `Geometry_GeometryCollection`.
+        ValueError
+            Unknown format or mode.
+        """
+
+    def color(
+        self,
+        key: str,
+        max_colors: int = 4,
+        method: str = "dsatur",
+        property: Optional[str] = None,
+    ) -> list[int]:
+        """
+        Colors the geometries of a collection so that geometries sharing an arc
+        have different colors, e.g. for choropleth styling. The coloring is
+        deterministic: ties are broken by the order of the geometries.
+
+        Parameters
+        ----------
+        key : str
+            Key to access the object by doing `topology.objects[key]`. The
+            object must be a `Geometry_GeometryCollection`.
+        max_colors : int
+            Maximum number of colors, between 1 and 64. Four colors suffice for
+            most maps with the `"dsatur"` method.
+        method : str
+            `"greedy"` colors the geometries in order with the first color not
+            used by their neighbors, `"dsatur"` colors first the geometries
+            whose neighbors use the most distinct colors.
+        property : Optional[str]
+            When specified, writes the color index into the properties of each
+            geometry under this name.
+
+        Returns
+        -------
+        list[int]
+            Color index of each geometry, from `0` to `max_colors - 1`
+
+        Raises
+        ------
+        KeyError
+            When `key` is not found in `objects`
+        TypeError
+            Selected object is not a `Geometry_GeometryCollection`.
+        ValueError
+            Unknown method or the geometries cannot be colored with
+            `max_colors` colors.
+        """
+
+    def quantize(
+        self,
+        transform: Optional[float] = None,
+        max_error: Optional[float] = None,
+        spherical: bool = False,
+    ) -> TopoJSON:
         """
         Returns a shallow copy of the specified topology with quantized and
         delta-encoded arcs according to the specified transform object. If the
-        topology is already quantized, an error is thrown. See also
-        topoquantize.
+        topology is already quantized, its arcs and points are first decoded
+        with the existing transform and then encoded again with the new one,
+        which allows to reduce the precision of a topology without its original
+        coordinates. Consecutive duplicate positions are collapsed, so a
+        degenerate arc keeps its first position followed by a zero delta
+        `[0, 0]`, as in topojson-client; arc indexes are left unchanged so that
+        shared arcs remain shared. See also topoquantize.
 
         The corresponding transform object is first computed using the bounding
         box of the topology. The quantization number `transform` must be a
@@ -177,21 +697,114 @@ class TopoJSON:
         1e6. If the topology does not already have a topology.bbox, one is
         computed using topojson.bbox.
 
+        Instead of a quantization number, a maximum error can be specified: a
+        quantization number whose maximum displacement (see
+        `TopoJSON.quantization_error`) does not exceed `max_error` is then
+        used. It is searched by bisection below the first power of two that
+        satisfies `max_error`; since the displacement does not always decrease
+        as the quantization number grows, it is not guaranteed to be the
+        smallest one.
 
         Parameters
         ----------
-        transform : float
+        transform : Optional[float]
             The quantization number `transform`.
+        max_error : Optional[float]
+            The maximum displacement of positions allowed, in the units of the
+            input coordinates.
+        spherical : bool
+            Uses the antimeridian-aware bounding box of the topology, see
+            `compute_bbox`, instead of topology.bbox. When it crosses the
+            antimeridian, the longitudes smaller than $x_0$ are shifted by
+            360° so that they are contiguous: decoded longitudes past the
+            antimeridian then exceed 180°.
 
         Returns
         -------
         TopoJSON
             Shallow copy with quantized and delta-encoded arcs.
 
+        Raises
+        ------
+        TypeError
+            If both or none of `transform` and `max_error` are specified.
+        RuntimeError
+            If transform is smaller than 2, if `max_error` is negative or if
+            no quantization number satisfies `max_error`.
+        """
+
+    def quantization_error(
+        self,
+        transform: float,
+        spherical: bool = False,
+    ) -> QuantizationReport:
+        """
+        Reports the displacement of positions that quantizing the topology
+        with the quantization number `transform` would cause, without
+        quantizing it. Arcs and points are compared to their quantized and
+        decoded positions.
+
+        Parameters
+        ----------
+        transform : float
+            The quantization number `transform`.
+        spherical : bool
+            Uses the antimeridian-aware bounding box, see `quantize`.
+
+        Returns
+        -------
+        QuantizationReport
+            Maximum and mean displacements and number of collapsed arcs.
+
+        Raises
+        ------
+        RuntimeError
+            If transform is smaller than 2.
+        """
+
+    def simplify_dp(self, tolerance: float) -> TopoJSON:
+        """
+        Returns a shallow copy of the topology where each arc is simplified
+        independently with the Douglas-Peucker algorithm. Since arcs are shared
+        between objects, borders shared by adjacent geometries remain
+        identical. Arc endpoints are always kept and rings of polygons never
+        collapse below 4 points.
+
+        Quantized arcs are simplified using their decoded coordinates and the
+        kept points are delta-encoded again.
+
+        Parameters
+        ----------
+        tolerance : float
+            Distance tolerance in map units (the units of decoded coordinates).
+            A point is removed if its distance to the simplified line is not
+            greater than the tolerance.
+
+        Returns
+        -------
+        TopoJSON
+            Shallow copy with simplified arcs.
+
         Raises
         ------
         RuntimeError
-            If topology is already quantized or transform is smaller than 2.
+            If tolerance is negative or not a number.
+        """
+
+    def unquantize(self) -> TopoJSON:
+        """
+        Returns a shallow copy of the specified topology with absolute
+        coordinates. Delta-encoded arcs are decoded with the transform of the
+        topology, as well as the coordinates of point geometries. The
+        returned topology has no transform: its coordinates can be edited or
+        reprojected and quantized again with `TopoJSON.quantize`.
+
+        If the topology is not quantized, an identical copy is returned.
+
+        Returns
+        -------
+        TopoJSON
+            Shallow copy with absolute coordinates.
         """
 
     def write(self, file: str):
@@ -220,6 +833,139 @@ class Transform:
     scale: list[float]
     translate: list[float]
 
+class QuantizationReport:
+    """
+    Displacement of positions caused by a quantization.
+
+    Attributes
+    ----------
+    transform : Transform
+        Transform used for the quantization
+    max_error : float
+        Maximum displacement, in the units of the input coordinates
+    mean_error : float
+        Mean displacement, in the units of the input coordinates
+    max_error_meters : Optional[float]
+        Maximum displacement in metres, when coordinates are longitudes and
+        latitudes
+    mean_error_meters : Optional[float]
+        Mean displacement in metres, when coordinates are longitudes and
+        latitudes
+    collapsed_arcs : int
+        Number of arcs whose positions would all be merged into one
+    """
+
+    transform: Transform
+    max_error: float
+    mean_error: float
+    max_error_meters: Optional[float]
+    mean_error_meters: Optional[float]
+    collapsed_arcs: int
+
+class Measure:
+    """
+    Measures of a geometry. Holes are subtracted from the area and included in
+    the perimeter.
+
+    Attributes
+    ----------
+    id : Optional[str]
+        Identifier of the geometry
+    area : float
+        Area of the polygons
+    perimeter : float
+        Length of the rings of the polygons
+    length : float
+        Length of the lines
+    """
+
+    id: Optional[str]
+    area: float
+    perimeter: float
+    length: float
+
+class WeightedNeighbors:
+    """
+    Neighbors of each object with the weight of each pair of neighbors.
+
+    Attributes
+    ----------
+    neighbors : list[list[int]]
+        Sorted indexes of the neighbors of each object
+    weights : list[list[float]]
+        Weight of each pair of neighbors, at the same positions as
+        `neighbors`: the length of the shared border or the number of shared
+        arcs
+    lengths : list[list[float]]
+        Length of the border shared by each pair of neighbors, in the units of
+        the decoded coordinates or in metres when spherical
+    counts : list[list[int]]
+        Number of arcs shared by each pair of neighbors, `0` for objects only
+        touching at a node
+    """
+
+    neighbors: list[list[int]]
+    weights: list[list[float]]
+    lengths: list[list[float]]
+    counts: list[list[int]]
+
+class SpatialIndex:
+    """
+    Spatial index over the polygons of the geometries of an object, built by
+    `TopoJSON.index`. The index holds an R-tree of the bounding boxes of the
+    geometries and their decoded polygons, so that it can be reused across
+    queries and pickled independently of the topology.
+    """
+
+    def locate(self, x: float, y: float) -> Optional[tuple[int, Optional[str]]]:
+        """
+        Returns the geometry containing the position `(x, y)`, testing the
+        rings of the candidate geometries with the even-odd rule. When several
+        geometries contain the position, the one with the smallest index is
+        returned. Positions on a boundary may be located in either geometry.
+
+        Parameters
+        ----------
+        x : float
+            X-value of the position, e.g. the longitude
+        y : float
+            Y-value of the position, e.g. the latitude
+
+        Returns
+        -------
+        Optional[tuple[int, Optional[str]]]
+            Index and id of the containing geometry, `None` if no geometry
+            contains the position
+        """
+
+    def locate_many(
+        self, points: list[tuple[float, float]]
+    ) -> list[Optional[tuple[int, Optional[str]]]]:
+        """
+        Equivalent to `locate` for each position of `points`.
+
+        Parameters
+        ----------
+        points : list[tuple[float, float]]
+            List of positions `(x, y)`
+
+        Returns
+        -------
+        list[Optional[tuple[int, Optional[str]]]]
+            Index and id of the containing geometry of each position
+        """
+
+    def __len__(self) -> int:
+        """
+        Returns the number of geometries of the index, including those without
+        polygons which are never located.
+
+        Returns
+        -------
+        int
+            Number of geometries
+        """
+
 type Geometry = (
     Geometry_Point
     | Geometry_MultiPoint
@@ -545,19 +1291,39 @@ class GeoVar:
     """
     Object used for `mesh` filter argument as a filter condition on geometry
     objects.
+
+    In a filter, `var(0)` is bound to the first geometry `a` and `var(1)` to
+    the second geometry `b`. Otherwise, the variable appearing first in the
+    expression, read from left to right, is bound to `a` and every other
+    variable to `b`, whatever the order in which they were created: `a < b`
+    and `b > a` are the same filter. Operands may also be literals such as
+    `int`, `float`, `str` or `bool`.
     """
 
-    def __getitem__(self, attribute: str) -> GeoVar:
+    def __getitem__(self, attribute: str | int | slice) -> GeoVar:
         """
-        Indicates to access the specified attribute of the geometry.
+        Indicates to access the specified attribute of the geometry, or the
+        specified field of the current value.
+
+        The attributes id, properties and bbox of the geometry are available
+        through `GeoVar`. The properties are parsed as JSON, so that their
+        fields can be accessed at any depth, e.g.
+        `a["properties"]["STATE"]`. Strings, numbers, booleans and nulls are
+        compared natively, without casting them with `int()` or `float()`.
 
-        Currently, only id, properties and bbox attributes are available
-        through `GeoVar`.
+        Values derived from the shape of the geometry are also available:
+        `type` (e.g. `"Polygon"`), `arc_count` (number of arc references),
+        `ring_count` (number of polygon rings, holes included) and `area`
+        (planar area of the polygons computed from the decoded arcs, holes
+        excluded). They are computed once per geometry in a `mesh` filter.
+
+        Strings and lists can also be indexed and sliced as in Python, e.g.
+        `a["id"][:2]` for the first two characters of the id.
 
         Parameters
         ----------
-        attribute : str
-            Geometry attribute
+        attribute : str | int | slice
+            Geometry attribute, field name, index or slice
 
         Returns
         -------
@@ -595,9 +1361,87 @@ class GeoVar:
             New object with updated internal state
         """
 
+    def str(self) -> GeoVar:
+        """
+        Indicates to cast the current value as a string.
+
+        Returns
+        -------
+        GeoVar
+            New object with updated internal state
+        """
+
+    def lower(self) -> GeoVar:
+        """
+        Indicates to convert the current string to lowercase.
+
+        Returns
+        -------
+        GeoVar
+            New object with updated internal state
+        """
+
+    def upper(self) -> GeoVar:
+        """
+        Indicates to convert the current string to uppercase.
+
+        Returns
+        -------
+        GeoVar
+            New object with updated internal state
+        """
+
+    def startswith(self, prefix: str) -> GeoVar:
+        """
+        Indicates to check whether the current string starts with `prefix`.
+
+        Parameters
+        ----------
+        prefix : str
+            Prefix
+
+        Returns
+        -------
+        GeoVar
+            New object with updated internal state
+        """
+
+    def endswith(self, suffix: str) -> GeoVar:
+        """
+        Indicates to check whether the current string ends with `suffix`.
+
+        Parameters
+        ----------
+        suffix : str
+            Suffix
+
+        Returns
+        -------
+        GeoVar
+            New object with updated internal state
+        """
+
+    def isin(self, values: Iterable[str | int | float | bool | None]) -> GeoVar:
+        """
+        Indicates to check whether the current value is one of the specified
+        literal values, as `value in values` in Python (the `in` operator
+        itself cannot be overloaded).
+
+        Parameters
+        ----------
+        values : Iterable[str | int | float | bool | None]
+            Literal values
+
+        Returns
+        -------
+        GeoVar
+            New object with updated internal state
+        """
+
     def __add__(self, other: object) -> GeoVar:
         """
-        Indicates to add the current value with another value.
+        Indicates to add the current value with another value. When `other`
+        is a string, the current string is concatenated with it.
 
         Parameters
         ----------
@@ -642,7 +1486,157 @@ class GeoVar:
 
     def __truediv__(self, other: object) -> GeoVar:
         """
-        Indicates to divide the current value with another value.
+        Indicates to divide the current value with another value.
+
+        Parameters
+        ----------
+        other : object
+            Another value
+
+        Returns
+        -------
+        GeoVar
+            New object with updated internal state
+        """
+
+    def __mod__(self, other: object) -> GeoVar:
+        """
+        Indicates to compute the remainder of the floored division of the current
+        value by another value.
+
+        Parameters
+        ----------
+        other : object
+            Another value, either a `GeoVar` or a literal
+
+        Returns
+        -------
+        GeoVar
+            New object with updated internal state
+        """
+
+    def __floordiv__(self, other: object) -> GeoVar:
+        """
+        Indicates to compute the floored division of the current value by another
+        value.
+
+        Parameters
+        ----------
+        other : object
+            Another value, either a `GeoVar` or a literal
+
+        Returns
+        -------
+        GeoVar
+            New object with updated internal state
+        """
+
+    def __abs__(self) -> GeoVar:
+        """
+        Indicates to take the absolute value of the current value.
+
+        Returns
+        -------
+        GeoVar
+            New object with updated internal state
+        """
+
+    def __eq__(self, other: object) -> GeoVar:  # type: ignore
+        """
+        Indicates to check the current value is equal with another value.
+
+        Parameters
+        ----------
+        other : object
+            Another value
+
+        Returns
+        -------
+        GeoVar
+            New object with updated internal state
+        """
+
+    def __ne__(self, other: object) -> GeoVar:  # type: ignore
+        """
+        Indicates to check the current value is not equal with another value.
+
+        Parameters
+        ----------
+        other : object
+            Another value
+
+        Returns
+        -------
+        GeoVar
+            New object with updated internal state
+        """
+
+    def __lt__(self, other: object) -> GeoVar:
+        """
+        Indicates to check the current value is less than another value.
+
+        Parameters
+        ----------
+        other : object
+            Another value, either a `GeoVar` or a literal
+
+        Returns
+        -------
+        GeoVar
+            New object with updated internal state
+        """
+
+    def __le__(self, other: object) -> GeoVar:
+        """
+        Indicates to check the current value is less than or equal to another
+        value.
+
+        Parameters
+        ----------
+        other : object
+            Another value, either a `GeoVar` or a literal
+
+        Returns
+        -------
+        GeoVar
+            New object with updated internal state
+        """
+
+    def __gt__(self, other: object) -> GeoVar:
+        """
+        Indicates to check the current value is greater than another value.
+
+        Parameters
+        ----------
+        other : object
+            Another value, either a `GeoVar` or a literal
+
+        Returns
+        -------
+        GeoVar
+            New object with updated internal state
+        """
+
+    def __ge__(self, other: object) -> GeoVar:
+        """
+        Indicates to check the current value is greater than or equal to another
+        value.
+
+        Parameters
+        ----------
+        other : object
+            Another value, either a `GeoVar` or a literal
+
+        Returns
+        -------
+        GeoVar
+            New object with updated internal state
+        """
+
+    def __and__(self, other: object) -> GeoVar:
+        """
+        Indicates to associate the current value to a boolean (and respectively the
+        same for other value) and to apply a 'and' operator between the two booleans.
 
         Parameters
         ----------
@@ -655,9 +1649,10 @@ class GeoVar:
             New object with updated internal state
         """
 
-    def __eq__(self, other: object) -> GeoVar:  # type: ignore
+    def __or__(self, other: object) -> GeoVar:
         """
-        Indicates to check the current value is equal with another value.
+        Indicates to associate the current value to a boolean (and respectively the
+        same for other value) and to apply a 'or' operator between the two booleans.
 
         Parameters
         ----------
@@ -670,14 +1665,9 @@ class GeoVar:
             New object with updated internal state
         """
 
-    def __ne__(self, other: object) -> GeoVar:  # type: ignore
+    def __invert__(self) -> GeoVar:
         """
-        Indicates to check the current value is not equal with another value.
-
-        Parameters
-        ----------
-        other : object
-            Another value
+        Indicates to associate the current value to a boolean and to negate it.
 
         Returns
         -------
@@ -685,15 +1675,14 @@ class GeoVar:
             New object with updated internal state
         """
 
-    def __and__(self, other: object) -> GeoVar:
+    def min(self, other: object) -> GeoVar:
         """
-        Indicates to associate the current value to a boolean (and respectively the
-        same for other value) and to apply a 'and' operator between the two booleans.
+        Indicates to take the minimum between the current value and another value.
 
         Parameters
         ----------
-        other : GeoVar
-            Another value
+        other : object
+            Another value, either a `GeoVar` or a literal
 
         Returns
         -------
@@ -701,15 +1690,14 @@ class GeoVar:
             New object with updated internal state
         """
 
-    def __or__(self, other: object) -> GeoVar:
+    def max(self, other: object) -> GeoVar:
         """
-        Indicates to associate the current value to a boolean (and respectively the
-        same for other value) and to apply a 'or' operator between the two booleans.
+        Indicates to take the maximum between the current value and another value.
 
         Parameters
         ----------
         other : object
-            Another value
+            Another value, either a `GeoVar` or a literal
 
         Returns
         -------
@@ -749,14 +1737,26 @@ def read(file: str) -> TopoJSON:
         Unable to deserialize the file.
     """
 
-def var() -> GeoVar:
+def var(position: Optional[int] = None) -> GeoVar:
     """
     Creates a GeoVar used in `mesh` filter argument.
 
+    Parameters
+    ----------
+    position : Optional[int]
+        Binds the variable to the first geometry with `0` or to the second one
+        with `1`. By default, the binding depends on the position of the
+        variable in the expression, see `GeoVar`.
+
     Returns
     -------
     GeoVar
         GeoVar object
+
+    Raises
+    ------
+    ValueError
+        When `position` is neither `0` nor `1`
     """
 
 def feature(topology: TopoJSON, o: Geometry) -> GeoJSON:
@@ -793,7 +1793,9 @@ def feature(topology: TopoJSON, o: Geometry) -> GeoJSON:
       collection of features, each with a geometry collection.
     """
 
-def merge(topology: TopoJSON, o: Geometry) -> FeatureGeometryType_MultiLineString:
+def merge(
+    topology: TopoJSON, o: Geometry, spherical: bool = False
+) -> FeatureGeometryType_MultiLineString:
     """
     Returns the GeoJSON MultiPolygon geometry object representing the union for
     the specified array of Polygon and MultiPolygon objects in the given
@@ -807,6 +1809,14 @@ def merge(topology: TopoJSON, o: Geometry) -> FeatureGeometryType_MultiLineStrin
         Topology object
     o : Geometry
         Geometry
+    spherical : bool
+        When `True`, exterior rings and holes are identified by comparing
+        the ring areas on the sphere (spherical excess, as d3-geo's
+        `geoArea`) instead of in the plane. Use it for longitude and
+        latitude topologies, notably for rings crossing the antimeridian.
+        Unlike `geoArea`, the smallest of the two areas delimited by a ring
+        is used, whatever its winding order. Planar areas are used by
+        default.
 
     Returns
     -------
@@ -821,10 +1831,103 @@ def merge(topology: TopoJSON, o: Geometry) -> FeatureGeometryType_MultiLineStrin
         Selected object is not a `Geometry_GeometryCollection`.
     """
 
+def merge_arcs(
+    topology: TopoJSON, objects: list[Geometry], spherical: bool = False
+) -> Geometry_MultiPolygon:
+    """
+    Equivalent to `merge`, but returns a TopoJSON MultiPolygon geometry object
+    which references the arcs of the topology instead of a GeoJSON geometry
+    object with coordinates.
+
+    Parameters
+    ----------
+    topology : TopoJSON
+        Topology object
+    objects : list[Geometry]
+        Geometries to merge
+    spherical : bool
+        Computes ring areas on the sphere, see `merge`.
+
+    Returns
+    -------
+    Geometry_MultiPolygon
+        TopoJSON MultiPolygon geometry object
+    """
+
+def dissolve(
+    topology: TopoJSON,
+    objects: list[Geometry],
+    by: str | GeoVar,
+    aggregate: Optional[dict[str, str]] = None,
+    spherical: bool = False,
+) -> GeoJSON_FeatureCollection:
+    """
+    Returns a GeoJSON FeatureCollection with one feature per group of
+    geometries sharing the same key. The Polygon and MultiPolygon geometries
+    of each group are merged as with `merge`, so that interior borders shared
+    by adjacent polygons of the same group are removed. For example, states
+    can be derived from counties with `by=(var()["id"].int() / 1000).int()`.
+
+    The key of each group is stored as the feature id and in its properties,
+    under the property name when `by` is a string or under `"key"` when `by`
+    is a `GeoVar`.
+
+    Parameters
+    ----------
+    topology : TopoJSON
+        Topology object
+    objects : list[Geometry]
+        Geometries to dissolve
+    by : str | GeoVar
+        Name of the property used as key or expression evaluated on each
+        geometry.
+    aggregate : Optional[dict[str, str]]
+        Properties to aggregate for each group, mapped to the aggregation to
+        apply on their non-null values: `"sum"`, `"first"` or `"count"`.
+    spherical : bool
+        Computes ring areas on the sphere, see `merge`.
+
+    Returns
+    -------
+    GeoJSON_FeatureCollection
+        GeoJSON FeatureCollection with a MultiPolygon feature per group
+
+    Raises
+    ------
+    TypeError
+        A summed property is not numeric.
+    ValueError
+        Unknown aggregation, or aggregation of the property storing the key.
+    """
+
+def filter(
+    topology: TopoJSON, objects: list[Geometry], predicate: GeoVar
+) -> Geometry_GeometryCollection:
+    """
+    Keeps the geometries for which the predicate is true, such as
+    `var()["id"].startswith("06")`. All the variables of the predicate are
+    bound to the evaluated geometry.
+
+    Parameters
+    ----------
+    topology : TopoJSON
+        Topology object
+    objects : list[Geometry]
+        Geometries to filter
+    predicate : GeoVar
+        Expression evaluated on each geometry
+
+    Returns
+    -------
+    Geometry_GeometryCollection
+        TopoJSON GeometryCollection of the kept geometries
+    """
+
 def mesh(
     topology: TopoJSON,
     object: Optional[Geometry] = None,
-    filter: Optional[GeoVar] = None,
+    filter: Optional[GeoVar | Callable[[Geometry, Geometry], bool]] = None,
+    compiled: bool = True,
 ) -> FeatureGeometryType_MultiLineString:
     """
     Returns the GeoJSON MultiLineString geometry object representing the
@@ -841,7 +1944,7 @@ def mesh(
         TopoJSON object containing the object to mesh
     object : Optional[Geometry]
         Specfied geometry to mesh
-    filter : Optional[GeoVar]
+    filter : Optional[GeoVar | Callable[[Geometry, Geometry], bool]]
         The filter variable is transformed into a function and it is called
         once for each candidate arc and takes two arguments, a and b, two
         geometry objects that share that arc. Each arc is only included in the
@@ -850,6 +1953,17 @@ def mesh(
         candidate arc is their boundary. If an arc is only used by a single
         geometry then a and b are identical.
 
+        The filter may also be any Python callable `(a, b) -> bool` taking two
+        geometry objects, which allows conditions that `GeoVar` cannot express.
+        Each geometry is converted once and the distinct pairs of geometries are
+        evaluated in batches, with a single call into Python per batch; the
+        callable is still called once per distinct pair, so a `GeoVar` remains
+        faster as it is evaluated without calling Python.
+    compiled : bool
+        A `GeoVar` filter is compiled once into a plan that memoizes the values
+        of each geometry. When `False`, it is interpreted again on each arc
+        instead, which is only useful to measure the speedup of the plan.
+
     Returns
     -------
     FeatureGeometryType_MultiLineString
@@ -861,7 +1975,62 @@ def mesh(
         When `key` is not found in `objects`
     """
 
-def bbox(topology: TopoJSON) -> list[float]:
+def mesh_arcs(
+    topology: TopoJSON,
+    object: Optional[Geometry] = None,
+    filter: Optional[GeoVar | Callable[[Geometry, Geometry], bool]] = None,
+) -> Geometry_MultiLineString:
+    """
+    Equivalent to `mesh`, but returns a TopoJSON MultiLineString geometry
+    object which references the arcs of the topology instead of a GeoJSON
+    geometry object with coordinates.
+
+    Parameters
+    ----------
+    topology : TopoJSON
+        Topology object
+    object : Optional[Geometry]
+        Geometry
+    filter : Optional[GeoVar | Callable[[Geometry, Geometry], bool]]
+        Filter applied on the candidate arcs, see `mesh`.
+
+    Returns
+    -------
+    Geometry_MultiLineString
+        TopoJSON MultiLineString geometry object
+    """
+
+def borders(
+    topology: TopoJSON,
+    object: Geometry,
+    spherical: bool = False,
+) -> GeoJSON_FeatureCollection:
+    """
+    Returns the borders between the geometries of an object, e.g. to label
+    borders or compute border statistics. Each feature stitches the arcs
+    separating a pair of geometries, or the exterior arcs of a geometry, with
+    the properties `left_id` and `right_id`, the ids of the geometries on the
+    left and on the right of the line (their index without id, `None` on the
+    right for the exterior), and `length`, the length of the border. Arcs used
+    twice by the same geometry are not borders.
+
+    Parameters
+    ----------
+    topology : TopoJSON
+        Topology object
+    object : Geometry
+        Geometry, usually a `Geometry_GeometryCollection`
+    spherical : bool
+        Measures the borders on the sphere, in metres, for coordinates in
+        longitudes and latitudes.
+
+    Returns
+    -------
+    GeoJSON_FeatureCollection
+        Border lines with their left and right geometries
+    """
+
+def bbox(topology: TopoJSON, spherical: bool = False) -> list[float]:
     """
     Returns the computed bounding box of the specified topology $[x_0, y_0,
     x_1, y_1]$ where $x_0$ is the minimum x-value, $y_0$ is the minimum
@@ -875,6 +2044,10 @@ def bbox(topology: TopoJSON) -> list[float]:
     ----------
     topology : TopoJSON
         TopoJSON object
+    spherical : bool
+        Finds the smallest longitude interval covering the arcs and points, for
+        coordinates in longitudes and latitudes: the returned bounding box has
+        $x_0 > x_1$ when it crosses the antimeridian, as specified by RFC 7946.
 
     Returns
     -------
@@ -882,7 +2055,103 @@ def bbox(topology: TopoJSON) -> list[float]:
         Computed bounding box
     """
 
-def neighbors(objects: list[Geometry]) -> list[list[int]]:
+def index(topology: TopoJSON, object: Geometry) -> SpatialIndex:
+    """
+    Builds a spatial index over the polygons of the geometries of an object to
+    locate the geometry containing a position. Geometries without polygons are
+    never located.
+
+    Parameters
+    ----------
+    topology : TopoJSON
+        Topology object
+    object : Geometry
+        Geometry. The geometries of a `Geometry_GeometryCollection` are indexed
+        one by one, any other object is indexed as a single geometry.
+
+    Returns
+    -------
+    SpatialIndex
+        Reusable and picklable spatial index
+    """
+
+def measure(
+    topology: TopoJSON, object: Geometry, spherical: bool = False
+) -> list[Measure]:
+    """
+    Measures the area and perimeter of the polygons and the length of the lines
+    of each geometry of an object, directly from the arcs. The length of each
+    arc is computed once, even when it is shared by several geometries.
+
+    Parameters
+    ----------
+    topology : TopoJSON
+        Topology object
+    object : Geometry
+        Geometry. The geometries of a `Geometry_GeometryCollection` are measured
+        one by one, any other object is measured as a single geometry.
+    spherical : bool
+        Measures lengths in metres and areas in square metres on the sphere,
+        for longitude and latitude topologies. The area of a ring is the
+        smallest of the two areas it delimits, so a ring enclosing more than a
+        hemisphere is measured as its complement. Planar measures in the units
+        of the coordinates are used by default.
+
+    Returns
+    -------
+    list[Measure]
+        Measures of each geometry
+    """
+
+def nearest(
+    topology: TopoJSON,
+    object: Geometry,
+    x: float,
+    y: float,
+    k: int = 1,
+    spherical: bool = False,
+) -> list[tuple[int, Optional[str], float]]:
+    """
+    Finds the `k` geometries of an object nearest to a position. The distance
+    is measured to the boundary of polygons, to lines and to points, and each
+    shared arc is only measured once. The segment index is built on every call.
+
+    Parameters
+    ----------
+    topology : TopoJSON
+        Topology object
+    object : Geometry
+        Geometry. The geometries of a `Geometry_GeometryCollection` are searched
+        one by one, any other object is searched as a single geometry.
+    x : float
+        Horizontal coordinate (or longitude) of the position
+    y : float
+        Vertical coordinate (or latitude) of the position
+    k : int
+        Number of geometries to return
+    spherical : bool
+        Measures the distance along the sphere, in metres, instead of in planar
+        coordinates. Segments are great-circle arcs and the distance is
+        measured across the antimeridian.
+
+    Returns
+    -------
+    list[tuple[int, Optional[str], float]]
+        Index, id and distance of the nearest geometries, sorted by distance
+
+    Raises
+    ------
+    ValueError
+        When `k` is zero
+    """
+
+def neighbors(
+    objects: list[Geometry],
+    mode: str = "rook",
+    topology: Optional[TopoJSON] = None,
+    weights: Optional[str] = None,
+    spherical: bool = False,
+) -> list[list[int]] | WeightedNeighbors:
     """
     Returns an array representing the set of neighboring objects for each
     object in the specified objects array. The returned array has the same
@@ -898,24 +2167,209 @@ def neighbors(objects: list[Geometry]) -> list[list[int]]:
     ----------
     objects : list[Geometry]
         List of neighboring objects
+    mode : str
+        `"rook"` for objects sharing an arc or `"queen"` for objects sharing an
+        arc or touching at a node of the topology, i.e. an endpoint of an arc.
+        Points have no neighbors in `"rook"` mode and are neighbors of the
+        objects touching their position in `"queen"` mode.
+    topology : Optional[TopoJSON]
+        Topology of the objects, required in `"queen"` mode to locate the
+        nodes and with `weights` to measure the shared borders.
+    weights : Optional[str]
+        When specified, `"length"` or `"count"` to weight each pair of
+        neighbors with the length of their shared border or with the number of
+        arcs they share. Only the shared arcs are decoded. Neighbors only
+        touching at a node in `"queen"` mode have a weight of 0.
+    spherical : bool
+        Measures the shared borders on the sphere, in metres, for coordinates
+        in longitudes and latitudes.
 
     Returns
     -------
-    list[list[int]]
-        Neighboring objects
+    list[list[int]] | WeightedNeighbors
+        Neighboring objects, with their weights when `weights` is specified
 
     Raises
     ------
     KeyError
         When `key` is not found in `objects`
+    ValueError
+        Unknown mode or weights, or missing topology in `"queen"` mode or with
+        `weights`.
+    """
+
+def neighbors_between(
+    objects_a: list[Geometry],
+    objects_b: list[Geometry],
+    ids: bool = False,
+) -> list[list[int | str]]:
+    """
+    Returns, for each object of `objects_a`, the objects of `objects_b` sharing
+    an arc with it. Each array of neighbors is sorted by index in ascending
+    order.
+
+    Parameters
+    ----------
+    objects_a : list[Geometry]
+        List of objects whose neighbors are returned
+    objects_b : list[Geometry]
+        List of candidate neighboring objects
+    ids : bool
+        Identifies the objects of `objects_b` by their id instead of their
+        index. Objects without id keep their index.
+
+    Returns
+    -------
+    list[list[int | str]]
+        Neighbors in `objects_b` of each object of `objects_a`
+    """
+
+def adjacency(
+    topology: TopoJSON,
+    objects: list[Geometry],
+    format: str = "csr",
+    mode: str = "rook",
+    spherical: bool = False,
+) -> (
+    tuple[list[int], list[int], list[float]]
+    | list[tuple[str | int, str | int, float]]
+    | dict[str | int, dict[str | int, float]]
+):
+    """
+    Returns the adjacency of the geometries, weighted by the length of their
+    shared borders. Geometries are identified by their id or, without id, by
+    their index in `objects`.
+
+    Parameters
+    ----------
+    topology : TopoJSON
+        Topology object
+    objects : list[Geometry]
+        List of neighboring objects
+    format : str
+        `"csr"` for the arrays `(indptr, indices, data)` of a sparse matrix,
+        e.g. `scipy.sparse.csr_array((data, indices, indptr))`, `"edges"` for a
+        list of `(id_a, id_b, shared_length)` tuples listing each pair of
+        neighbors once, or `"dict"` for `{id_a: {id_b: shared_length}}`.
+    mode : str
+        `"rook"` or `"queen"` adjacency, see `neighbors`.
+    spherical : bool
+        Measures the shared borders on the sphere, in metres, for coordinates
+        in longitudes and latitudes.
+
+    Returns
+    -------
+    tuple[list[int], list[int], list[float]] | list[tuple[str | int, str | int, float]] | dict[str | int, dict[str | int, float]]
+        Adjacency in the requested format
+
+    Raises
+    ------
+    ValueError
+        Unknown format or mode.
+    """
+
+def color(
+    objects: list[Geometry],
+    max_colors: int = 4,
+    method: str = "dsatur",
+) -> list[int]:
+    """
+    Colors the objects so that objects sharing an arc have different colors,
+    e.g. for choropleth styling. The coloring is deterministic: ties are broken
+    by the order of the objects.
+
+    Parameters
+    ----------
+    objects : list[Geometry]
+        List of objects to color
+    max_colors : int
+        Maximum number of colors, between 1 and 64. Four colors suffice for most
+        maps with the `"dsatur"` method.
+    method : str
+        `"greedy"` colors the objects in order with the first color not used by
+        their neighbors, `"dsatur"` colors first the objects whose neighbors use
+        the most distinct colors.
+
+    Returns
+    -------
+    list[int]
+        Color index of each object, from `0` to `max_colors - 1`
+
+    Raises
+    ------
+    ValueError
+        Unknown method or the objects cannot be colored with `max_colors`
+        colors.
+    """
+
+def object_bbox(
+    topology: TopoJSON,
+    object: Geometry,
+    spherical: bool = False,
+) -> list[float]:
+    """
+    Returns the computed bounding box $[x_0, y_0, x_1, y_1]$ of an object,
+    decoding only the arcs it references. If the object has no points and no
+    arcs, the returned bounding box is
+    $[\\infty, \\infty, -\\infty, -\\infty]$.
+
+    Parameters
+    ----------
+    topology : TopoJSON
+        Topology object
+    object : Geometry
+        Geometry
+    spherical : bool
+        Finds the smallest longitude interval covering the object, see `bbox`.
+
+    Returns
+    -------
+    list[float]
+        Computed bounding box
+    """
+
+def compute_geometry_bboxes(
+    topology: TopoJSON,
+    object: Geometry,
+    spherical: bool = False,
+) -> Geometry:
+    """
+    Returns a copy of an object with the `bbox` of the object and of all its
+    geometries filled. Only the arcs referenced by the object are decoded, once
+    each. Geometries without points and arcs get no bounding box.
+
+    Parameters
+    ----------
+    topology : TopoJSON
+        Topology object
+    object : Geometry
+        Geometry
+    spherical : bool
+        Finds the smallest longitude interval covering each geometry, see
+        `bbox`.
+
+    Returns
+    -------
+    Geometry
+        Copy of the object with bounding boxes
     """
 
-def quantize(topology: TopoJSON, transform: float) -> TopoJSON:
+def quantize(
+    topology: TopoJSON,
+    transform: Optional[float] = None,
+    max_error: Optional[float] = None,
+    spherical: bool = False,
+) -> TopoJSON:
     """
     Returns a shallow copy of the specified topology with quantized and
     delta-encoded arcs according to the specified transform object. If the
-    topology is already quantized, an error is thrown. See also
-    topoquantize.
+    topology is already quantized, its arcs and points are first decoded with
+    the existing transform and then encoded again with the new one, which
+    allows to reduce the precision of a topology without its original
+    coordinates. Consecutive duplicate positions are collapsed, so a degenerate
+    arc keeps its first position followed by a zero delta `[0, 0]`, as in
+    topojson-client; arc indexes are left unchanged so that shared arcs remain
+    shared. See also topoquantize.
 
     The corresponding transform object is first computed using the bounding
     box of the topology. The quantization number `transform` must be a
@@ -925,21 +2379,120 @@ def quantize(topology: TopoJSON, transform: float) -> TopoJSON:
     1e6. If the topology does not already have a topology.bbox, one is
     computed using topojson.bbox.
 
+    Instead of a quantization number, a maximum error can be specified: a
+    quantization number whose maximum displacement (see
+    `topojson.quantization_error`) does not exceed `max_error` is then used.
+    It is searched by bisection below the first power of two that satisfies
+    `max_error`; since the displacement does not always decrease as the
+    quantization number grows, it is not guaranteed to be the smallest one.
 
     Parameters
     ----------
     topology : TopoJSON
         TopoJSON object to quantize
-    transform : float
+    transform : Optional[float]
         The quantization number `transform`.
+    max_error : Optional[float]
+        The maximum displacement of positions allowed, in the units of the
+        input coordinates.
+    spherical : bool
+        Uses the antimeridian-aware bounding box of the topology, see `bbox`,
+        instead of topology.bbox. When it crosses the antimeridian, the
+        longitudes smaller than $x_0$ are shifted by 360° so that they are
+        contiguous: decoded longitudes past the antimeridian then exceed 180°.
 
     Returns
     -------
     TopoJSON
         Shallow copy with quantized and delta-encoded arcs.
 
+    Raises
+    ------
+    TypeError
+        If both or none of `transform` and `max_error` are specified.
+    RuntimeError
+        If transform is smaller than 2, if `max_error` is negative or if no
+        quantization number satisfies `max_error`.
+    """
+
+def quantization_error(
+    topology: TopoJSON,
+    transform: float,
+    spherical: bool = False,
+) -> QuantizationReport:
+    """
+    Reports the displacement of positions that quantizing the topology with the
+    quantization number `transform` would cause, without quantizing it. Arcs
+    and points are compared to their quantized and decoded positions.
+
+    Parameters
+    ----------
+    topology : TopoJSON
+        TopoJSON object
+    transform : float
+        The quantization number `transform`.
+    spherical : bool
+        Uses the antimeridian-aware bounding box, see `quantize`.
+
+    Returns
+    -------
+    QuantizationReport
+        Maximum and mean displacements and number of collapsed arcs.
+
+    Raises
+    ------
+    RuntimeError
+        If transform is smaller than 2.
+    """
+
+def simplify_dp(topology: TopoJSON, tolerance: float) -> TopoJSON:
+    """
+    Returns a shallow copy of the topology where each arc is simplified
+    independently with the Douglas-Peucker algorithm. Since arcs are shared
+    between objects, borders shared by adjacent geometries remain identical.
+    Arc endpoints are always kept and rings of polygons never collapse below 4
+    points.
+
+    Quantized arcs are simplified using their decoded coordinates and the kept
+    points are delta-encoded again.
+
+    Parameters
+    ----------
+    topology : TopoJSON
+        TopoJSON object to simplify
+    tolerance : float
+        Distance tolerance in map units (the units of decoded coordinates). A
+        point is removed if its distance to the simplified line is not greater
+        than the tolerance.
+
+    Returns
+    -------
+    TopoJSON
+        Shallow copy with simplified arcs.
+
     Raises
     ------
     RuntimeError
-        If topology is already quantized or transform is smaller than 2.
+        If tolerance is negative or not a number.
+    """
+
+def unquantize(topology: TopoJSON) -> TopoJSON:
+    """
+    Returns a shallow copy of the specified topology with absolute coordinates.
+    Delta-encoded arcs are decoded with the transform of the topology, as well
+    as the coordinates of point geometries. The returned topology has no
+    transform: its coordinates can be edited or reprojected and quantized again
+    with `topojson.quantize`.
+
+    If the topology is not quantized, an identical copy is returned.
+
+    Parameters
+    ----------
+    topology : TopoJSON
+        TopoJSON object to unquantize
+
+    Returns
+    -------
+    TopoJSON
+        Shallow copy with absolute coordinates.
     """